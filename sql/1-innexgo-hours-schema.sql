@@ -99,6 +99,68 @@ create view recent_school_duration_data_v as
   on maxids.id = sdd.school_duration_data_id;
 
 
+-- an academic term (semester, quarter, etc) that courses may be assigned to
+drop table if exists term_t cascade;
+create table term_t(
+  term_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  school_id bigint not null references school_t(school_id)
+);
+
+drop table if exists term_data_t cascade;
+create table term_data_t(
+  term_data_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  term_id bigint not null references term_t(term_id),
+  name text not null,
+  start_time bigint not null,
+  end_time bigint not null,
+  active bool not null
+);
+
+create view recent_term_data_v as
+  select td.* from term_data_t td
+  inner join (
+   select max(term_data_id) id 
+   from term_data_t 
+   group by term_id
+  ) maxids
+  on maxids.id = td.term_data_id;
+
+-- a span of time the school is closed (holidays, snow days, testing days)
+-- no sessions may be held or requested during an active closure
+drop table if exists closure_t cascade;
+create table closure_t(
+  closure_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  school_id bigint not null references school_t(school_id)
+);
+
+drop table if exists closure_data_t cascade;
+create table closure_data_t(
+  closure_data_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  closure_id bigint not null references closure_t(closure_id),
+  name text not null,
+  start_time bigint not null,
+  end_time bigint not null,
+  active bool not null
+);
+
+create view recent_closure_data_v as
+  select cld.* from closure_data_t cld
+  inner join (
+   select max(closure_data_id) id 
+   from closure_data_t 
+   group by closure_id
+  ) maxids
+  on maxids.id = cld.closure_data_id;
+
+
 drop table if exists school_key_t cascade;
 create table school_key_t(
   school_key_key text primary key,
@@ -106,7 +168,8 @@ create table school_key_t(
   creator_user_id bigint not null,
   school_id bigint not null references school_t(school_id),
   start_time bigint not null,
-  end_time bigint not null
+  end_time bigint not null,
+  short_code text unique -- NULLABLE (human typeable alias for the key)
 );
 
 drop table if exists school_key_data_t cascade;
@@ -127,6 +190,18 @@ create view recent_school_key_data_v as
   ) maxids
   on maxids.id = skd.school_key_data_id;
 
+-- an invitation for a specific email address to become an admin of a school
+-- it may only be redeemed by the auth user who owns that email
+drop table if exists school_invitation_t cascade;
+create table school_invitation_t(
+  school_invitation_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  school_id bigint not null references school_t(school_id),
+  email text not null,
+  end_time bigint not null
+);
+
 drop table if exists adminship_t cascade;
 create table adminship_t(
   adminship_id bigserial primary key,
@@ -135,7 +210,8 @@ create table adminship_t(
   user_id bigint not null,
   school_id bigint not null references school_t(school_id),
   adminship_kind bigint not null, -- ADMIN, CANCEL
-  school_key_key text -- NULLABLE (not all adminships are from keys
+  school_key_key text, -- NULLABLE (not all adminships are from keys
+  school_invitation_id bigint references school_invitation_t(school_invitation_id) -- NULLABLE
 );
 
 create view recent_adminship_v as
@@ -147,6 +223,37 @@ create view recent_adminship_v as
   ) maxids
   on maxids.id = a.adminship_id;
 
+-- links a guardian (parent) to a student, letting the guardian read the student's records
+-- REQUESTED: the guardian asked for access and the student hasn't approved yet
+-- ACTIVE: approved by the student, or issued by an admin of one of the student's schools
+-- CANCEL: revoked by the guardian, the student, or an admin
+drop table if exists guardianship_t cascade;
+create table guardianship_t(
+  guardianship_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  guardian_user_id bigint not null,
+  student_user_id bigint not null,
+  guardianship_kind bigint not null, -- REQUESTED | ACTIVE | CANCEL
+  weekly_summary bool not null
+);
+
+create view recent_guardianship_v as
+  select g.* from guardianship_t g
+  inner join (
+   select max(guardianship_id) id 
+   from guardianship_t
+   group by guardian_user_id, student_user_id
+  ) maxids
+  on maxids.id = g.guardianship_id;
+
+-- each time the weekly guardian summaries went out, so that restarts don't skip or repeat a week
+drop table if exists guardianship_summary_run_t cascade;
+create table guardianship_summary_run_t(
+  guardianship_summary_run_id bigserial primary key,
+  creation_time bigint not null
+);
+
 -- represents a named location
 -- locations must be physical, 
 drop table if exists location_t cascade;
@@ -179,6 +286,38 @@ create view recent_location_data_v as
   ) maxids
   on maxids.id = ld.location_data_id;
 
+-- lets students sign themselves in at a location by typing a rotating code
+-- the secret is never sent to clients, only codes derived from it
+drop table if exists location_checkin_t cascade;
+create table location_checkin_t(
+  location_checkin_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  location_id bigint not null references location_t(location_id),
+  secret text not null,
+  active bool not null
+);
+
+create view recent_location_checkin_v as
+  select lc.* from location_checkin_t lc
+  inner join (
+   select max(location_checkin_id) id 
+   from location_checkin_t 
+   group by location_id
+  ) maxids
+  on maxids.id = lc.location_checkin_id;
+
+-- every self check in attempt, successful or not, so that we can rate limit guessing
+drop table if exists checkin_attempt_t cascade;
+create table checkin_attempt_t(
+  checkin_attempt_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  location_id bigint not null references location_t(location_id),
+  success bool not null,
+  code_period bigint -- NULLABLE (the period of the code that was used, only for successful attempts)
+);
+
 -- a course run by a teacher. represents one class offering
 drop table if exists course_t cascade;
 create table course_t(
@@ -196,6 +335,7 @@ create table course_data_t(
   creator_user_id bigint not null,
   course_id bigint not null references course_t(course_id),
   location_id bigint not null references location_t(location_id),
+  term_id bigint references term_t(term_id), -- NULLABLE (the term the course runs in, if it has one)
   name text not null,
   description text not null,
   homeroom bool not null, 
@@ -220,7 +360,8 @@ create table course_key_t(
   max_uses bigint not null,
   course_membership_kind bigint not null,
   start_time bigint not null,
-  end_time bigint not null
+  end_time bigint not null,
+  short_code text unique -- NULLABLE (human typeable alias for the key)
 );
 
 drop table if exists course_key_data_t cascade;
@@ -242,6 +383,19 @@ create view recent_course_key_data_v as
   on maxids.id = ckd.course_key_data_id;
 
 
+-- an invitation for a specific email address to join a course
+-- it may only be redeemed by the auth user who owns that email
+drop table if exists course_invitation_t cascade;
+create table course_invitation_t(
+  course_invitation_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  course_id bigint not null references course_t(course_id),
+  email text not null,
+  course_membership_kind bigint not null,
+  end_time bigint not null
+);
+
 -- Many to Many mapper for users to course
 drop table if exists course_membership_t cascade;
 create table course_membership_t(
@@ -250,8 +404,9 @@ create table course_membership_t(
   creator_user_id bigint not null,
   user_id bigint not null,
   course_id bigint not null references course_t(course_id),
-  course_membership_kind bigint not null, -- STUDENT | INSTRUCTOR | CANCEL
-  course_key_key text references course_key_t(course_key_key) -- NULLABLE
+  course_membership_kind bigint not null, -- STUDENT | INSTRUCTOR | TEACHING_ASSISTANT | OBSERVER | CANCEL
+  course_key_key text references course_key_t(course_key_key), -- NULLABLE
+  course_invitation_id bigint references course_invitation_t(course_invitation_id) -- NULLABLE
 );
 
 create view recent_course_membership_v as
@@ -338,15 +493,194 @@ create table session_request_response_t(
   commitment_id bigint references commitment_t(commitment_id) -- NULLABLE
 );
 
+-- an agenda or summary attached to a session
+drop table if exists session_note_t cascade;
+create table session_note_t(
+  session_note_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  session_id bigint not null references session_t(session_id)
+);
+
+drop table if exists session_note_data_t cascade;
+create table session_note_data_t(
+  session_note_data_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  session_note_id bigint not null references session_note_t(session_note_id),
+  session_note_kind bigint not null, -- AGENDA | SUMMARY
+  session_note_visibility bigint not null, -- ATTENDEES | INSTRUCTORS
+  content text not null,
+  active bool not null
+);
+
+create view recent_session_note_data_v as
+  select snd.* from session_note_data_t snd
+  inner join (
+   select max(session_note_data_id) id 
+   from session_note_data_t 
+   group by session_note_id
+  ) maxids
+  on maxids.id = snd.session_note_data_id;
+
+-- a cancellation or reschedule of a session, kept so attendees can be told what changed and why
+drop table if exists session_change_t cascade;
+create table session_change_t(
+  session_change_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  session_id bigint not null references session_t(session_id),
+  session_change_kind bigint not null, -- CANCEL | RESCHEDULE
+  reason text not null,
+  old_start_time bigint not null,
+  old_end_time bigint not null,
+  new_start_time bigint, -- NULLABLE (only for RESCHEDULE)
+  new_end_time bigint -- NULLABLE (only for RESCHEDULE)
+);
+
+-- a file attached to either a session request or a session
+-- the contents live in the blob store under the checksum, so identical files are only stored once
+drop table if exists attachment_t cascade;
+create table attachment_t(
+  attachment_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  session_request_id bigint references session_request_t(session_request_id), -- NULLABLE
+  session_id bigint references session_t(session_id), -- NULLABLE
+  name text not null,
+  media_type text not null,
+  size bigint not null,
+  checksum text not null,
+  check (num_nulls(session_request_id, session_id) = 1)
+);
+
+-- the link to a virtual meeting held at a location or for a session
+-- kept apart from location data, which anyone can read, and only handed out through meeting passes
+drop table if exists meeting_link_t cascade;
+create table meeting_link_t(
+  meeting_link_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  location_id bigint references location_t(location_id), -- NULLABLE
+  session_id bigint references session_t(session_id), -- NULLABLE
+  url text not null,
+  active bool not null,
+  check (num_nulls(location_id, session_id) = 1)
+);
+
+create view recent_meeting_link_v as
+  select ml.* from meeting_link_t ml
+  inner join (
+   select max(meeting_link_id) id 
+   from meeting_link_t 
+   group by location_id, session_id
+  ) maxids
+  on maxids.id = ml.meeting_link_id;
+
+-- a short lived, one time pass to a session's meeting, issued to someone entitled to join it
+drop table if exists meeting_pass_t cascade;
+create table meeting_pass_t(
+  meeting_pass_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  session_id bigint not null references session_t(session_id),
+  meeting_link_id bigint not null references meeting_link_t(meeting_link_id),
+  meeting_pass_token text not null unique,
+  expiration_time bigint not null
+);
+
+-- following a pass to the meeting, which is how virtual attendance is counted
+drop table if exists meeting_join_t cascade;
+create table meeting_join_t(
+  meeting_pass_id bigint primary key references meeting_pass_t(meeting_pass_id),
+  creation_time bigint not null
+);
+
+-- an attendee's rating of a session they went to, at most one per attendee and session
+drop table if exists feedback_t cascade;
+create table feedback_t(
+  feedback_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  commitment_id bigint not null unique references commitment_t(commitment_id)
+);
+
+drop table if exists feedback_data_t cascade;
+create table feedback_data_t(
+  feedback_data_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  feedback_id bigint not null references feedback_t(feedback_id),
+  rating bigint not null, -- 1 to 5
+  comment text not null, -- empty if none was left
+  active bool not null
+);
+
+create view recent_feedback_data_v as
+  select fd.* from feedback_data_t fd
+  inner join (
+   select max(feedback_data_id) id 
+   from feedback_data_t 
+   group by feedback_id
+  ) maxids
+  on maxids.id = fd.feedback_data_id;
+
+-- a block of time an instructor is available for a course, split into slots that students can book
+drop table if exists availability_t cascade;
+create table availability_t(
+  availability_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  course_id bigint not null references course_t(course_id)
+);
+
+drop table if exists availability_data_t cascade;
+create table availability_data_t(
+  availability_data_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  availability_id bigint not null references availability_t(availability_id),
+  name text not null,
+  start_time bigint not null,
+  end_time bigint not null,
+  slot_duration bigint not null,
+  active bool not null
+);
+
+create view recent_availability_data_v as
+  select ad.* from availability_data_t ad
+  inner join (
+   select max(availability_data_id) id 
+   from availability_data_t 
+   group by availability_id
+  ) maxids
+  on maxids.id = ad.availability_data_id;
+
+-- a slot that a student booked, and the commitment (to a session made just for it) that holds it
+-- the slot is free again once the session is cancelled or the commitment is withdrawn
+drop table if exists availability_booking_t cascade;
+create table availability_booking_t(
+  availability_booking_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  availability_id bigint not null references availability_t(availability_id),
+  start_time bigint not null,
+  commitment_id bigint not null unique references commitment_t(commitment_id)
+);
+
 
 drop table if exists encounter_t cascade;
+-- creation_time is when the encounter happened, which for offline kiosks may be well before received_time
 create table encounter_t(
   encounter_id bigserial primary key,
   creation_time bigint not null,
   creator_user_id bigint not null,
   location_id bigint not null references location_t(location_id),
   attendee_user_id bigint not null,
-  encounter_kind bigint not null -- HARDWARE | MANUAL
+  encounter_kind bigint not null, -- HARDWARE | MANUAL | SELF_CHECKIN
+  received_time bigint not null,
+  idempotency_key text, -- NULLABLE (client generated, used to deduplicate kiosk uploads)
+  unique (creator_user_id, idempotency_key)
 );
 
 -- represents a stay at a location where both the sign in and out were recorded
@@ -357,7 +691,8 @@ create table stay_t(
   creation_time bigint not null,
   creator_user_id bigint not null,
   attendee_user_id bigint not null,
-  location_id bigint not null references location_t(location_id)
+  location_id bigint not null references location_t(location_id),
+  session_id bigint references session_t(session_id) -- NULLABLE (set when recorded on a session's attendance sheet)
 );
 
 -- the time variants are used when the teacher edits the stay data
@@ -368,6 +703,7 @@ create table stay_data_t(
   stay_data_id bigserial primary key,
   creation_time bigint not null,
   creator_user_id bigint not null,
+  stay_id bigint not null references stay_t(stay_id),
   fst_encounter_id bigint references encounter_t(encounter_id), -- NULLABLE
   fst_time bigint, -- NULLABLE
   snd_encounter_id bigint references encounter_t(encounter_id), -- NULLABLE
@@ -377,6 +713,37 @@ create table stay_data_t(
   active bool not null
 );
 
+create view recent_stay_data_v as
+  select syd.* from stay_data_t syd
+  inner join (
+   select max(stay_data_id) id 
+   from stay_data_t 
+   group by stay_id
+  ) maxids
+  on maxids.id = syd.stay_data_id;
+
+-- a student sent from one location to another, expected back by expected_return_time
+-- the pass is considered returned once the student is seen at the origin again
+drop table if exists hall_pass_t cascade;
+create table hall_pass_t(
+  hall_pass_id bigserial primary key,
+  creation_time bigint not null,
+  creator_user_id bigint not null,
+  attendee_user_id bigint not null,
+  origin_location_id bigint not null references location_t(location_id),
+  destination_location_id bigint not null references location_t(location_id),
+  destination_stay_id bigint not null references stay_t(stay_id),
+  hall_pass_reason_kind bigint not null, -- RESTROOM | NURSE | LIBRARY | OFFICE | COUNSELOR | OTHER
+  expected_return_time bigint not null
+);
+
+-- each time overdue hall passes were looked for, so that passes coming due while the server is down still alert
+drop table if exists hall_pass_check_t cascade;
+create table hall_pass_check_t(
+  hall_pass_check_id bigserial primary key,
+  creation_time bigint not null
+);
+
 -- represents period of time when the student wasn't present but should have been
 -- these are cateogrized into types:
 -- linked to an attendance revision
@@ -384,11 +751,24 @@ create table stay_data_t(
 -- TARDY: user arrived late (start time defined)
 -- LEAVE_NORETURN: user left before session ended (end time defined)
 -- LEAVE_RETURN: user left and then came back in the middle of a session (start and end time defined)
+-- the most recent one for a commitment is authoritative, an inactive one clears it
 drop table if exists irregularity_t cascade;
 create table irregularity_t(
   irregularity_id bigserial primary key,
   creation_time bigint not null,
+  creator_user_id bigint not null,
   commitment_id bigint not null references commitment_t(commitment_id),
-  fst_encounter_id bigint not null references encounter_t(encounter_id),
-  snd_encounter_id bigint not null references encounter_t(encounter_id)
+  irregularity_kind bigint not null, -- ABSENT | TARDY | LEAVE_NORETURN | LEAVE_RETURN
+  start_time bigint, -- NULLABLE
+  end_time bigint, -- NULLABLE
+  active bool not null
 );
+
+create view recent_irregularity_v as
+  select i.* from irregularity_t i
+  inner join (
+   select max(irregularity_id) id 
+   from irregularity_t 
+   group by commitment_id
+  ) maxids
+  on maxids.id = i.irregularity_id;
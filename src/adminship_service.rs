@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use innexgo_hours_api::request;
 use std::convert::TryInto;
@@ -17,6 +18,7 @@ impl From<tokio_postgres::row::Row> for Adminship {
         .try_into()
         .unwrap(),
       school_key_key: row.get("school_key_key"),
+      school_invitation_id: row.get("school_invitation_id"),
     }
   }
 }
@@ -28,6 +30,7 @@ pub async fn add(
   school_id: i64,
   adminship_kind: request::AdminshipKind,
   school_key_key: Option<String>,
+  school_invitation_id: Option<i64>,
 ) -> Result<Adminship, tokio_postgres::Error> {
   let creation_time = current_time_millis();
 
@@ -40,9 +43,10 @@ pub async fn add(
            user_id,
            school_id,
            adminship_kind,
-           school_key_key
+           school_key_key,
+           school_invitation_id
        )
-       VALUES ($1, $2, $3, $4, $5, $6)
+       VALUES ($1, $2, $3, $4, $5, $6, $7)
        RETURNING adminship_id
       ",
       &[
@@ -52,6 +56,7 @@ pub async fn add(
         &school_id,
         &(adminship_kind.clone() as i64),
         &school_key_key,
+        &school_invitation_id,
       ],
     )
     .await?
@@ -66,6 +71,7 @@ pub async fn add(
     school_id,
     adminship_kind,
     school_key_key,
+    school_invitation_id,
   })
 }
 
@@ -178,6 +184,30 @@ pub async fn count_school_key_uses(
   Ok(get_by_school_key_key(con, school_key_key).await?.len() as i64)
 }
 
+pub async fn get_by_school_invitation_id(
+  con: &mut impl GenericClient,
+  school_invitation_id: i64,
+) -> Result<Vec<Adminship>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "SELECT * FROM adminship_t WHERE school_invitation_id = $1",
+      &[&school_invitation_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn count_school_invitation_uses(
+  con: &mut impl GenericClient,
+  school_invitation_id: i64,
+) -> Result<i64, tokio_postgres::Error> {
+  Ok(get_by_school_invitation_id(con, school_invitation_id).await?.len() as i64)
+}
+
 pub async fn get_by_school_id(
   con: &mut impl GenericClient,
   school_id: i64,
@@ -225,11 +255,15 @@ pub async fn query(
   props: innexgo_hours_api::request::AdminshipViewProps,
 ) -> Result<Vec<Adminship>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT a.* FROM recent_adminship_v a"
-    } else {
-      "SELECT a.* FROM adminship_t a"
-    },
+    utils::select_versions(
+      "adminship_t",
+      "a",
+      "adminship_id",
+      "user_id, school_id",
+      props.only_recent,
+      10,
+    )
+    .as_str(),
     " LEFT JOIN school_key_t sk ON a.school_key_key = sk.school_key_key",
     " WHERE 1 = 1",
     " AND ($1::bigint[] IS NULL OR a.adminship_id = ANY($1))",
@@ -241,6 +275,7 @@ pub async fn query(
     " AND ($7::bigint[] IS NULL OR a.adminship_kind = ANY($7))",
     " AND ($8::bool     IS NULL OR a.school_key_key IS NOT NULL = $8)",
     " AND ($9::text[]   IS NULL OR sk.school_key_key = ANY($9))",
+    " AND ($10::bigint  IS NULL OR a.creation_time <= $10)",
     " ORDER BY a.adminship_id",
   ]
   .join("\n");
@@ -262,6 +297,7 @@ pub async fn query(
           .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
         &props.adminship_has_source,
         &props.school_key_key,
+        &props.as_of,
       ],
     )
     .await?
@@ -1,9 +1,10 @@
+use super::auth::AuthService;
 use super::handlers;
 use super::utils;
 use super::Config;
 use super::Db;
 use super::SERVICE_NAME;
-use auth_service_api::client::AuthService;
+use futures_util::Stream;
 use innexgo_hours_api::response::InnexgoHoursError;
 use std::collections::HashMap;
 use std::convert::Infallible;
@@ -66,6 +67,20 @@ pub fn api(
         warp::path!("public" / "location_data" / "new"),
         handlers::location_data_new,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "location_checkin" / "new"),
+        handlers::location_checkin_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "location_checkin" / "code"),
+        handlers::location_checkin_code,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -80,6 +95,13 @@ pub fn api(
         warp::path!("public" / "course_key_data" / "new"),
         handlers::course_key_data_new,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "course_key" / "link"),
+        handlers::course_key_link,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -87,6 +109,20 @@ pub fn api(
         warp::path!("public" / "course_membership" / "new_key"),
         handlers::course_membership_new_key,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "course_invitation" / "new"),
+        handlers::course_invitation_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "course_membership" / "new_invitation"),
+        handlers::course_membership_new_invitation,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -122,6 +158,41 @@ pub fn api(
         warp::path!("public" / "school_duration_data" / "new"),
         handlers::school_duration_data_new,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "term" / "new"),
+        handlers::term_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "term_data" / "new"),
+        handlers::term_data_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "term" / "rollover"),
+        handlers::term_rollover,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "closure" / "new"),
+        handlers::closure_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "closure_data" / "new"),
+        handlers::closure_data_new,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -136,6 +207,13 @@ pub fn api(
         warp::path!("public" / "school_key_data" / "new"),
         handlers::school_key_data_new,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "school_key" / "link"),
+        handlers::school_key_link,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -143,6 +221,20 @@ pub fn api(
         warp::path!("public" / "adminship" / "new_key"),
         handlers::adminship_new_key,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "school_invitation" / "new"),
+        handlers::school_invitation_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "adminship" / "new_invitation"),
+        handlers::adminship_new_invitation,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -150,6 +242,34 @@ pub fn api(
         warp::path!("public" / "adminship" / "new_cancel"),
         handlers::adminship_new_cancel,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "guardianship" / "new_request"),
+        handlers::guardianship_new_request,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "guardianship" / "new_approve"),
+        handlers::guardianship_new_approve,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "guardianship" / "new_admin"),
+        handlers::guardianship_new_admin,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "guardianship" / "new_cancel"),
+        handlers::guardianship_new_cancel,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -182,8 +302,87 @@ pub fn api(
         config.clone(),
         db.clone(),
         auth_service.clone(),
-        warp::path!("public" / "commitment" / "new"),
-        handlers::commitment_new,
+        warp::path!("public" / "session" / "cancel"),
+        handlers::session_cancel,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session" / "reschedule"),
+        handlers::session_reschedule,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_note" / "new"),
+        handlers::session_note_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_note_data" / "new"),
+        handlers::session_note_data_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "feedback" / "new"),
+        handlers::feedback_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "feedback_data" / "new"),
+        handlers::feedback_data_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "meeting_link" / "new"),
+        handlers::meeting_link_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "meeting_pass" / "new"),
+        handlers::meeting_pass_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "attachment" / "new").and(warp::body::content_length_limit(
+          handlers::ATTACHMENT_NEW_BODY_LIMIT
+        )),
+        handlers::attachment_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability" / "new"),
+        handlers::availability_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability_data" / "new"),
+        handlers::availability_data_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability_booking" / "new"),
+        handlers::availability_booking_new,
       ),
       adapter(
         config.clone(),
@@ -192,6 +391,27 @@ pub fn api(
         warp::path!("public" / "commitment" / "new"),
         handlers::commitment_new,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "encounter" / "new"),
+        handlers::encounter_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "encounter" / "new_checkin"),
+        handlers::encounter_new_checkin,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "encounter" / "new_batch"),
+        handlers::encounter_new_batch,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -206,6 +426,27 @@ pub fn api(
         warp::path!("public" / "stay_data" / "new"),
         handlers::stay_data_new,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "attendance_sheet" / "new"),
+        handlers::attendance_sheet_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "hall_pass" / "new"),
+        handlers::hall_pass_new,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "hall_pass" / "new_return"),
+        handlers::hall_pass_new_return,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -227,6 +468,13 @@ pub fn api(
         warp::path!("public" / "school_data" / "view"),
         handlers::school_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "school_data" / "history"),
+        handlers::school_data_history,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -241,6 +489,55 @@ pub fn api(
         warp::path!("public" / "school_duration_data" / "view"),
         handlers::school_duration_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "school_duration_data" / "history"),
+        handlers::school_duration_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "term" / "view"),
+        handlers::term_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "term_data" / "view"),
+        handlers::term_data_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "term_data" / "history"),
+        handlers::term_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "closure" / "view"),
+        handlers::closure_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "closure_data" / "view"),
+        handlers::closure_data_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "closure_data" / "history"),
+        handlers::closure_data_history,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -255,6 +552,13 @@ pub fn api(
         warp::path!("public" / "school_key_data" / "view"),
         handlers::school_key_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "school_key_data" / "history"),
+        handlers::school_key_data_history,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -269,6 +573,13 @@ pub fn api(
         warp::path!("public" / "course_data" / "view"),
         handlers::course_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "course_data" / "history"),
+        handlers::course_data_history,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -283,6 +594,13 @@ pub fn api(
         warp::path!("public" / "location_data" / "view"),
         handlers::location_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "location_data" / "history"),
+        handlers::location_data_history,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -297,6 +615,55 @@ pub fn api(
         warp::path!("public" / "stay_data" / "view"),
         handlers::stay_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "stay_data" / "history"),
+        handlers::stay_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "attendance_sheet" / "view"),
+        handlers::attendance_sheet_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "irregularity" / "view"),
+        handlers::irregularity_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "homeroom_attendance" / "view"),
+        handlers::homeroom_attendance_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "hall_pass" / "view"),
+        handlers::hall_pass_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "occupancy" / "view_location"),
+        handlers::occupancy_view_location,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "occupancy" / "view_school"),
+        handlers::occupancy_view_school,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -318,6 +685,132 @@ pub fn api(
         warp::path!("public" / "session_data" / "view"),
         handlers::session_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_data" / "history"),
+        handlers::session_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_change" / "view"),
+        handlers::session_change_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_note" / "view"),
+        handlers::session_note_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_note_data" / "view"),
+        handlers::session_note_data_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "session_note_data" / "history"),
+        handlers::session_note_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "meeting_link" / "view"),
+        handlers::meeting_link_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "meeting_join" / "view"),
+        handlers::meeting_join_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "feedback_data" / "view"),
+        handlers::feedback_data_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "feedback_data" / "history"),
+        handlers::feedback_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "feedback_summary" / "view"),
+        handlers::feedback_summary_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "attachment" / "view"),
+        handlers::attachment_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "attachment" / "download"),
+        handlers::attachment_download,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability" / "view"),
+        handlers::availability_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability_data" / "view"),
+        handlers::availability_data_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability_data" / "history"),
+        handlers::availability_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability_slot" / "view"),
+        handlers::availability_slot_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "free_time" / "view"),
+        handlers::free_time_view,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "availability_booking" / "view"),
+        handlers::availability_booking_view,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -346,6 +839,20 @@ pub fn api(
         warp::path!("public" / "course_key_data" / "view"),
         handlers::course_key_data_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "course_key_data" / "history"),
+        handlers::course_key_data_history,
+      ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "course_invitation" / "view"),
+        handlers::course_invitation_view,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -353,6 +860,13 @@ pub fn api(
         warp::path!("public" / "course_membership" / "view"),
         handlers::course_membership_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "school_invitation" / "view"),
+        handlers::school_invitation_view,
+      ),
       adapter(
         config.clone(),
         db.clone(),
@@ -360,12 +874,32 @@ pub fn api(
         warp::path!("public" / "adminship" / "view"),
         handlers::adminship_view,
       ),
+      adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "guardianship" / "view"),
+        handlers::guardianship_view,
+      ),
       adapter(
         config.clone(),
         db.clone(),
         auth_service.clone(),
         warp::path!("public" / "commitment" / "view"),
         handlers::commitment_view,
+      ),
+      sse_adapter(
+        config.clone(),
+        db.clone(),
+        auth_service.clone(),
+        warp::path!("public" / "event" / "subscribe"),
+        handlers::event_subscribe,
+      ),
+      redirect_adapter(
+        config.clone(),
+        db.clone(),
+        warp::path!("public" / "meeting_pass" / "redeem" / String),
+        handlers::meeting_pass_redeem,
       )
     ))
     .recover(handle_rejection)
@@ -423,6 +957,12 @@ async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, Infa
   {
     message = InnexgoHoursError::DecodeError;
     code = StatusCode::BAD_REQUEST;
+  } else if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+    code = StatusCode::PAYLOAD_TOO_LARGE;
+    message = InnexgoHoursError::AttachmentTooLarge;
+  } else if err.find::<warp::reject::LengthRequired>().is_some() {
+    code = StatusCode::LENGTH_REQUIRED;
+    message = InnexgoHoursError::DecodeError;
   } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
     code = StatusCode::METHOD_NOT_ALLOWED;
     message = InnexgoHoursError::MethodNotAllowed;
@@ -443,6 +983,101 @@ async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, Infa
   Ok(warp::reply::with_status(warp::reply::json(&message), code))
 }
 
+// this function adapts a streaming handler function to a warp filter
+// browsers can't attach a body to an EventSource, so props come from the query string (see sse_props)
+fn sse_adapter<PropsType, StreamType, F>(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone,
+  handler: fn(Config, Db, AuthService, PropsType) -> F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+  F: Future<Output = Result<StreamType, InnexgoHoursError>> + Send,
+  PropsType: Send + serde::de::DeserializeOwned + 'static,
+  StreamType: Stream<Item = Result<warp::sse::Event, Infallible>> + Send + 'static,
+{
+  // lets you pass in an arbitrary parameter
+  fn with<T: Clone + Send>(t: T) -> impl Filter<Extract = (T,), Error = Infallible> + Clone {
+    warp::any().map(move || t.clone())
+  }
+
+  filter
+    .and(warp::get())
+    .and(with((config, db, auth_service)))
+    .and(warp::query::<Vec<(String, String)>>())
+    .and_then(async move |(config, db, auth_service), pairs| {
+      let props = sse_props(pairs).map_err(innexgo_hours_error)?;
+      handler(config, db, auth_service, props)
+        .await
+        .map_err(innexgo_hours_error)
+    })
+    .map(|stream| warp::sse::reply(warp::sse::keep_alive().stream(stream)))
+}
+
+// builds props out of query string pairs
+// list props are given by repeating the key with a [] suffix, like ?courseId[]=1&courseId[]=2,
+// and their numeric items become numbers, while every other prop is taken as a string
+pub fn sse_props<PropsType: serde::de::DeserializeOwned>(
+  pairs: Vec<(String, String)>,
+) -> Result<PropsType, InnexgoHoursError> {
+  let mut props = serde_json::Map::new();
+  for (key, value) in pairs {
+    match key.strip_suffix("[]") {
+      Some(key) => {
+        let item = match value.parse::<i64>() {
+          Ok(number) => serde_json::Value::from(number),
+          Err(_) => serde_json::Value::from(value),
+        };
+        match props
+          .entry(key)
+          .or_insert_with(|| serde_json::Value::Array(vec![]))
+        {
+          serde_json::Value::Array(items) => items.push(item),
+          _ => return Err(InnexgoHoursError::DecodeError),
+        }
+      }
+      None => {
+        if props.insert(key, serde_json::Value::from(value)).is_some() {
+          return Err(InnexgoHoursError::DecodeError);
+        }
+      }
+    }
+  }
+
+  serde_json::from_value(serde_json::Value::Object(props))
+    .map_err(|_| InnexgoHoursError::DecodeError)
+}
+
+// this function adapts a handler that sends the browser on to somewhere else
+// these are followed as plain links, so the only prop is the token in the path
+fn redirect_adapter<F>(
+  config: Config,
+  db: Db,
+  filter: impl Filter<Extract = (String,), Error = warp::Rejection> + Clone,
+  handler: fn(Config, Db, String) -> F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+  F: Future<Output = Result<String, InnexgoHoursError>> + Send,
+{
+  // lets you pass in an arbitrary parameter
+  fn with<T: Clone + Send>(t: T) -> impl Filter<Extract = (T,), Error = Infallible> + Clone {
+    warp::any().map(move || t.clone())
+  }
+
+  filter
+    .and(warp::get())
+    .and(with((config, db)))
+    .and_then(async move |token, (config, db)| {
+      let url = handler(config, db, token)
+        .await
+        .map_err(innexgo_hours_error)?;
+      warp::http::Uri::try_from(url)
+        .map_err(|_| innexgo_hours_error(InnexgoHoursError::MeetingLinkInvalid))
+    })
+    .map(|uri: warp::http::Uri| warp::redirect::temporary(uri))
+}
+
 // This type represents errors that we can generate
 // These will be automatically converted to a proper string later
 #[derive(Debug)]
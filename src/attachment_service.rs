@@ -0,0 +1,131 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Attachment {
+  // select * from attachment_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Attachment {
+    Attachment {
+      attachment_id: row.get("attachment_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      session_request_id: row.get("session_request_id"),
+      session_id: row.get("session_id"),
+      name: row.get("name"),
+      media_type: row.get("media_type"),
+      size: row.get("size"),
+      checksum: row.get("checksum"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  session_request_id: Option<i64>,
+  session_id: Option<i64>,
+  name: String,
+  media_type: String,
+  size: i64,
+  checksum: String,
+) -> Result<Attachment, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let attachment_id = con
+    .query_one(
+      "INSERT INTO
+       attachment_t(
+           creation_time,
+           creator_user_id,
+           session_request_id,
+           session_id,
+           name,
+           media_type,
+           size,
+           checksum
+       )
+       VALUES($1, $2, $3, $4, $5, $6, $7, $8)
+       RETURNING attachment_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &session_request_id,
+        &session_id,
+        &name,
+        &media_type,
+        &size,
+        &checksum,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(Attachment {
+    attachment_id,
+    creation_time,
+    creator_user_id,
+    session_request_id,
+    session_id,
+    name,
+    media_type,
+    size,
+    checksum,
+  })
+}
+
+pub async fn get_by_attachment_id(
+  con: &mut impl GenericClient,
+  attachment_id: i64,
+) -> Result<Option<Attachment>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM attachment_t WHERE attachment_id=$1",
+      &[&attachment_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::AttachmentViewProps,
+) -> Result<Vec<Attachment>, tokio_postgres::Error> {
+  let results = con
+    .query(
+      "
+      SELECT at.* FROM attachment_t at
+      LEFT JOIN session_request_t sr ON at.session_request_id = sr.session_request_id
+      LEFT JOIN session_t ses ON at.session_id = ses.session_id
+      WHERE 1 = 1
+      AND ($1::bigint[] IS NULL OR at.attachment_id = ANY($1))
+      AND ($2::bigint   IS NULL OR at.creation_time >= $2)
+      AND ($3::bigint   IS NULL OR at.creation_time <= $3)
+      AND ($4::bigint[] IS NULL OR at.creator_user_id = ANY($4))
+      AND ($5::bigint[] IS NULL OR at.session_request_id = ANY($5))
+      AND ($6::bigint[] IS NULL OR at.session_id = ANY($6))
+      AND ($7::text[]   IS NULL OR at.media_type = ANY($7))
+      AND ($8::bigint[] IS NULL OR COALESCE(sr.course_id, ses.course_id) = ANY($8))
+      ORDER BY at.attachment_id
+      ",
+      &[
+        &props.attachment_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.session_request_id,
+        &props.session_id,
+        &props.media_type,
+        &props.course_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
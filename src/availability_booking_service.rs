@@ -0,0 +1,147 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for AvailabilityBooking {
+  // select * from availability_booking order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> AvailabilityBooking {
+    AvailabilityBooking {
+      availability_booking_id: row.get("availability_booking_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      availability_id: row.get("availability_id"),
+      start_time: row.get("start_time"),
+      commitment_id: row.get("commitment_id"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  availability_id: i64,
+  start_time: i64,
+  commitment_id: i64,
+) -> Result<AvailabilityBooking, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let availability_booking_id = con
+    .query_one(
+      "INSERT INTO
+       availability_booking_t(
+           creation_time,
+           creator_user_id,
+           availability_id,
+           start_time,
+           commitment_id
+       )
+       VALUES($1, $2, $3, $4, $5)
+       RETURNING availability_booking_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &availability_id,
+        &start_time,
+        &commitment_id,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(AvailabilityBooking {
+    availability_booking_id,
+    creation_time,
+    creator_user_id,
+    availability_id,
+    start_time,
+    commitment_id,
+  })
+}
+
+pub async fn get_by_availability_booking_id(
+  con: &mut impl GenericClient,
+  availability_booking_id: i64,
+) -> Result<Option<AvailabilityBooking>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM availability_booking_t WHERE availability_booking_id=$1",
+      &[&availability_booking_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// the start times of slots that are held by a session that's still on, with its commitment still active
+pub async fn get_taken_start_times(
+  con: &mut impl GenericClient,
+  availability_id: i64,
+) -> Result<Vec<i64>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT ab.start_time FROM availability_booking_t ab
+      INNER JOIN commitment_t c ON c.commitment_id = ab.commitment_id
+      INNER JOIN recent_commitment_v rc
+        ON rc.session_id = c.session_id
+        AND rc.attendee_user_id = c.attendee_user_id
+      INNER JOIN recent_session_data_v sd ON sd.session_id = c.session_id
+      WHERE 1 = 1
+      AND ab.availability_id = $1
+      AND rc.active
+      AND sd.active
+      ",
+      &[&availability_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.get(0))
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::AvailabilityBookingViewProps,
+) -> Result<Vec<AvailabilityBooking>, tokio_postgres::Error> {
+  let results = con
+    .query(
+      "
+      SELECT ab.* FROM availability_booking_t ab
+      INNER JOIN availability_t a ON ab.availability_id = a.availability_id
+      INNER JOIN commitment_t c ON ab.commitment_id = c.commitment_id
+      WHERE 1 = 1
+      AND ($1::bigint[] IS NULL OR ab.availability_booking_id = ANY($1))
+      AND ($2::bigint   IS NULL OR ab.creation_time >= $2)
+      AND ($3::bigint   IS NULL OR ab.creation_time <= $3)
+      AND ($4::bigint[] IS NULL OR ab.creator_user_id = ANY($4))
+      AND ($5::bigint[] IS NULL OR ab.availability_id = ANY($5))
+      AND ($6::bigint   IS NULL OR ab.start_time >= $6)
+      AND ($7::bigint   IS NULL OR ab.start_time <= $7)
+      AND ($8::bigint[] IS NULL OR a.course_id = ANY($8))
+      AND ($9::bigint[] IS NULL OR c.attendee_user_id = ANY($9))
+      ORDER BY ab.availability_booking_id
+      ",
+      &[
+        &props.availability_booking_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.availability_id,
+        &props.min_start_time,
+        &props.max_start_time,
+        &props.course_id,
+        &props.attendee_user_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
@@ -0,0 +1,226 @@
+use super::db_types::*;
+use super::utils;
+use super::utils::current_time_millis;
+use std::convert::From;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for AvailabilityData {
+  // select * from availability_data order only, otherwise it will fail
+  fn from(row: tokio_postgres::Row) -> AvailabilityData {
+    AvailabilityData {
+      availability_data_id: row.get("availability_data_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      availability_id: row.get("availability_id"),
+      name: row.get("name"),
+      start_time: row.get("start_time"),
+      end_time: row.get("end_time"),
+      slot_duration: row.get("slot_duration"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  availability_id: i64,
+  name: String,
+  start_time: i64,
+  end_time: i64,
+  slot_duration: i64,
+  active: bool,
+) -> Result<AvailabilityData, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let availability_data_id = con
+    .query_one(
+      "INSERT INTO
+       availability_data_t(
+           creation_time,
+           creator_user_id,
+           availability_id,
+           name,
+           start_time,
+           end_time,
+           slot_duration,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+       RETURNING availability_data_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &availability_id,
+        &name,
+        &start_time,
+        &end_time,
+        &slot_duration,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(AvailabilityData {
+    availability_data_id,
+    creation_time,
+    creator_user_id,
+    availability_id,
+    name,
+    start_time,
+    end_time,
+    slot_duration,
+    active,
+  })
+}
+
+pub async fn get_by_availability_id(
+  con: &mut impl GenericClient,
+  availability_id: i64,
+) -> Result<Option<AvailabilityData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT ad.* FROM recent_availability_data_v ad
+      WHERE ad.availability_id = $1
+      ",
+      &[&availability_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the availability, oldest first
+pub async fn get_history_by_availability_id(
+  con: &mut impl GenericClient,
+  availability_id: i64,
+) -> Result<Vec<AvailabilityData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT ad.* FROM availability_data_t ad
+      WHERE ad.availability_id = $1
+      ORDER BY ad.availability_data_id
+      ",
+      &[&availability_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// active availabilities that still have time left after min_time
+pub async fn get_bookable(
+  con: &mut impl GenericClient,
+  availability_id: &Option<Vec<i64>>,
+  course_id: &Option<Vec<i64>>,
+  min_time: i64,
+  max_time: Option<i64>,
+) -> Result<Vec<AvailabilityData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT ad.* FROM recent_availability_data_v ad
+      INNER JOIN availability_t a ON ad.availability_id = a.availability_id
+      WHERE 1 = 1
+      AND ad.active
+      AND ad.end_time > $3
+      AND ($1::bigint[] IS NULL OR ad.availability_id = ANY($1))
+      AND ($2::bigint[] IS NULL OR a.course_id = ANY($2))
+      AND ($4::bigint   IS NULL OR ad.start_time <= $4)
+      ORDER BY ad.start_time, ad.availability_id
+      ",
+      &[availability_id, course_id, &min_time, &max_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// the start time of every slot, a trailing remainder shorter than a slot isn't offered
+pub fn slot_start_times(availability_data: &AvailabilityData) -> Vec<i64> {
+  if availability_data.slot_duration <= 0 {
+    return vec![];
+  }
+
+  (availability_data.start_time..=availability_data.end_time - availability_data.slot_duration)
+    .step_by(availability_data.slot_duration as usize)
+    .collect()
+}
+
+// if a slot starts at time, worked out without listing every slot
+pub fn is_slot_start(availability_data: &AvailabilityData, time: i64) -> bool {
+  availability_data.slot_duration > 0
+    && time >= availability_data.start_time
+    && (time - availability_data.start_time) % availability_data.slot_duration == 0
+    && time + availability_data.slot_duration <= availability_data.end_time
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: innexgo_hours_api::request::AvailabilityDataViewProps,
+) -> Result<Vec<AvailabilityData>, tokio_postgres::Error> {
+  let sql = [
+    utils::select_versions(
+      "availability_data_t",
+      "ad",
+      "availability_data_id",
+      "availability_id",
+      props.only_recent,
+      13,
+    )
+    .as_str(),
+    " INNER JOIN availability_t a ON ad.availability_id = a.availability_id",
+    " WHERE 1 = 1",
+    " AND ($1::bigint[]  IS NULL OR ad.availability_data_id = ANY($1))",
+    " AND ($2::bigint    IS NULL OR ad.creation_time >= $2)",
+    " AND ($3::bigint    IS NULL OR ad.creation_time <= $3)",
+    " AND ($4::bigint[]  IS NULL OR ad.creator_user_id = ANY($4))",
+    " AND ($5::bigint[]  IS NULL OR ad.availability_id = ANY($5))",
+    " AND ($6::bigint    IS NULL OR ad.start_time >= $6)",
+    " AND ($7::bigint    IS NULL OR ad.start_time <= $7)",
+    " AND ($8::bigint    IS NULL OR ad.end_time >= $8)",
+    " AND ($9::bigint    IS NULL OR ad.end_time <= $9)",
+    " AND ($10::bool     IS NULL OR ad.active = $10)",
+    " AND ($11::bigint[] IS NULL OR a.course_id = ANY($11))",
+    " AND ($12::bigint[] IS NULL OR a.creator_user_id = ANY($12))",
+    " AND ($13::bigint   IS NULL OR ad.creation_time <= $13)",
+    " ORDER BY ad.availability_data_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.availability_data_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.availability_id,
+        &props.min_start_time,
+        &props.max_start_time,
+        &props.min_end_time,
+        &props.max_end_time,
+        &props.active,
+        &props.course_id,
+        &props.instructor_user_id,
+        &props.as_of,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
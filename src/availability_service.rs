@@ -0,0 +1,110 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Availability {
+  // select * from availability order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Availability {
+    Availability {
+      availability_id: row.get("availability_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      course_id: row.get("course_id"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  course_id: i64,
+) -> Result<Availability, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let availability_id = con
+    .query_one(
+      "INSERT INTO
+       availability_t(
+           creation_time,
+           creator_user_id,
+           course_id
+       )
+       VALUES($1, $2, $3)
+       RETURNING availability_id
+      ",
+      &[&creation_time, &creator_user_id, &course_id],
+    )
+    .await?
+    .get(0);
+
+  Ok(Availability {
+    availability_id,
+    creation_time,
+    creator_user_id,
+    course_id,
+  })
+}
+
+pub async fn get_by_availability_id(
+  con: &mut impl GenericClient,
+  availability_id: i64,
+) -> Result<Option<Availability>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM availability_t WHERE availability_id=$1",
+      &[&availability_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// locks the availability until the end of the transaction
+// bookings take this first so that two students can't both see a slot as free and take it
+pub async fn lock_by_availability_id(
+  con: &mut impl GenericClient,
+  availability_id: i64,
+) -> Result<Option<Availability>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM availability_t WHERE availability_id=$1 FOR UPDATE",
+      &[&availability_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::AvailabilityViewProps,
+) -> Result<Vec<Availability>, tokio_postgres::Error> {
+  let results = con
+    .query(
+      "
+      SELECT a.* FROM availability_t a WHERE 1 = 1
+      AND ($1::bigint[] IS NULL OR a.availability_id = ANY($1))
+      AND ($2::bigint   IS NULL OR a.creation_time >= $2)
+      AND ($3::bigint   IS NULL OR a.creation_time <= $3)
+      AND ($4::bigint[] IS NULL OR a.creator_user_id = ANY($4))
+      AND ($5::bigint[] IS NULL OR a.course_id = ANY($5))
+      ORDER BY a.availability_id
+      ",
+      &[
+        &props.availability_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.course_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
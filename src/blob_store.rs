@@ -0,0 +1,60 @@
+use super::utils;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// where the contents of attachments are kept, addressed by their checksum
+// files in a local directory by default, or held in memory for tests
+#[derive(Clone)]
+pub enum BlobStore {
+  Local(PathBuf),
+  Memory(Arc<Mutex<HashMap<String, Vec<u8>>>>),
+}
+
+impl BlobStore {
+  pub fn new_local(path: &str) -> BlobStore {
+    BlobStore::Local(PathBuf::from(path))
+  }
+
+  pub fn new_memory() -> BlobStore {
+    BlobStore::Memory(Arc::new(Mutex::new(HashMap::new())))
+  }
+
+  // storing a key that's already there does nothing, which is how identical uploads share a blob
+  pub async fn put(&self, key: &str, data: &[u8]) -> Result<(), std::io::Error> {
+    match self {
+      BlobStore::Local(root) => {
+        let path = root.join(key);
+        if tokio::fs::try_exists(&path).await? {
+          return Ok(());
+        }
+        tokio::fs::create_dir_all(root).await?;
+        // write to the side and move it into place, so a half written blob is never served
+        let tmp_path = root.join(format!("{}.{}.tmp", key, utils::gen_random_string()));
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, &path).await
+      }
+      BlobStore::Memory(blobs) => {
+        blobs
+          .lock()
+          .await
+          .entry(key.to_owned())
+          .or_insert_with(|| data.to_vec());
+        Ok(())
+      }
+    }
+  }
+
+  pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, std::io::Error> {
+    match self {
+      BlobStore::Local(root) => match tokio::fs::read(root.join(key)).await {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+      },
+      BlobStore::Memory(blobs) => Ok(blobs.lock().await.get(key).cloned()),
+    }
+  }
+}
@@ -0,0 +1,92 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  location_id: i64,
+  code_period: Option<i64>,
+) -> Result<CheckinAttempt, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+  let success = code_period.is_some();
+
+  let checkin_attempt_id = con
+    .query_one(
+      "INSERT INTO
+       checkin_attempt_t(
+           creation_time,
+           creator_user_id,
+           location_id,
+           success,
+           code_period
+       )
+       VALUES ($1, $2, $3, $4, $5)
+       RETURNING checkin_attempt_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &location_id,
+        &success,
+        &code_period,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(CheckinAttempt {
+    checkin_attempt_id,
+    creation_time,
+    creator_user_id,
+    location_id,
+    success,
+    code_period,
+  })
+}
+
+pub async fn count_by_creator_user_id_since(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  min_creation_time: i64,
+) -> Result<i64, tokio_postgres::Error> {
+  let result = con
+    .query_one(
+      "
+      SELECT count(*) FROM checkin_attempt_t ca
+      WHERE 1 = 1
+      AND ca.creator_user_id = $1
+      AND ca.creation_time >= $2
+      ",
+      &[&creator_user_id, &min_creation_time],
+    )
+    .await?
+    .get(0);
+
+  Ok(result)
+}
+
+// if the user already checked in at the location with the code of this period
+pub async fn is_code_period_used(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  location_id: i64,
+  code_period: i64,
+) -> Result<bool, tokio_postgres::Error> {
+  let result: i64 = con
+    .query_one(
+      "
+      SELECT count(*) FROM checkin_attempt_t ca
+      WHERE 1 = 1
+      AND ca.creator_user_id = $1
+      AND ca.location_id = $2
+      AND ca.code_period = $3
+      AND ca.success
+      ",
+      &[&creator_user_id, &location_id, &code_period],
+    )
+    .await?
+    .get(0);
+
+  Ok(result > 0)
+}
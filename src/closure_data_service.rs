@@ -0,0 +1,201 @@
+use super::db_types::*;
+use super::utils;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for ClosureData {
+  // select * from closure_data_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> ClosureData {
+    ClosureData {
+      closure_data_id: row.get("closure_data_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      closure_id: row.get("closure_id"),
+      name: row.get("name"),
+      start_time: row.get("start_time"),
+      end_time: row.get("end_time"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  closure_id: i64,
+  name: String,
+  start_time: i64,
+  end_time: i64,
+  active: bool,
+) -> Result<ClosureData, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let closure_data_id = con
+    .query_one(
+      "INSERT INTO
+       closure_data_t(
+           creation_time,
+           creator_user_id,
+           closure_id,
+           name,
+           start_time,
+           end_time,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7)
+       RETURNING closure_data_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &closure_id,
+        &name,
+        &start_time,
+        &end_time,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(ClosureData {
+    closure_data_id,
+    creation_time,
+    creator_user_id,
+    closure_id,
+    name,
+    start_time,
+    end_time,
+    active,
+  })
+}
+
+pub async fn get_by_closure_id(
+  con: &mut impl GenericClient,
+  closure_id: i64,
+) -> Result<Option<ClosureData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT cld.* FROM recent_closure_data_v cld
+      WHERE cld.closure_id = $1
+      ",
+      &[&closure_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the closure, oldest first
+pub async fn get_history_by_closure_id(
+  con: &mut impl GenericClient,
+  closure_id: i64,
+) -> Result<Vec<ClosureData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT cld.* FROM closure_data_t cld
+      WHERE cld.closure_id = $1
+      ORDER BY cld.closure_data_id
+      ",
+      &[&closure_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// active closures of the school that overlap [start_time, end_time)
+pub async fn get_active_by_school_id_in_range(
+  con: &mut impl GenericClient,
+  school_id: i64,
+  start_time: i64,
+  end_time: i64,
+) -> Result<Vec<ClosureData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT cld.* FROM recent_closure_data_v cld
+      JOIN closure_t cl ON cld.closure_id = cl.closure_id
+      WHERE 1 = 1
+      AND cl.school_id = $1
+      AND cld.active
+      AND cld.start_time < $3
+      AND cld.end_time > $2
+      ORDER BY cld.start_time
+      ",
+      &[&school_id, &start_time, &end_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: innexgo_hours_api::request::ClosureDataViewProps,
+) -> Result<Vec<ClosureData>, tokio_postgres::Error> {
+  let sql = [
+    utils::select_versions(
+      "closure_data_t",
+      "cld",
+      "closure_data_id",
+      "closure_id",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
+    " JOIN closure_t cl ON cld.closure_id = cl.closure_id",
+    " WHERE 1 = 1",
+    " AND ($1::bigint[]  IS NULL OR cld.closure_data_id = ANY($1))",
+    " AND ($2::bigint    IS NULL OR cld.creation_time >= $2)",
+    " AND ($3::bigint    IS NULL OR cld.creation_time <= $3)",
+    " AND ($4::bigint[]  IS NULL OR cld.creator_user_id = ANY($4))",
+    " AND ($5::bigint[]  IS NULL OR cld.closure_id = ANY($5))",
+    " AND ($6::text[]    IS NULL OR cld.name = ANY($6))",
+    " AND ($7::text      IS NULL OR cld.name LIKE CONCAT('%',$7,'%'))",
+    " AND ($8::bigint    IS NULL OR cld.start_time >= $8)",
+    " AND ($9::bigint    IS NULL OR cld.start_time <= $9)",
+    " AND ($10::bigint   IS NULL OR cld.end_time >= $10)",
+    " AND ($11::bigint   IS NULL OR cld.end_time <= $11)",
+    " AND ($12::bool     IS NULL OR cld.active = $12)",
+    " AND ($13::bigint[] IS NULL OR cl.school_id = ANY($13))",
+    " AND ($14::bigint  IS NULL OR cld.creation_time <= $14)",
+    " ORDER BY cld.closure_data_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.closure_data_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.closure_id,
+        &props.name,
+        &props.partial_name,
+        &props.min_start_time,
+        &props.max_start_time,
+        &props.min_end_time,
+        &props.max_end_time,
+        &props.active,
+        &props.school_id,
+        &props.as_of,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
@@ -0,0 +1,96 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Closure {
+  // select * from closure_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Closure {
+    Closure {
+      closure_id: row.get("closure_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      school_id: row.get("school_id"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  school_id: i64,
+) -> Result<Closure, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let closure_id = con
+    .query_one(
+      "INSERT INTO
+       closure_t(
+           creation_time,
+           creator_user_id,
+           school_id
+       )
+       VALUES($1, $2, $3)
+       RETURNING closure_id
+      ",
+      &[&creation_time, &creator_user_id, &school_id],
+    )
+    .await?
+    .get(0);
+
+  // return closure
+  Ok(Closure {
+    closure_id,
+    creation_time,
+    creator_user_id,
+    school_id,
+  })
+}
+
+pub async fn get_by_closure_id(
+  con: &mut impl GenericClient,
+  closure_id: i64,
+) -> Result<Option<Closure>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM closure_t WHERE closure_id=$1",
+      &[&closure_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::ClosureViewProps,
+) -> Result<Vec<Closure>, tokio_postgres::Error> {
+  let sql = "SELECT cl.* FROM closure_t cl WHERE 1 = 1
+     AND ($1::bigint[] IS NULL OR cl.closure_id = ANY($1))
+     AND ($2::bigint   IS NULL OR cl.creation_time >= $2)
+     AND ($3::bigint   IS NULL OR cl.creation_time <= $3)
+     AND ($4::bigint[] IS NULL OR cl.creator_user_id = ANY($4))
+     AND ($5::bigint[] IS NULL OR cl.school_id = ANY($5))
+     ORDER BY cl.closure_id
+     ";
+
+  let stmnt = con.prepare(sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.closure_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.school_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(results)
+}
@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use innexgo_hours_api::request;
 use tokio_postgres::GenericClient;
@@ -92,16 +93,73 @@ pub async fn get_by_attendee_user_id_session_id(
   Ok(result)
 }
 
+pub async fn get_active_by_session_id(
+  con: &mut impl GenericClient,
+  session_id: i64,
+) -> Result<Vec<Commitment>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT c.* FROM recent_commitment_v c
+      WHERE 1 = 1
+      AND c.session_id = $1
+      AND c.active
+      ORDER BY c.commitment_id
+      ",
+      &[&session_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+// active commitments to sessions starting within [min_start_time, max_start_time)
+pub async fn get_active_by_attendee_user_id_in_range(
+  con: &mut impl GenericClient,
+  attendee_user_id: i64,
+  min_start_time: i64,
+  max_start_time: i64,
+) -> Result<Vec<Commitment>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT c.* FROM recent_commitment_v c
+      JOIN recent_session_data_v sd ON sd.session_id = c.session_id
+      WHERE 1 = 1
+      AND c.attendee_user_id = $1
+      AND c.active
+      AND sd.active
+      AND sd.start_time >= $2
+      AND sd.start_time < $3
+      ORDER BY sd.start_time
+      ",
+      &[&attendee_user_id, &min_start_time, &max_start_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: request::CommitmentViewProps,
 ) -> Result<Vec<Commitment>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT c.* FROM recent_commitment_v c"
-    } else {
-      "SELECT c.* FROM commitment_t c"
-    },
+    utils::select_versions(
+      "commitment_t",
+      "c",
+      "commitment_id",
+      "session_id, attendee_user_id",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
     "INNER JOIN session_t ses ON ses.session_id = c.session_id",
     "INNER JOIN recent_session_data_v sesd ON sesd.session_id = c.session_id",
     "LEFT JOIN session_request_response_t srr ON srr.commitment_id = c.commitment_id",
@@ -119,6 +177,7 @@ pub async fn query(
     "AND ($11::bigint  IS NULL OR sesd.end_time <= $11)",
     "AND ($12::bool    IS NULL OR c.active IS NOT NULL = $12)",
     "AND ($13::bool    IS NULL OR srr.commitment_id IS NOT NULL = $13)",
+    "AND ($14::bigint  IS NULL OR c.creation_time <= $14)",
     "ORDER BY c.commitment_id",
   ]
   .join("\n");
@@ -142,6 +201,7 @@ pub async fn query(
         &props.max_end_time,
         &props.active,
         &props.from_request_response,
+        &props.as_of,
       ],
     )
     .await?
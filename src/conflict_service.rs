@@ -0,0 +1,126 @@
+use super::db_types::*;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+// every query here finds active sessions overlapping [start_time, end_time)
+// session_id is the session being scheduled, which can't conflict with itself
+
+// sessions run by any instructor of the course at the same time
+pub async fn get_by_instructors_of_course_id(
+  con: &mut impl GenericClient,
+  course_id: i64,
+  session_id: Option<i64>,
+  start_time: i64,
+  end_time: i64,
+) -> Result<Vec<Conflict>, tokio_postgres::Error> {
+  let instructor = request::CourseMembershipKind::Instructor as i64;
+
+  let result = con
+    .query(
+      "
+      SELECT DISTINCT ON (other.user_id, sd.session_id) other.user_id, sd.*
+      FROM recent_course_membership_v mine
+      JOIN recent_course_membership_v other ON other.user_id = mine.user_id
+      JOIN session_t ses ON ses.course_id = other.course_id
+      JOIN recent_session_data_v sd ON sd.session_id = ses.session_id
+      WHERE 1 = 1
+      AND mine.course_id = $1
+      AND mine.course_membership_kind = $5
+      AND other.course_membership_kind = $5
+      AND ($2::bigint IS NULL OR sd.session_id <> $2)
+      AND sd.active
+      AND sd.start_time < $4
+      AND sd.end_time > $3
+      ORDER BY other.user_id, sd.session_id
+      ",
+      &[&course_id, &session_id, &start_time, &end_time, &instructor],
+    )
+    .await?
+    .into_iter()
+    .map(|row| Conflict {
+      conflict_kind: request::ConflictKind::Instructor,
+      user_id: Some(row.get("user_id")),
+      location_id: None,
+      session_data: row.into(),
+    })
+    .collect();
+
+  Ok(result)
+}
+
+// sessions held in the course's location at the same time
+pub async fn get_by_location_of_course_id(
+  con: &mut impl GenericClient,
+  course_id: i64,
+  session_id: Option<i64>,
+  start_time: i64,
+  end_time: i64,
+) -> Result<Vec<Conflict>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT mine.location_id, sd.*
+      FROM recent_course_data_v mine
+      JOIN recent_course_data_v other ON other.location_id = mine.location_id
+      JOIN session_t ses ON ses.course_id = other.course_id
+      JOIN recent_session_data_v sd ON sd.session_id = ses.session_id
+      WHERE 1 = 1
+      AND mine.course_id = $1
+      AND ($2::bigint IS NULL OR sd.session_id <> $2)
+      AND sd.active
+      AND sd.start_time < $4
+      AND sd.end_time > $3
+      ORDER BY sd.session_id
+      ",
+      &[&course_id, &session_id, &start_time, &end_time],
+    )
+    .await?
+    .into_iter()
+    .map(|row| Conflict {
+      conflict_kind: request::ConflictKind::Location,
+      user_id: None,
+      location_id: Some(row.get("location_id")),
+      session_data: row.into(),
+    })
+    .collect();
+
+  Ok(result)
+}
+
+// sessions any of the attendees are already committed to at the same time
+pub async fn get_by_attendee_user_ids(
+  con: &mut impl GenericClient,
+  attendee_user_ids: &[i64],
+  session_id: Option<i64>,
+  start_time: i64,
+  end_time: i64,
+) -> Result<Vec<Conflict>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT c.attendee_user_id, sd.*
+      FROM recent_commitment_v c
+      JOIN recent_session_data_v sd ON sd.session_id = c.session_id
+      WHERE 1 = 1
+      AND c.attendee_user_id = ANY($1)
+      AND ($2::bigint IS NULL OR sd.session_id <> $2)
+      AND c.active
+      AND sd.active
+      AND sd.start_time < $4
+      AND sd.end_time > $3
+      ORDER BY c.attendee_user_id, sd.session_id
+      ",
+      &[&attendee_user_ids, &session_id, &start_time, &end_time],
+    )
+    .await?
+    .into_iter()
+    .map(|row| Conflict {
+      conflict_kind: request::ConflictKind::Attendee,
+      user_id: Some(row.get("attendee_user_id")),
+      location_id: None,
+      session_data: row.into(),
+    })
+    .collect();
+
+  Ok(result)
+}
@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use tokio_postgres::GenericClient;
 
@@ -11,6 +12,7 @@ impl From<tokio_postgres::row::Row> for CourseData {
       creator_user_id: row.get("creator_user_id"),
       course_id: row.get("course_id"),
       location_id: row.get("location_id"),
+      term_id: row.get("term_id"),
       name: row.get("name"),
       description: row.get("description"),
       homeroom: row.get("homeroom"),
@@ -25,6 +27,7 @@ pub async fn add(
   creator_user_id: i64,
   course_id: i64,
   location_id: i64,
+  term_id: Option<i64>,
   name: String,
   description: String,
   homeroom: bool,
@@ -40,12 +43,13 @@ pub async fn add(
            creator_user_id,
            course_id,
            location_id,
+           term_id,
            name,
            description,
            homeroom,
            active
        )
-       VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
        RETURNING course_data_id
       ",
       &[
@@ -53,6 +57,7 @@ pub async fn add(
         &creator_user_id,
         &course_id,
         &location_id,
+        &term_id,
         &name,
         &description,
         &homeroom,
@@ -69,6 +74,7 @@ pub async fn add(
     creator_user_id,
     course_id,
     location_id,
+    term_id,
     name,
     description,
     homeroom,
@@ -93,6 +99,27 @@ pub async fn get_by_course_id(
   Ok(result)
 }
 
+// every version of the course, oldest first
+pub async fn get_history_by_course_id(
+  con: &mut impl GenericClient,
+  course_id: i64,
+) -> Result<Vec<CourseData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT cd.* FROM course_data_t cd
+      WHERE cd.course_id = $1
+      ORDER BY cd.course_data_id
+      ",
+      &[&course_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 pub async fn is_active_by_course_id(
   con: &mut impl GenericClient,
   course_id: i64,
@@ -105,6 +132,28 @@ pub async fn is_active_by_course_id(
   Ok(result)
 }
 
+// courses currently assigned to a term that haven't been archived
+pub async fn get_active_by_term_id(
+  con: &mut impl GenericClient,
+  term_id: i64,
+) -> Result<Vec<CourseData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT cd.* FROM recent_course_data_v cd
+      WHERE cd.term_id = $1
+      AND cd.active
+      ORDER BY cd.course_id
+      ",
+      &[&term_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 #[allow(unused)]
 pub async fn get_by_course_data_id(
   con: &mut impl GenericClient,
@@ -125,11 +174,15 @@ pub async fn query(
   props: innexgo_hours_api::request::CourseDataViewProps,
 ) -> Result<Vec<CourseData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT cd.* FROM recent_course_data_v cd"
-    } else {
-      "SELECT cd.* FROM course_data_t cd"
-    },
+    utils::select_versions(
+      "course_data_t",
+      "cd",
+      "course_data_id",
+      "course_id",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
     " JOIN course_t c ON cd.course_id = c.course_id",
     " WHERE 1 = 1",
     " AND ($1::bigint[]  IS NULL OR cd.course_data_id = ANY($1))",
@@ -145,6 +198,8 @@ pub async fn query(
     " AND ($11::bool     IS NULL OR cd.homeroom = $11)",
     " AND ($12::bool     IS NULL OR cd.active = $12)",
     " AND ($13::bigint[] IS NULL OR c.school_id = ANY($13))",
+    " AND ($14::bigint  IS NULL OR cd.creation_time <= $14)",
+    " AND ($15::bigint[] IS NULL OR cd.term_id = ANY($15))",
     " ORDER BY cd.course_data_id",
   ]
   .join("\n");
@@ -168,6 +223,8 @@ pub async fn query(
         &props.homeroom,
         &props.active,
         &props.school_id,
+        &props.as_of,
+        &props.term_id,
       ],
     )
     .await?
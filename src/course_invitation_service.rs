@@ -0,0 +1,131 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use std::convert::TryInto;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for CourseInvitation {
+  // select * from course_invitation_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> CourseInvitation {
+    CourseInvitation {
+      course_invitation_id: row.get("course_invitation_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      course_id: row.get("course_id"),
+      email: row.get("email"),
+      course_membership_kind: (row.get::<_, i64>("course_membership_kind") as u8)
+        .try_into()
+        .unwrap(),
+      end_time: row.get("end_time"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  course_id: i64,
+  email: String,
+  course_membership_kind: request::CourseMembershipKind,
+  end_time: i64,
+) -> Result<CourseInvitation, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let course_invitation_id = con
+    .query_one(
+      "INSERT INTO
+       course_invitation_t(
+           creation_time,
+           creator_user_id,
+           course_id,
+           email,
+           course_membership_kind,
+           end_time
+       )
+       VALUES($1, $2, $3, $4, $5, $6)
+       RETURNING course_invitation_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &course_id,
+        &email,
+        &(course_membership_kind.clone() as i64),
+        &end_time,
+      ],
+    )
+    .await?
+    .get(0);
+
+  // return course_invitation
+  Ok(CourseInvitation {
+    course_invitation_id,
+    creation_time,
+    creator_user_id,
+    course_id,
+    email,
+    course_membership_kind,
+    end_time,
+  })
+}
+
+pub async fn get_by_course_invitation_id(
+  con: &mut impl GenericClient,
+  course_invitation_id: i64,
+) -> Result<Option<CourseInvitation>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM course_invitation_t WHERE course_invitation_id=$1",
+      &[&course_invitation_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::CourseInvitationViewProps,
+) -> Result<Vec<CourseInvitation>, tokio_postgres::Error> {
+  let sql = "
+    SELECT ci.* FROM course_invitation_t ci
+    WHERE 1 = 1
+    AND ($1::bigint[] IS NULL OR ci.course_invitation_id = ANY($1))
+    AND ($2::bigint   IS NULL OR ci.creation_time >= $2)
+    AND ($3::bigint   IS NULL OR ci.creation_time <= $3)
+    AND ($4::bigint[] IS NULL OR ci.creator_user_id = ANY($4))
+    AND ($5::bigint[] IS NULL OR ci.course_id = ANY($5))
+    AND ($6::text[]   IS NULL OR ci.email = ANY($6))
+    AND ($7::bigint[] IS NULL OR ci.course_membership_kind = ANY($7))
+    AND ($8::bigint   IS NULL OR ci.end_time >= $8)
+    AND ($9::bigint   IS NULL OR ci.end_time <= $9)
+    ORDER BY ci.course_invitation_id
+  ";
+
+  let stmnt = con.prepare(sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.course_invitation_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.course_id,
+        &props.email,
+        &props
+          .course_membership_kind
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.min_end_time,
+        &props.max_end_time,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(results)
+}
@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use tokio_postgres::GenericClient;
 
@@ -64,16 +65,70 @@ pub async fn get_by_course_key_data_id(
   Ok(result)
 }
 
+pub async fn get_by_course_key_key(
+  con: &mut impl GenericClient,
+  course_key_key: &str,
+) -> Result<Option<CourseKeyData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT ckd.* FROM recent_course_key_data_v ckd
+      WHERE ckd.course_key_key = $1
+      ",
+      &[&course_key_key],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the course key, oldest first
+pub async fn get_history_by_course_key_key(
+  con: &mut impl GenericClient,
+  course_key_key: &str,
+) -> Result<Vec<CourseKeyData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT ckd.* FROM course_key_data_t ckd
+      WHERE ckd.course_key_key = $1
+      ORDER BY ckd.course_key_data_id
+      ",
+      &[&course_key_key],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+pub async fn is_active_by_course_key_key(
+  con: &mut impl GenericClient,
+  course_key_key: &str,
+) -> Result<bool, tokio_postgres::Error> {
+  let result = matches!(
+    get_by_course_key_key(con, course_key_key).await?,
+    Some(CourseKeyData { active: true, .. })
+  );
+
+  Ok(result)
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: innexgo_hours_api::request::CourseKeyDataViewProps,
 ) -> Result<Vec<CourseKeyData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT ckd.* FROM recent_course_key_data_v ckd"
-    } else {
-      "SELECT ckd.* FROM course_key_data_t ckd"
-    },
+    utils::select_versions(
+      "course_key_data_t",
+      "ckd",
+      "course_key_data_id",
+      "course_key_key",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
     " JOIN course_key_t ck ON ckd.course_key_key = ck.course_key_key",
     " WHERE 1 = 1",
     " AND ($1::bigint[] IS NULL OR ckd.course_key_data_id = ANY($1))",
@@ -89,6 +144,7 @@ pub async fn query(
     " AND ($11::bigint  IS NULL OR ck.start_time <= $11)",
     " AND ($12::bigint  IS NULL OR ck.end_time >= $12)",
     " AND ($13::bigint  IS NULL OR ck.end_time <= $13)",
+    " AND ($14::bigint  IS NULL OR ckd.creation_time <= $14)",
     " ORDER BY ckd.course_key_data_id",
   ]
   .join("\n");
@@ -113,6 +169,7 @@ pub async fn query(
         &props.max_start_time,
         &props.min_end_time,
         &props.max_end_time,
+        &props.as_of,
       ],    )
     .await?
     .into_iter()
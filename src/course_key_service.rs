@@ -1,5 +1,6 @@
 use super::db_types::*;
 use super::utils::current_time_millis;
+use super::utils::normalize_short_code;
 use innexgo_hours_api::request;
 use std::convert::TryInto;
 use tokio_postgres::GenericClient;
@@ -18,6 +19,7 @@ impl From<tokio_postgres::row::Row> for CourseKey {
         .unwrap(),
       start_time: row.get("start_time"),
       end_time: row.get("end_time"),
+      short_code: row.get("short_code"),
     }
   }
 }
@@ -31,6 +33,7 @@ pub async fn add(
   course_membership_kind: request::CourseMembershipKind,
   start_time: i64,
   end_time: i64,
+  short_code: Option<String>,
 ) -> Result<CourseKey, tokio_postgres::Error> {
   let creation_time = current_time_millis();
 
@@ -45,9 +48,10 @@ pub async fn add(
            max_uses,
            course_membership_kind,
            start_time,
-           end_time
+           end_time,
+           short_code
        )
-       VALUES($1, $2, $3, $4, $5, $6, $7, $8)
+       VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
        RETURNING course_key_key
       ",
       &[
@@ -59,6 +63,7 @@ pub async fn add(
         &(course_membership_kind.clone() as i64),
         &start_time,
         &end_time,
+        &short_code,
       ],
     )
     .await?
@@ -74,6 +79,7 @@ pub async fn add(
     course_membership_kind,
     start_time,
     end_time,
+    short_code,
   })
 }
 
@@ -92,6 +98,32 @@ pub async fn get_by_course_key_key(
   Ok(result)
 }
 
+pub async fn get_by_short_code(
+  con: &mut impl GenericClient,
+  short_code: &str,
+) -> Result<Option<CourseKey>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM course_key_t WHERE short_code=$1",
+      &[&short_code],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// keys may be redeemed either by their full key or by their short code
+pub async fn get_by_key_or_short_code(
+  con: &mut impl GenericClient,
+  key: &str,
+) -> Result<Option<CourseKey>, tokio_postgres::Error> {
+  match get_by_course_key_key(con, key).await? {
+    Some(x) => Ok(Some(x)),
+    None => get_by_short_code(con, &normalize_short_code(key)).await,
+  }
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: request::CourseKeyViewProps,
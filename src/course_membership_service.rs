@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use innexgo_hours_api::request;
 use std::convert::TryInto;
@@ -17,6 +18,7 @@ impl From<tokio_postgres::row::Row> for CourseMembership {
         .try_into()
         .unwrap(),
       course_key_key: row.get("course_key_key"),
+      course_invitation_id: row.get("course_invitation_id"),
     }
   }
 }
@@ -28,6 +30,7 @@ pub async fn add(
   course_id: i64,
   course_membership_kind: request::CourseMembershipKind,
   course_key_key: Option<String>,
+  course_invitation_id: Option<i64>,
 ) -> Result<CourseMembership, tokio_postgres::Error> {
   let creation_time = current_time_millis();
 
@@ -40,9 +43,10 @@ pub async fn add(
            user_id,
            course_id,
            course_membership_kind,
-           course_key_key
+           course_key_key,
+           course_invitation_id
        )
-       VALUES ($1, $2, $3, $4, $5, $6)
+       VALUES ($1, $2, $3, $4, $5, $6, $7)
        RETURNING course_membership_id
       ",
       &[
@@ -52,6 +56,7 @@ pub async fn add(
         &course_id,
         &(course_membership_kind.clone() as i64),
         &course_key_key,
+        &course_invitation_id,
       ],
     )
     .await?
@@ -66,6 +71,7 @@ pub async fn add(
     course_id,
     course_membership_kind,
     course_key_key,
+    course_invitation_id,
   })
 }
 
@@ -108,6 +114,30 @@ pub async fn count_course_key_uses(
   Ok(get_by_course_key_key(con, course_key_key).await?.len() as i64)
 }
 
+pub async fn get_by_course_invitation_id(
+  con: &mut impl GenericClient,
+  course_invitation_id: i64,
+) -> Result<Vec<CourseMembership>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "SELECT * FROM course_membership_t WHERE course_invitation_id=$1",
+      &[&course_invitation_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn count_course_invitation_uses(
+  con: &mut impl GenericClient,
+  course_invitation_id: i64,
+) -> Result<i64, tokio_postgres::Error> {
+  Ok(get_by_course_invitation_id(con, course_invitation_id).await?.len() as i64)
+}
+
 pub async fn get_by_user_id_course_id(
   con: &mut impl GenericClient,
   user_id: i64,
@@ -129,42 +159,74 @@ pub async fn get_by_user_id_course_id(
   Ok(result)
 }
 
-pub async fn is_student(
-  con: &mut impl GenericClient,
-  user_id: i64,
-  course_id: i64,
-) -> Result<bool, tokio_postgres::Error> {
-  let result = matches!(
-    get_by_user_id_course_id(con, user_id, course_id).await?,
-    Some(CourseMembership {
-      course_membership_kind: request::CourseMembershipKind::Student,
-      ..
-    })
-  );
+// What a course member is allowed to do.
+// Handlers check for a capability instead of a specific membership kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CourseCapability {
+  // see the course, its sessions and its members
+  View,
+  // see other members' commitments, session requests, encounters and stays
+  ViewRecords,
+  // create and edit sessions and commitments, and respond to session requests
+  RunSessions,
+  // record encounters and stays at the course's location
+  TakeAttendance,
+  // edit course data, keys, invitations and memberships
+  Manage,
+  // file session requests and be committed to sessions
+  Attend,
+}
 
-  Ok(result)
+// the capability matrix
+pub fn kind_has_capability(
+  course_membership_kind: &request::CourseMembershipKind,
+  capability: CourseCapability,
+) -> bool {
+  use request::CourseMembershipKind as K;
+  use CourseCapability as C;
+  match (course_membership_kind, capability) {
+    (K::Instructor, C::Attend) => false,
+    (K::Instructor, _) => true,
+    (K::TeachingAssistant, C::View | C::ViewRecords | C::RunSessions | C::TakeAttendance) => true,
+    (K::Observer, C::View | C::ViewRecords) => true,
+    (K::Student, C::View | C::Attend) => true,
+    _ => false,
+  }
 }
 
-pub async fn is_instructor(
+fn kinds_with_capability(capability: CourseCapability) -> Vec<i64> {
+  vec![
+    request::CourseMembershipKind::Student,
+    request::CourseMembershipKind::Instructor,
+    request::CourseMembershipKind::TeachingAssistant,
+    request::CourseMembershipKind::Observer,
+  ]
+  .into_iter()
+  .filter(|x| kind_has_capability(x, capability))
+  .map(|x| x as i64)
+  .collect()
+}
+
+pub async fn has_capability(
   con: &mut impl GenericClient,
   user_id: i64,
   course_id: i64,
+  capability: CourseCapability,
 ) -> Result<bool, tokio_postgres::Error> {
-  let result = matches!(
-    get_by_user_id_course_id(con, user_id, course_id).await?,
-    Some(CourseMembership {
-      course_membership_kind: request::CourseMembershipKind::Instructor,
-      ..
-    })
-  );
+  let result = match get_by_user_id_course_id(con, user_id, course_id).await? {
+    Some(x) => kind_has_capability(&x.course_membership_kind, capability),
+    None => false,
+  };
+
   Ok(result)
 }
 
-// if the user id is an instructor at the given location
-pub async fn is_instructor_at(
+// if the user has the capability in any active course held at the given location
+pub async fn has_capability_at(
   con: &mut impl GenericClient,
   user_id: i64,
   location_id: i64,
+  capability: CourseCapability,
 ) -> Result<bool, tokio_postgres::Error> {
   let result: i64 = con
     .query_one(
@@ -175,14 +237,10 @@ pub async fn is_instructor_at(
       WHERE 1 = 1
       AND cm.user_id = $1
       AND cd.location_id = $2
-      AND cm.course_membership_kind = $3
+      AND cm.course_membership_kind = ANY($3)
       AND cd.active
       ",
-      &[
-        &user_id,
-        &location_id,
-        &(request::CourseMembershipKind::Instructor as i64),
-      ],
+      &[&user_id, &location_id, &kinds_with_capability(capability)],
     )
     .await?
     .get(0);
@@ -190,7 +248,59 @@ pub async fn is_instructor_at(
   Ok(result > 0)
 }
 
-pub async fn is_member(
+// everyone who has the capability in the course
+pub async fn get_user_ids_with_capability(
+  con: &mut impl GenericClient,
+  course_id: i64,
+  capability: CourseCapability,
+) -> Result<Vec<i64>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT cm.user_id
+      FROM recent_course_membership_v cm
+      WHERE 1 = 1
+      AND cm.course_id = $1
+      AND cm.course_membership_kind = ANY($2)
+      ",
+      &[&course_id, &kinds_with_capability(capability)],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.get(0))
+    .collect();
+
+  Ok(result)
+}
+
+// everyone who has the capability in any active course held at the given location
+pub async fn get_user_ids_with_capability_at(
+  con: &mut impl GenericClient,
+  location_id: i64,
+  capability: CourseCapability,
+) -> Result<Vec<i64>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT DISTINCT cm.user_id
+      FROM recent_course_membership_v cm
+      JOIN recent_course_data_v cd ON cd.course_id = cm.course_id
+      WHERE 1 = 1
+      AND cd.location_id = $1
+      AND cm.course_membership_kind = ANY($2)
+      AND cd.active
+      ",
+      &[&location_id, &kinds_with_capability(capability)],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.get(0))
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn is_instructor(
   con: &mut impl GenericClient,
   user_id: i64,
   course_id: i64,
@@ -198,12 +308,10 @@ pub async fn is_member(
   let result = matches!(
     get_by_user_id_course_id(con, user_id, course_id).await?,
     Some(CourseMembership {
-      course_membership_kind: request::CourseMembershipKind::Instructor
-        | request::CourseMembershipKind::Student,
+      course_membership_kind: request::CourseMembershipKind::Instructor,
       ..
     })
   );
-
   Ok(result)
 }
 
@@ -254,11 +362,15 @@ pub async fn query(
   props: innexgo_hours_api::request::CourseMembershipViewProps,
 ) -> Result<Vec<CourseMembership>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT cm.* FROM recent_course_membership_v cm"
-    } else {
-      "SELECT cm.* FROM course_membership_t cm"
-    },
+    utils::select_versions(
+      "course_membership_t",
+      "cm",
+      "course_membership_id",
+      "user_id, course_id",
+      props.only_recent,
+      10,
+    )
+    .as_str(),
     " WHERE 1 = 1",
     " AND ($1::bigint[] IS NULL OR cm.course_membership_id = ANY($1))",
     " AND ($2::bigint   IS NULL OR cm.creation_time >= $2)",
@@ -269,6 +381,7 @@ pub async fn query(
     " AND ($7::bigint[] IS NULL OR cm.course_membership_kind = ANY($7))",
     " AND ($8::bool     IS NULL OR cm.course_key_key IS NOT NULL = $8)",
     " AND ($9::text[]   IS NULL OR cm.course_key_key = ANY($9))",
+    " AND ($10::bigint  IS NULL OR cm.creation_time <= $10)",
     " ORDER BY cm.course_membership_id",
   ]
   .join("\n");
@@ -290,6 +403,7 @@ pub async fn query(
           .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
         &props.course_membership_from_key,
         &props.course_key_key,
+        &props.as_of,
       ],
     )
     .await?
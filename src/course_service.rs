@@ -69,6 +69,9 @@ pub async fn query(
      AND ($3::bigint   IS NULL OR c.creation_time <= $3)
      AND ($4::bigint[] IS NULL OR c.creator_user_id = ANY($4))
      AND ($5::bigint[] IS NULL OR c.school_id = ANY($5))
+     AND ($6::bigint[] IS NULL OR c.course_id IN (
+       SELECT cd.course_id FROM recent_course_data_v cd WHERE cd.term_id = ANY($6)
+     ))
      ORDER BY c.course_id
      ";
 
@@ -83,6 +86,7 @@ pub async fn query(
         &props.max_creation_time,
         &props.creator_user_id,
         &props.school_id,
+        &props.term_id,
       ],
     )
     .await?
@@ -1,7 +1,14 @@
 use either::Either;
 use innexgo_hours_api::request::AdminshipKind;
+use innexgo_hours_api::request::ConflictKind;
 use innexgo_hours_api::request::CourseMembershipKind;
 use innexgo_hours_api::request::EncounterKind;
+use innexgo_hours_api::request::GuardianshipKind;
+use innexgo_hours_api::request::HallPassReasonKind;
+use innexgo_hours_api::request::IrregularityKind;
+use innexgo_hours_api::request::SessionChangeKind;
+use innexgo_hours_api::request::SessionNoteKind;
+use innexgo_hours_api::request::SessionNoteVisibility;
 use innexgo_hours_api::request::SubscriptionKind;
 
 #[derive(Clone, Debug)]
@@ -53,6 +60,46 @@ pub struct SchoolDurationData {
   pub active: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct Term {
+  pub term_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub school_id: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct TermData {
+  pub term_data_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub term_id: i64,
+  pub name: String,
+  pub start_time: i64,
+  pub end_time: i64,
+  pub active: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Closure {
+  pub closure_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub school_id: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClosureData {
+  pub closure_data_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub closure_id: i64,
+  pub name: String,
+  pub start_time: i64,
+  pub end_time: i64,
+  pub active: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct SchoolKey {
   pub school_key_key: String,
@@ -61,6 +108,7 @@ pub struct SchoolKey {
   pub school_id: i64,
   pub start_time: i64,
   pub end_time: i64,
+  pub short_code: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -72,6 +120,16 @@ pub struct SchoolKeyData {
   pub active: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct SchoolInvitation {
+  pub school_invitation_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub school_id: i64,
+  pub email: String,
+  pub end_time: i64,
+}
+
 #[derive(Clone, Debug)]
 pub struct Adminship {
   pub adminship_id: i64,
@@ -81,6 +139,24 @@ pub struct Adminship {
   pub school_id: i64,
   pub adminship_kind: AdminshipKind,
   pub school_key_key: Option<String>,
+  pub school_invitation_id: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GuardianshipSummaryRun {
+  pub guardianship_summary_run_id: i64,
+  pub creation_time: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Guardianship {
+  pub guardianship_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub guardian_user_id: i64,
+  pub student_user_id: i64,
+  pub guardianship_kind: GuardianshipKind,
+  pub weekly_summary: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -103,6 +179,26 @@ pub struct LocationData {
   pub active: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct LocationCheckin {
+  pub location_checkin_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub location_id: i64,
+  pub secret: String,
+  pub active: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct CheckinAttempt {
+  pub checkin_attempt_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub location_id: i64,
+  pub success: bool,
+  pub code_period: Option<i64>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Course {
   pub course_id: i64,
@@ -118,6 +214,7 @@ pub struct CourseData {
   pub creator_user_id: i64,
   pub course_id: i64,
   pub location_id: i64,
+  pub term_id: Option<i64>,
   pub name: String,
   pub description: String,
   pub homeroom: bool,
@@ -134,6 +231,7 @@ pub struct CourseKey {
   pub course_membership_kind: CourseMembershipKind,
   pub start_time: i64,
   pub end_time: i64,
+  pub short_code: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -145,6 +243,17 @@ pub struct CourseKeyData {
   pub active: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct CourseInvitation {
+  pub course_invitation_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub course_id: i64,
+  pub email: String,
+  pub course_membership_kind: CourseMembershipKind,
+  pub end_time: i64,
+}
+
 #[derive(Clone, Debug)]
 pub struct CourseMembership {
   pub course_membership_id: i64,
@@ -154,6 +263,7 @@ pub struct CourseMembership {
   pub course_id: i64,
   pub course_membership_kind: CourseMembershipKind,
   pub course_key_key: Option<String>,
+  pub course_invitation_id: Option<i64>,
 }
 
 #[derive(Clone, Debug)]
@@ -176,6 +286,26 @@ pub struct SessionData {
   pub active: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct SessionNote {
+  pub session_note_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub session_id: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SessionNoteData {
+  pub session_note_data_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub session_note_id: i64,
+  pub session_note_kind: SessionNoteKind,
+  pub session_note_visibility: SessionNoteVisibility,
+  pub content: String,
+  pub active: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct SessionRequest {
   pub session_request_id: i64,
@@ -196,6 +326,115 @@ pub struct SessionRequestResponse {
   pub commitment_id: Option<i64>,
 }
 
+// a slot some of the attendees could all make
+#[derive(Clone, Debug)]
+pub struct FreeSlot {
+  pub start_time: i64,
+  pub end_time: i64,
+  pub free_attendee_user_ids: Vec<i64>,
+  pub busy_attendee_user_ids: Vec<i64>,
+}
+
+// a student's place in a homeroom, where they take daily attendance
+#[derive(Clone, Debug)]
+pub struct Homeroom {
+  pub course_id: i64,
+  pub school_id: i64,
+  pub location_id: i64,
+  pub student_user_id: i64,
+}
+
+// whether a student turned up to their homeroom on a given day
+#[derive(Clone, Debug)]
+pub struct HomeroomAttendance {
+  pub homeroom: Homeroom,
+  pub arrival_time: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Attachment {
+  pub attachment_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub session_request_id: Option<i64>,
+  pub session_id: Option<i64>,
+  pub name: String,
+  pub media_type: String,
+  pub size: i64,
+  pub checksum: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MeetingLink {
+  pub meeting_link_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub location_id: Option<i64>,
+  pub session_id: Option<i64>,
+  pub url: String,
+  pub active: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct MeetingPass {
+  pub meeting_pass_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub session_id: i64,
+  pub meeting_link_id: i64,
+  pub meeting_pass_token: String,
+  pub expiration_time: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MeetingJoin {
+  pub meeting_pass_id: i64,
+  pub creation_time: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Feedback {
+  pub feedback_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub commitment_id: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FeedbackData {
+  pub feedback_data_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub feedback_id: i64,
+  pub rating: i64,
+  pub comment: String,
+  pub active: bool,
+}
+
+// the current version of a piece of feedback, with what it was about and an instructor it counts towards
+#[derive(Clone, Debug)]
+pub struct FeedbackEntry {
+  pub course_id: i64,
+  pub instructor_user_id: i64,
+  pub session_id: i64,
+  pub attendee_user_id: i64,
+  pub feedback_data: FeedbackData,
+}
+
+#[derive(Clone, Debug)]
+pub struct SessionChange {
+  pub session_change_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub session_id: i64,
+  pub session_change_kind: SessionChangeKind,
+  pub reason: String,
+  pub old_start_time: i64,
+  pub old_end_time: i64,
+  pub new_start_time: Option<i64>,
+  pub new_end_time: Option<i64>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Commitment {
   pub commitment_id: i64,
@@ -206,6 +445,37 @@ pub struct Commitment {
   pub active: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct Availability {
+  pub availability_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub course_id: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AvailabilityData {
+  pub availability_data_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub availability_id: i64,
+  pub name: String,
+  pub start_time: i64,
+  pub end_time: i64,
+  pub slot_duration: i64,
+  pub active: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct AvailabilityBooking {
+  pub availability_booking_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub availability_id: i64,
+  pub start_time: i64,
+  pub commitment_id: i64,
+}
+
 #[derive(Clone, Debug)]
 pub struct Encounter {
   pub encounter_id: i64,
@@ -214,6 +484,8 @@ pub struct Encounter {
   pub location_id: i64,
   pub attendee_user_id: i64,
   pub encounter_kind: EncounterKind,
+  pub received_time: i64,
+  pub idempotency_key: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -223,6 +495,7 @@ pub struct Stay {
   pub creator_user_id: i64,
   pub location_id: i64,
   pub attendee_user_id: i64,
+  pub session_id: Option<i64>,
 }
 
 // left is encounter_id, right is timestamp
@@ -236,3 +509,54 @@ pub struct StayData {
   pub snd: Either<i64, i64>,
   pub active: bool,
 }
+
+// not a table: an attendee currently at a location, derived from stays and encounters
+// left is stay_id of an open stay, right is encounter_id of an unmatched sign in
+#[derive(Clone, Debug)]
+pub struct Occupant {
+  pub attendee_user_id: i64,
+  pub location_id: i64,
+  pub arrival_time: i64,
+  pub source: Either<i64, i64>,
+}
+
+// not a table: an already scheduled session that overlaps one being scheduled
+// user_id is the instructor or attendee who would be in both, location_id the room both would use
+#[derive(Clone, Debug)]
+pub struct Conflict {
+  pub conflict_kind: ConflictKind,
+  pub user_id: Option<i64>,
+  pub location_id: Option<i64>,
+  pub session_data: SessionData,
+}
+
+#[derive(Clone, Debug)]
+pub struct Irregularity {
+  pub irregularity_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub commitment_id: i64,
+  pub irregularity_kind: IrregularityKind,
+  pub start_time: Option<i64>,
+  pub end_time: Option<i64>,
+  pub active: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct HallPassCheck {
+  pub hall_pass_check_id: i64,
+  pub creation_time: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct HallPass {
+  pub hall_pass_id: i64,
+  pub creation_time: i64,
+  pub creator_user_id: i64,
+  pub attendee_user_id: i64,
+  pub origin_location_id: i64,
+  pub destination_location_id: i64,
+  pub destination_stay_id: i64,
+  pub hall_pass_reason_kind: HallPassReasonKind,
+  pub expected_return_time: i64,
+}
@@ -16,18 +16,24 @@ impl From<tokio_postgres::row::Row> for Encounter {
       encounter_kind: (row.get::<_, i64>("encounter_kind") as u8)
         .try_into()
         .unwrap(),
+      received_time: row.get("received_time"),
+      idempotency_key: row.get("idempotency_key"),
     }
   }
 }
 
+// scan_time is only provided for encounters recorded on a device before upload, otherwise it's now
 pub async fn add(
   con: &mut impl GenericClient,
   creator_user_id: i64,
   location_id: i64,
   attendee_user_id: i64,
   encounter_kind: request::EncounterKind,
+  scan_time: Option<i64>,
+  idempotency_key: Option<String>,
 ) -> Result<Encounter, tokio_postgres::Error> {
-  let creation_time = current_time_millis();
+  let received_time = current_time_millis();
+  let creation_time = scan_time.unwrap_or(received_time);
 
   let encounter_id = con
     .query_one(
@@ -37,9 +43,11 @@ pub async fn add(
            creator_user_id,
            location_id,
            attendee_user_id,
-           encounter_kind
+           encounter_kind,
+           received_time,
+           idempotency_key
        )
-       VALUES($1, $2, $3, $4, $5)
+       VALUES($1, $2, $3, $4, $5, $6, $7)
        RETURNING encounter_id
       ",
       &[
@@ -48,6 +56,8 @@ pub async fn add(
         &location_id,
         &attendee_user_id,
         &(encounter_kind.clone() as i64),
+        &received_time,
+        &idempotency_key,
       ],
     )
     .await?
@@ -61,6 +71,8 @@ pub async fn add(
     location_id,
     attendee_user_id,
     encounter_kind,
+    received_time,
+    idempotency_key,
   })
 }
 
@@ -79,6 +91,22 @@ pub async fn get_by_encounter_id(
   Ok(result)
 }
 
+pub async fn get_by_creator_user_id_idempotency_key(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  idempotency_key: &str,
+) -> Result<Option<Encounter>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM encounter_t WHERE creator_user_id=$1 AND idempotency_key=$2",
+      &[&creator_user_id, &idempotency_key],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: request::EncounterViewProps,
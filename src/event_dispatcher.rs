@@ -0,0 +1,42 @@
+use super::events;
+use super::handlers;
+use super::utils;
+use super::Config;
+use super::Db;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+// subscribes right away, so that nothing published after this returns is missed
+pub fn spawn(config: Config, db: Db) -> tokio::task::JoinHandle<()> {
+  let receiver = config.events.subscribe();
+  tokio::spawn(run(config, db, receiver))
+}
+
+// resolves every published event once and passes it on to the subscribers
+async fn run(config: Config, db: Db, mut receiver: broadcast::Receiver<events::Event>) {
+  loop {
+    let dispatched = match receiver.recv().await {
+      Ok(event) => {
+        let con = &mut *db.lock().await;
+        match handlers::dispatch_event(con, event).await {
+          Ok(dispatched) => dispatched,
+          Err(e) => {
+            utils::log(utils::Event {
+              msg: "failed to dispatch event".to_owned(),
+              source: Some(format!("{:?}", e)),
+              severity: utils::SeverityKind::Warning,
+            });
+            continue;
+          }
+        }
+      }
+      // we fell behind, so every subscriber has missed something
+      Err(RecvError::Lagged(_)) => events::Dispatched::Resync,
+      Err(RecvError::Closed) => return,
+    };
+
+    // it's fine if nobody is listening
+    let _ = config.dispatched_events.send(Arc::new(dispatched));
+  }
+}
@@ -0,0 +1,44 @@
+use super::db_types::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+// subscribers that fall further behind than this lose the oldest events
+static CHANNEL_CAPACITY: usize = 1024;
+
+// something that just happened, pushed live to subscribers who may see it
+#[derive(Clone, Debug)]
+pub enum Event {
+  Encounter(Encounter),
+  SessionRequest(SessionRequest),
+  SessionRequestResponse(SessionRequestResponse),
+  Commitment(Commitment),
+  SessionChange(SessionChange),
+  HallPassOverdue(HallPass),
+}
+
+pub fn channel() -> broadcast::Sender<Event> {
+  let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+  sender
+}
+
+// an event once it has been resolved for all subscribers at the same time
+#[derive(Debug)]
+pub enum Dispatched {
+  Visible {
+    school_id: i64,
+    course_id: Option<i64>,
+    location_id: Option<i64>,
+    // the users allowed to see it
+    audience: HashSet<i64>,
+    name: &'static str,
+    data: serde_json::Value,
+  },
+  // events were dropped before they could be resolved, so subscribers should refetch
+  Resync,
+}
+
+pub fn dispatched_channel() -> broadcast::Sender<Arc<Dispatched>> {
+  let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+  sender
+}
@@ -0,0 +1,217 @@
+use super::db_types::*;
+use super::utils;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for FeedbackData {
+  // select * from feedback_data_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> FeedbackData {
+    FeedbackData {
+      feedback_data_id: row.get("feedback_data_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      feedback_id: row.get("feedback_id"),
+      rating: row.get("rating"),
+      comment: row.get("comment"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  feedback_id: i64,
+  rating: i64,
+  comment: String,
+  active: bool,
+) -> Result<FeedbackData, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let feedback_data_id = con
+    .query_one(
+      "INSERT INTO
+       feedback_data_t(
+           creation_time,
+           creator_user_id,
+           feedback_id,
+           rating,
+           comment,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5, $6)
+       RETURNING feedback_data_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &feedback_id,
+        &rating,
+        &comment,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(FeedbackData {
+    feedback_data_id,
+    creation_time,
+    creator_user_id,
+    feedback_id,
+    rating,
+    comment,
+    active,
+  })
+}
+
+pub async fn get_by_feedback_id(
+  con: &mut impl GenericClient,
+  feedback_id: i64,
+) -> Result<Option<FeedbackData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT fd.* FROM recent_feedback_data_v fd
+      WHERE fd.feedback_id = $1
+      ",
+      &[&feedback_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the feedback, oldest first
+pub async fn get_history_by_feedback_id(
+  con: &mut impl GenericClient,
+  feedback_id: i64,
+) -> Result<Vec<FeedbackData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT fd.* FROM feedback_data_t fd
+      WHERE fd.feedback_id = $1
+      ORDER BY fd.feedback_data_id
+      ",
+      &[&feedback_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// the feedback that stands, for sessions of the given courses (or the courses the given users teach)
+// there is one entry for each instructor of the session's course, so it can be tallied per instructor
+pub async fn get_active_entries(
+  con: &mut impl GenericClient,
+  course_id: &Option<Vec<i64>>,
+  instructor_user_id: &Option<Vec<i64>>,
+  min_start_time: Option<i64>,
+  max_start_time: Option<i64>,
+) -> Result<Vec<FeedbackEntry>, tokio_postgres::Error> {
+  let instructor = request::CourseMembershipKind::Instructor as i64;
+
+  let result = con
+    .query(
+      "
+      SELECT ses.course_id, cm.user_id instructor_user_id, c.session_id, c.attendee_user_id, fd.*
+      FROM recent_feedback_data_v fd
+      JOIN feedback_t f ON fd.feedback_id = f.feedback_id
+      JOIN commitment_t c ON f.commitment_id = c.commitment_id
+      JOIN session_t ses ON c.session_id = ses.session_id
+      JOIN recent_session_data_v sd ON sd.session_id = ses.session_id
+      JOIN recent_course_membership_v cm ON cm.course_id = ses.course_id
+      WHERE 1 = 1
+      AND fd.active
+      AND cm.course_membership_kind = $5
+      AND ($1::bigint[] IS NULL OR ses.course_id = ANY($1))
+      AND ($2::bigint[] IS NULL OR cm.user_id = ANY($2))
+      AND ($3::bigint   IS NULL OR sd.start_time >= $3)
+      AND ($4::bigint   IS NULL OR sd.start_time <= $4)
+      ORDER BY ses.course_id, cm.user_id, fd.feedback_data_id
+      ",
+      &[
+        course_id,
+        instructor_user_id,
+        &min_start_time,
+        &max_start_time,
+        &instructor,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| FeedbackEntry {
+      course_id: row.get("course_id"),
+      instructor_user_id: row.get("instructor_user_id"),
+      session_id: row.get("session_id"),
+      attendee_user_id: row.get("attendee_user_id"),
+      feedback_data: row.into(),
+    })
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::FeedbackDataViewProps,
+) -> Result<Vec<FeedbackData>, tokio_postgres::Error> {
+  let sql = [
+    utils::select_versions(
+      "feedback_data_t",
+      "fd",
+      "feedback_data_id",
+      "feedback_id",
+      props.only_recent,
+      11,
+    )
+    .as_str(),
+    " JOIN feedback_t f ON fd.feedback_id = f.feedback_id",
+    " JOIN commitment_t c ON f.commitment_id = c.commitment_id",
+    " JOIN session_t ses ON c.session_id = ses.session_id",
+    " WHERE 1 = 1",
+    " AND ($1::bigint[]  IS NULL OR fd.feedback_data_id = ANY($1))",
+    " AND ($2::bigint    IS NULL OR fd.creation_time >= $2)",
+    " AND ($3::bigint    IS NULL OR fd.creation_time <= $3)",
+    " AND ($4::bigint[]  IS NULL OR fd.creator_user_id = ANY($4))",
+    " AND ($5::bigint[]  IS NULL OR fd.feedback_id = ANY($5))",
+    " AND ($6::bigint    IS NULL OR fd.rating >= $6)",
+    " AND ($7::bigint    IS NULL OR fd.rating <= $7)",
+    " AND ($8::bool      IS NULL OR fd.active = $8)",
+    " AND ($9::bigint[]  IS NULL OR c.session_id = ANY($9))",
+    " AND ($10::bigint[] IS NULL OR ses.course_id = ANY($10))",
+    " AND ($11::bigint   IS NULL OR fd.creation_time <= $11)",
+    " ORDER BY fd.feedback_data_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.feedback_data_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.feedback_id,
+        &props.min_rating,
+        &props.max_rating,
+        &props.active,
+        &props.session_id,
+        &props.course_id,
+        &props.as_of,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
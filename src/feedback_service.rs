@@ -0,0 +1,122 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Feedback {
+  // select * from feedback_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Feedback {
+    Feedback {
+      feedback_id: row.get("feedback_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      commitment_id: row.get("commitment_id"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  commitment_id: i64,
+) -> Result<Feedback, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let feedback_id = con
+    .query_one(
+      "INSERT INTO
+       feedback_t(
+           creation_time,
+           creator_user_id,
+           commitment_id
+       )
+       VALUES($1, $2, $3)
+       RETURNING feedback_id
+      ",
+      &[&creation_time, &creator_user_id, &commitment_id],
+    )
+    .await?
+    .get(0);
+
+  Ok(Feedback {
+    feedback_id,
+    creation_time,
+    creator_user_id,
+    commitment_id,
+  })
+}
+
+pub async fn get_by_feedback_id(
+  con: &mut impl GenericClient,
+  feedback_id: i64,
+) -> Result<Option<Feedback>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM feedback_t WHERE feedback_id=$1",
+      &[&feedback_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// feedback hangs off a particular version of the commitment, so look through all of them
+pub async fn get_by_attendee_user_id_session_id(
+  con: &mut impl GenericClient,
+  attendee_user_id: i64,
+  session_id: i64,
+) -> Result<Option<Feedback>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT f.* FROM feedback_t f
+      JOIN commitment_t c ON f.commitment_id = c.commitment_id
+      WHERE c.attendee_user_id = $1
+      AND c.session_id = $2
+      ",
+      &[&attendee_user_id, &session_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::FeedbackViewProps,
+) -> Result<Vec<Feedback>, tokio_postgres::Error> {
+  let results = con
+    .query(
+      "
+      SELECT f.* FROM feedback_t f
+      INNER JOIN commitment_t c ON f.commitment_id = c.commitment_id
+      INNER JOIN session_t ses ON c.session_id = ses.session_id
+      WHERE 1 = 1
+      AND ($1::bigint[] IS NULL OR f.feedback_id = ANY($1))
+      AND ($2::bigint   IS NULL OR f.creation_time >= $2)
+      AND ($3::bigint   IS NULL OR f.creation_time <= $3)
+      AND ($4::bigint[] IS NULL OR f.creator_user_id = ANY($4))
+      AND ($5::bigint[] IS NULL OR f.commitment_id = ANY($5))
+      AND ($6::bigint[] IS NULL OR c.session_id = ANY($6))
+      AND ($7::bigint[] IS NULL OR ses.course_id = ANY($7))
+      ORDER BY f.feedback_id
+      ",
+      &[
+        &props.feedback_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.commitment_id,
+        &props.session_id,
+        &props.course_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
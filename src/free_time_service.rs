@@ -0,0 +1,147 @@
+use super::closure_data_service;
+use super::conflict_service;
+use super::db_types::*;
+use super::school_duration_data_service;
+use tokio_postgres::GenericClient;
+
+static MINUTE: i64 = 60 * 1000;
+static DAY: i64 = 24 * 60 * 60 * 1000;
+
+// school durations repeat weekly: day 0 is sunday, and minutes count from midnight utc
+// there's no per school time zone, so a school away from utc stores its durations shifted
+// the epoch fell on a thursday
+fn day_of_week(day_number: i64) -> i64 {
+  (day_number + 4).rem_euclid(7)
+}
+
+// every occurrence of the school's durations within [min_time, max_time), merged and in order
+pub fn expand_school_durations(
+  school_durations: &[SchoolDurationData],
+  min_time: i64,
+  max_time: i64,
+) -> Vec<(i64, i64)> {
+  let mut windows = vec![];
+
+  for day_number in min_time.div_euclid(DAY)..=(max_time - 1).div_euclid(DAY) {
+    for sdd in school_durations
+      .iter()
+      .filter(|x| x.day == day_of_week(day_number))
+    {
+      let start_time = (day_number * DAY + sdd.minute_start * MINUTE).max(min_time);
+      let end_time = (day_number * DAY + sdd.minute_end * MINUTE).min(max_time);
+      if start_time < end_time {
+        windows.push((start_time, end_time));
+      }
+    }
+  }
+
+  windows.sort();
+
+  // durations may overlap each other
+  let mut merged: Vec<(i64, i64)> = vec![];
+  for (start_time, end_time) in windows {
+    match merged.last_mut() {
+      Some(last) if start_time <= last.1 => last.1 = last.1.max(end_time),
+      _ => merged.push((start_time, end_time)),
+    }
+  }
+  merged
+}
+
+// what's left of the windows once the holes are cut out of them
+pub fn subtract(windows: &[(i64, i64)], holes: &[(i64, i64)]) -> Vec<(i64, i64)> {
+  let mut result = vec![];
+
+  for &(start_time, end_time) in windows {
+    let mut pieces = vec![(start_time, end_time)];
+    for &(hole_start, hole_end) in holes {
+      pieces = pieces
+        .into_iter()
+        .flat_map(|(s, e)| {
+          if hole_end <= s || hole_start >= e {
+            vec![(s, e)]
+          } else {
+            [(s, hole_start), (hole_end, e)]
+              .into_iter()
+              .filter(|(s, e)| s < e)
+              .collect()
+          }
+        })
+        .collect();
+    }
+    result.extend(pieces);
+  }
+
+  result
+}
+
+// cuts the school's open time into slots, and works out who is free for each
+// slots nobody can make are left out, the rest are ranked by how many attendees are free
+pub async fn get_free_slots(
+  con: &mut impl GenericClient,
+  school_id: i64,
+  attendee_user_ids: &[i64],
+  min_time: i64,
+  max_time: i64,
+  slot_duration: i64,
+) -> Result<Vec<FreeSlot>, tokio_postgres::Error> {
+  let school_durations =
+    school_duration_data_service::get_active_by_school_id(con, school_id).await?;
+
+  let closures =
+    closure_data_service::get_active_by_school_id_in_range(con, school_id, min_time, max_time)
+      .await?
+      .into_iter()
+      .map(|x| (x.start_time, x.end_time))
+      .collect::<Vec<_>>();
+
+  let windows = subtract(
+    &expand_school_durations(&school_durations, min_time, max_time),
+    &closures,
+  );
+
+  let busy =
+    conflict_service::get_by_attendee_user_ids(con, attendee_user_ids, None, min_time, max_time)
+      .await?;
+
+  let mut slots = vec![];
+  for (window_start, window_end) in windows {
+    let mut start_time = window_start;
+    while start_time + slot_duration <= window_end {
+      let end_time = start_time + slot_duration;
+
+      let (busy_attendee_user_ids, free_attendee_user_ids): (Vec<i64>, Vec<i64>) =
+        attendee_user_ids
+          .iter()
+          .copied()
+          .partition(|&attendee_user_id| {
+            busy.iter().any(|x| {
+              x.user_id == Some(attendee_user_id)
+                && x.session_data.start_time < end_time
+                && x.session_data.end_time > start_time
+            })
+          });
+
+      if !free_attendee_user_ids.is_empty() {
+        slots.push(FreeSlot {
+          start_time,
+          end_time,
+          free_attendee_user_ids,
+          busy_attendee_user_ids,
+        });
+      }
+
+      start_time = end_time;
+    }
+  }
+
+  // earliest first among slots that are equally good
+  slots.sort_by(|a, b| {
+    b.free_attendee_user_ids
+      .len()
+      .cmp(&a.free_attendee_user_ids.len())
+      .then(a.start_time.cmp(&b.start_time))
+  });
+
+  Ok(slots)
+}
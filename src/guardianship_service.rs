@@ -0,0 +1,243 @@
+use super::db_types::*;
+use super::utils;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use std::convert::TryInto;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Guardianship {
+  // select * from guardianship_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Guardianship {
+    Guardianship {
+      guardianship_id: row.get("guardianship_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      guardian_user_id: row.get("guardian_user_id"),
+      student_user_id: row.get("student_user_id"),
+      guardianship_kind: (row.get::<_, i64>("guardianship_kind") as u8)
+        .try_into()
+        .unwrap(),
+      weekly_summary: row.get("weekly_summary"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  guardian_user_id: i64,
+  student_user_id: i64,
+  guardianship_kind: request::GuardianshipKind,
+  weekly_summary: bool,
+) -> Result<Guardianship, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let guardianship_id = con
+    .query_one(
+      "INSERT INTO
+       guardianship_t(
+           creation_time,
+           creator_user_id,
+           guardian_user_id,
+           student_user_id,
+           guardianship_kind,
+           weekly_summary
+       )
+       VALUES ($1, $2, $3, $4, $5, $6)
+       RETURNING guardianship_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &guardian_user_id,
+        &student_user_id,
+        &(guardianship_kind.clone() as i64),
+        &weekly_summary,
+      ],
+    )
+    .await?
+    .get(0);
+
+  // return guardianship
+  Ok(Guardianship {
+    guardianship_id,
+    creation_time,
+    creator_user_id,
+    guardian_user_id,
+    student_user_id,
+    guardianship_kind,
+    weekly_summary,
+  })
+}
+
+pub async fn get_by_guardian_user_id_student_user_id(
+  con: &mut impl GenericClient,
+  guardian_user_id: i64,
+  student_user_id: i64,
+) -> Result<Option<Guardianship>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT g.* FROM recent_guardianship_v g
+      WHERE 1 = 1
+      AND g.guardian_user_id = $1
+      AND g.student_user_id = $2
+      ",
+      &[&guardian_user_id, &student_user_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// if guardian_user_id may currently read student_user_id's records
+pub async fn is_guardian(
+  con: &mut impl GenericClient,
+  guardian_user_id: i64,
+  student_user_id: i64,
+) -> Result<bool, tokio_postgres::Error> {
+  let result = matches!(
+    get_by_guardian_user_id_student_user_id(con, guardian_user_id, student_user_id).await?,
+    Some(Guardianship {
+      guardianship_kind: request::GuardianshipKind::Active,
+      ..
+    })
+  );
+
+  Ok(result)
+}
+
+// everyone who may currently read student_user_id's records as their guardian
+pub async fn get_guardian_user_ids(
+  con: &mut impl GenericClient,
+  student_user_id: i64,
+) -> Result<Vec<i64>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT g.guardian_user_id FROM recent_guardianship_v g
+      WHERE 1 = 1
+      AND g.student_user_id = $1
+      AND g.guardianship_kind = $2
+      ",
+      &[
+        &student_user_id,
+        &(request::GuardianshipKind::Active as i64),
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.get(0))
+    .collect();
+
+  Ok(result)
+}
+
+// if admin_user_id is an admin of a school where student_user_id is in a course
+pub async fn is_admin_of_student(
+  con: &mut impl GenericClient,
+  admin_user_id: i64,
+  student_user_id: i64,
+) -> Result<bool, tokio_postgres::Error> {
+  let result: i64 = con
+    .query_one(
+      "
+      SELECT count(cm.*)
+      FROM recent_course_membership_v cm
+      JOIN course_t c ON c.course_id = cm.course_id
+      JOIN recent_adminship_v a ON a.school_id = c.school_id
+      WHERE 1 = 1
+      AND cm.user_id = $1
+      AND cm.course_membership_kind = $2
+      AND a.user_id = $3
+      AND a.adminship_kind = $4
+      ",
+      &[
+        &student_user_id,
+        &(request::CourseMembershipKind::Student as i64),
+        &admin_user_id,
+        &(request::AdminshipKind::Admin as i64),
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(result > 0)
+}
+
+pub async fn get_weekly_summary_recipients(
+  con: &mut impl GenericClient,
+) -> Result<Vec<Guardianship>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT g.* FROM recent_guardianship_v g
+      WHERE 1 = 1
+      AND g.guardianship_kind = $1
+      AND g.weekly_summary
+      ",
+      &[&(request::GuardianshipKind::Active as i64)],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::GuardianshipViewProps,
+) -> Result<Vec<Guardianship>, tokio_postgres::Error> {
+  let sql = [
+    utils::select_versions(
+      "guardianship_t",
+      "g",
+      "guardianship_id",
+      "guardian_user_id, student_user_id",
+      props.only_recent,
+      9,
+    )
+    .as_str(),
+    " WHERE 1 = 1",
+    " AND ($1::bigint[] IS NULL OR g.guardianship_id = ANY($1))",
+    " AND ($2::bigint   IS NULL OR g.creation_time >= $2)",
+    " AND ($3::bigint   IS NULL OR g.creation_time <= $3)",
+    " AND ($4::bigint[] IS NULL OR g.creator_user_id = ANY($4))",
+    " AND ($5::bigint[] IS NULL OR g.guardian_user_id = ANY($5))",
+    " AND ($6::bigint[] IS NULL OR g.student_user_id = ANY($6))",
+    " AND ($7::bigint[] IS NULL OR g.guardianship_kind = ANY($7))",
+    " AND ($8::bool     IS NULL OR g.weekly_summary = $8)",
+    " AND ($9::bigint   IS NULL OR g.creation_time <= $9)",
+    " ORDER BY g.guardianship_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.guardianship_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.guardian_user_id,
+        &props.student_user_id,
+        &props
+          .guardianship_kind
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.weekly_summary,
+        &props.as_of,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
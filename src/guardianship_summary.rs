@@ -0,0 +1,159 @@
+use super::auth::AuthService;
+use super::commitment_service;
+use super::guardianship_service;
+use super::guardianship_summary_run_service;
+use super::stay_service;
+use super::utils;
+use super::Config;
+use super::Db;
+use std::error::Error;
+use std::time::Duration;
+
+static ONE_WEEK_MILLIS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+// how often to check whether a week has gone by since the last run
+static CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// what one guardian gets told about one student
+struct Summary {
+  guardianship_id: i64,
+  guardian_user_id: i64,
+  student_user_id: i64,
+  num_commitments: usize,
+  num_stays: usize,
+}
+
+// every week, email each opted-in guardian a digest of their student's activity
+// the last run is kept in the database, so restarts neither skip nor repeat a week
+pub async fn run(config: Config, db: Db, auth_service: AuthService) {
+  loop {
+    if let Err(e) = send_summaries_if_due(&config, &db, &auth_service).await {
+      utils::log(utils::Event {
+        msg: e.to_string(),
+        source: e.source().map(|x| x.to_string()),
+        severity: utils::SeverityKind::Error,
+      });
+    }
+    tokio::time::sleep(CHECK_INTERVAL).await;
+  }
+}
+
+async fn send_summaries_if_due(
+  config: &Config,
+  db: &Db,
+  auth_service: &AuthService,
+) -> Result<(), Box<dyn Error>> {
+  // gather everything first, mail is slow and nobody else can use the database while we hold it
+  let summaries = {
+    let con = &mut *db.lock().await;
+    let mut sp = con.transaction().await?;
+
+    let min_time = match guardianship_summary_run_service::lock_latest(&mut sp).await? {
+      Some(last_run)
+        if last_run.creation_time + ONE_WEEK_MILLIS <= utils::current_time_millis() =>
+      {
+        last_run.creation_time
+      }
+      Some(_) => return Ok(()),
+      // the very first run only starts the clock
+      None => {
+        guardianship_summary_run_service::add(&mut sp).await?;
+        sp.commit().await?;
+        return Ok(());
+      }
+    };
+
+    // recorded before sending, a crash partway through loses some mail rather than repeating it
+    let max_time = guardianship_summary_run_service::add(&mut sp)
+      .await?
+      .creation_time;
+
+    let mut summaries = vec![];
+    for guardianship in guardianship_service::get_weekly_summary_recipients(&mut sp).await? {
+      let commitments = commitment_service::get_active_by_attendee_user_id_in_range(
+        &mut sp,
+        guardianship.student_user_id,
+        min_time,
+        max_time,
+      )
+      .await?;
+
+      let stays = stay_service::get_by_attendee_user_id_in_range(
+        &mut sp,
+        guardianship.student_user_id,
+        min_time,
+        max_time,
+      )
+      .await?;
+
+      summaries.push(Summary {
+        guardianship_id: guardianship.guardianship_id,
+        guardian_user_id: guardianship.guardian_user_id,
+        student_user_id: guardianship.student_user_id,
+        num_commitments: commitments.len(),
+        num_stays: stays.len(),
+      });
+    }
+
+    sp.commit().await?;
+    summaries
+  };
+
+  for summary in summaries {
+    // a failure for one guardian shouldn't stop the rest of the summaries
+    if let Err(e) = send_summary(config, auth_service, &summary).await {
+      utils::log(utils::Event {
+        msg: e,
+        source: Some(format!("guardianship {}", summary.guardianship_id)),
+        severity: utils::SeverityKind::Warning,
+      });
+    }
+  }
+
+  Ok(())
+}
+
+async fn send_summary(
+  config: &Config,
+  auth_service: &AuthService,
+  summary: &Summary,
+) -> Result<(), String> {
+  let guardian_email = auth_service
+    .get_email_by_user_id(summary.guardian_user_id)
+    .await
+    .map_err(|e| format!("{:?}", e))?;
+
+  let student_name = auth_service
+    .get_name_by_user_id(summary.student_user_id)
+    .await
+    .map_err(|e| format!("{:?}", e))?;
+
+  config
+    .mail_service
+    .mail_new(mail_service_api::request::MailNewProps {
+      request_id: rand::random(),
+      destination: guardian_email,
+      topic: "guardianship_summary".to_owned(),
+      title: format!("Weekly Innexgo Hours Summary for {}", student_name),
+      content: [
+        format!(
+          "<p>Here is what {} did this past week:</p>",
+          utils::escape_html(&student_name)
+        ),
+        format!(
+          "<p>Office hours sessions committed to: {}</p>",
+          summary.num_commitments
+        ),
+        format!("<p>Visits recorded: {}</p>", summary.num_stays),
+        format!(
+          "<p>See <a href=\"{}/dashboard\">Innexgo Hours</a> for the full record.</p>",
+          config.site_external_url
+        ),
+      ]
+      .join(""),
+    })
+    .await
+    .map_err(|e| format!("{:?}", e))?;
+
+  Ok(())
+}
@@ -0,0 +1,61 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for GuardianshipSummaryRun {
+  // select * from guardianship_summary_run_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> GuardianshipSummaryRun {
+    GuardianshipSummaryRun {
+      guardianship_summary_run_id: row.get("guardianship_summary_run_id"),
+      creation_time: row.get("creation_time"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+) -> Result<GuardianshipSummaryRun, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let guardianship_summary_run_id = con
+    .query_one(
+      "INSERT INTO
+       guardianship_summary_run_t(
+           creation_time
+       )
+       VALUES($1)
+       RETURNING guardianship_summary_run_id
+      ",
+      &[&creation_time],
+    )
+    .await?
+    .get(0);
+
+  Ok(GuardianshipSummaryRun {
+    guardianship_summary_run_id,
+    creation_time,
+  })
+}
+
+// locks the table until the end of the transaction, so that two instances can't both decide a run is due
+pub async fn lock_latest(
+  con: &mut impl GenericClient,
+) -> Result<Option<GuardianshipSummaryRun>, tokio_postgres::Error> {
+  con
+    .batch_execute("LOCK TABLE guardianship_summary_run_t IN EXCLUSIVE MODE")
+    .await?;
+
+  let result = con
+    .query_opt(
+      "
+      SELECT gsr.* FROM guardianship_summary_run_t gsr
+      ORDER BY gsr.guardianship_summary_run_id DESC
+      LIMIT 1
+      ",
+      &[],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
@@ -0,0 +1,59 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for HallPassCheck {
+  // select * from hall_pass_check_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> HallPassCheck {
+    HallPassCheck {
+      hall_pass_check_id: row.get("hall_pass_check_id"),
+      creation_time: row.get("creation_time"),
+    }
+  }
+}
+
+pub async fn add(con: &mut impl GenericClient) -> Result<HallPassCheck, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let hall_pass_check_id = con
+    .query_one(
+      "INSERT INTO
+       hall_pass_check_t(
+           creation_time
+       )
+       VALUES($1)
+       RETURNING hall_pass_check_id
+      ",
+      &[&creation_time],
+    )
+    .await?
+    .get(0);
+
+  Ok(HallPassCheck {
+    hall_pass_check_id,
+    creation_time,
+  })
+}
+
+// locks the table until the end of the transaction, so that two instances can't both check the same window
+pub async fn lock_latest(
+  con: &mut impl GenericClient,
+) -> Result<Option<HallPassCheck>, tokio_postgres::Error> {
+  con
+    .batch_execute("LOCK TABLE hall_pass_check_t IN EXCLUSIVE MODE")
+    .await?;
+
+  let result = con
+    .query_opt(
+      "
+      SELECT hpc.* FROM hall_pass_check_t hpc
+      ORDER BY hpc.hall_pass_check_id DESC
+      LIMIT 1
+      ",
+      &[],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
@@ -0,0 +1,57 @@
+use super::events;
+use super::hall_pass_check_service;
+use super::hall_pass_service;
+use super::utils;
+use super::Config;
+use super::Db;
+use std::error::Error;
+use std::time::Duration;
+
+static CHECK_INTERVAL_MILLIS: i64 = 60 * 1000;
+
+// pushes an alert to subscribed dashboards whenever a hall pass runs past its expected return time
+// the last check is kept in the database, so passes that came due while the server was down still alert
+pub async fn run(config: Config, db: Db) {
+  let mut interval = tokio::time::interval(Duration::from_millis(CHECK_INTERVAL_MILLIS as u64));
+
+  loop {
+    interval.tick().await;
+    // a failed check isn't recorded, so the same window is tried again next time
+    if let Err(e) = alert_overdue(&config, &db).await {
+      utils::log(utils::Event {
+        msg: e.to_string(),
+        source: e.source().map(|x| x.to_string()),
+        severity: utils::SeverityKind::Error,
+      });
+    }
+  }
+}
+
+async fn alert_overdue(config: &Config, db: &Db) -> Result<(), Box<dyn Error>> {
+  let overdue = {
+    let con = &mut *db.lock().await;
+    let mut sp = con.transaction().await?;
+
+    // the very first check looks at every pass there has ever been
+    let min_time = hall_pass_check_service::lock_latest(&mut sp)
+      .await?
+      .map_or(0, |x| x.creation_time);
+
+    let max_time = hall_pass_check_service::add(&mut sp).await?.creation_time;
+
+    let overdue = hall_pass_service::get_overdue(&mut sp, min_time, max_time).await?;
+
+    // recorded before alerting, a crash in between loses some alerts rather than repeating them
+    sp.commit().await?;
+    overdue
+  };
+
+  for hall_pass in overdue {
+    // it's fine if nobody is listening
+    let _ = config
+      .events
+      .send(events::Event::HallPassOverdue(hall_pass));
+  }
+
+  Ok(())
+}
@@ -0,0 +1,199 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use std::convert::TryInto;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for HallPass {
+  // select * from hall_pass_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> HallPass {
+    HallPass {
+      hall_pass_id: row.get("hall_pass_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      attendee_user_id: row.get("attendee_user_id"),
+      origin_location_id: row.get("origin_location_id"),
+      destination_location_id: row.get("destination_location_id"),
+      destination_stay_id: row.get("destination_stay_id"),
+      hall_pass_reason_kind: (row.get::<_, i64>("hall_pass_reason_kind") as u8)
+        .try_into()
+        .unwrap(),
+      expected_return_time: row.get("expected_return_time"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  attendee_user_id: i64,
+  origin_location_id: i64,
+  destination_location_id: i64,
+  destination_stay_id: i64,
+  hall_pass_reason_kind: request::HallPassReasonKind,
+  expected_return_time: i64,
+) -> Result<HallPass, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let hall_pass_id = con
+    .query_one(
+      "INSERT INTO
+       hall_pass_t(
+           creation_time,
+           creator_user_id,
+           attendee_user_id,
+           origin_location_id,
+           destination_location_id,
+           destination_stay_id,
+           hall_pass_reason_kind,
+           expected_return_time
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+       RETURNING hall_pass_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &attendee_user_id,
+        &origin_location_id,
+        &destination_location_id,
+        &destination_stay_id,
+        &(hall_pass_reason_kind.clone() as i64),
+        &expected_return_time,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(HallPass {
+    hall_pass_id,
+    creation_time,
+    creator_user_id,
+    attendee_user_id,
+    origin_location_id,
+    destination_location_id,
+    destination_stay_id,
+    hall_pass_reason_kind,
+    expected_return_time,
+  })
+}
+
+pub async fn get_by_hall_pass_id(
+  con: &mut impl GenericClient,
+  hall_pass_id: i64,
+) -> Result<Option<HallPass>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM hall_pass_t WHERE hall_pass_id=$1",
+      &[&hall_pass_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// when the student was first seen back at the origin, if they have been
+pub async fn get_return_time(
+  con: &mut impl GenericClient,
+  hall_pass: &HallPass,
+) -> Result<Option<i64>, tokio_postgres::Error> {
+  let result = con
+    .query_one(
+      "
+      SELECT min(e.creation_time) FROM encounter_t e
+      WHERE 1 = 1
+      AND e.attendee_user_id = $1
+      AND e.location_id = $2
+      AND e.creation_time > $3
+      ",
+      &[
+        &hall_pass.attendee_user_id,
+        &hall_pass.origin_location_id,
+        &hall_pass.creation_time,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(result)
+}
+
+// passes that came due in (min_time, max_time] and whose student hasn't come back
+pub async fn get_overdue(
+  con: &mut impl GenericClient,
+  min_time: i64,
+  max_time: i64,
+) -> Result<Vec<HallPass>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT hp.* FROM hall_pass_t hp
+      WHERE 1 = 1
+      AND hp.expected_return_time > $1
+      AND hp.expected_return_time <= $2
+      AND NOT EXISTS (
+        SELECT 1 FROM encounter_t e
+        WHERE e.attendee_user_id = hp.attendee_user_id
+        AND e.location_id = hp.origin_location_id
+        AND e.creation_time > hp.creation_time
+      )
+      ORDER BY hp.hall_pass_id
+      ",
+      &[&min_time, &max_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::HallPassViewProps,
+) -> Result<Vec<HallPass>, tokio_postgres::Error> {
+  let sql = "
+    SELECT hp.* FROM hall_pass_t hp
+    WHERE 1 = 1
+    AND ($1::bigint[] IS NULL OR hp.hall_pass_id = ANY($1))
+    AND ($2::bigint   IS NULL OR hp.creation_time >= $2)
+    AND ($3::bigint   IS NULL OR hp.creation_time <= $3)
+    AND ($4::bigint[] IS NULL OR hp.creator_user_id = ANY($4))
+    AND ($5::bigint[] IS NULL OR hp.attendee_user_id = ANY($5))
+    AND ($6::bigint[] IS NULL OR hp.origin_location_id = ANY($6))
+    AND ($7::bigint[] IS NULL OR hp.destination_location_id = ANY($7))
+    AND ($8::bigint[] IS NULL OR hp.hall_pass_reason_kind = ANY($8))
+    AND ($9::bigint   IS NULL OR hp.expected_return_time >= $9)
+    AND ($10::bigint  IS NULL OR hp.expected_return_time <= $10)
+    ORDER BY hp.hall_pass_id
+  ";
+
+  let stmnt = con.prepare(sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.hall_pass_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.attendee_user_id,
+        &props.origin_location_id,
+        &props.destination_location_id,
+        &props
+          .hall_pass_reason_kind
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.min_expected_return_time,
+        &props.max_expected_return_time,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(results)
+}
@@ -1,7 +1,8 @@
+use super::auth::AuthService;
 use super::Db;
-use auth_service_api::client::AuthService;
 use auth_service_api::response::AuthError;
 use auth_service_api::response::User;
+use mail_service_api::response::MailError;
 
 use innexgo_hours_api::request;
 use innexgo_hours_api::response;
@@ -12,31 +13,66 @@ use super::utils;
 // db
 
 use super::adminship_service;
+use super::attachment_service;
+use super::availability_booking_service;
+use super::availability_data_service;
+use super::availability_service;
+use super::checkin_attempt_service;
+use super::closure_data_service;
+use super::closure_service;
 use super::commitment_service;
+use super::conflict_service;
 use super::course_data_service;
+use super::course_invitation_service;
 use super::course_key_data_service;
 use super::course_key_service;
 use super::course_membership_service;
+use super::course_membership_service::CourseCapability;
 use super::course_service;
 use super::encounter_service;
+use super::events;
+use super::feedback_data_service;
+use super::feedback_service;
+use super::free_time_service;
+use super::guardianship_service;
+use super::hall_pass_service;
+use super::homeroom_service;
+use super::irregularity_service;
+use super::location_checkin_service;
 use super::location_data_service;
 use super::location_service;
+use super::meeting_join_service;
+use super::meeting_link_service;
+use super::meeting_pass_service;
+use super::occupancy_service;
 use super::school_data_service;
 use super::school_duration_data_service;
 use super::school_duration_service;
+use super::school_invitation_service;
 use super::school_key_data_service;
 use super::school_key_service;
 use super::school_service;
+use super::session_change_service;
 use super::session_data_service;
+use super::session_note_data_service;
+use super::session_note_service;
 use super::session_request_response_service;
 use super::session_request_service;
 use super::session_service;
 use super::stay_data_service;
 use super::stay_service;
 use super::subscription_service;
+use super::term_data_service;
+use super::term_service;
 
 use either::*;
+use futures_util::Stream;
+use std::collections::HashSet;
+use std::convert::Infallible;
 use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use tokio_postgres::GenericClient;
 
 use super::Config;
@@ -50,6 +86,20 @@ fn report_postgres_err(e: tokio_postgres::Error) -> response::InnexgoHoursError
   response::InnexgoHoursError::InternalServerError
 }
 
+// another row already holds the value of a unique column
+fn is_unique_violation(e: &tokio_postgres::Error) -> bool {
+  e.code() == Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION)
+}
+
+fn report_blob_err(e: std::io::Error) -> response::InnexgoHoursError {
+  utils::log(utils::Event {
+    msg: e.to_string(),
+    source: e.source().map(|e| e.to_string()),
+    severity: utils::SeverityKind::Error,
+  });
+  response::InnexgoHoursError::InternalServerError
+}
+
 fn report_auth_err(e: AuthError) -> response::InnexgoHoursError {
   match e {
     AuthError::ApiKeyNonexistent => response::InnexgoHoursError::ApiKeyUnauthorized,
@@ -73,6 +123,38 @@ fn report_auth_err(e: AuthError) -> response::InnexgoHoursError {
   }
 }
 
+fn report_mail_err(e: MailError) -> response::InnexgoHoursError {
+  let ie = match e {
+    MailError::DestinationBounced => response::InnexgoHoursError::EmailBounced,
+    MailError::DestinationProhibited => response::InnexgoHoursError::EmailBounced,
+    _ => response::InnexgoHoursError::EmailUnknown,
+  };
+  utils::log(utils::Event {
+    msg: ie.as_ref().to_owned(),
+    source: Some(format!("mail service: {}", e.as_ref())),
+    severity: utils::SeverityKind::Error,
+  });
+  ie
+}
+
+fn report_qr_err(e: qrcode::types::QrError) -> response::InnexgoHoursError {
+  utils::log(utils::Event {
+    msg: e.to_string(),
+    source: Some("qr code render".to_owned()),
+    severity: utils::SeverityKind::Error,
+  });
+  response::InnexgoHoursError::InternalServerError
+}
+
+fn report_serde_err(e: serde_json::Error) -> response::InnexgoHoursError {
+  utils::log(utils::Event {
+    msg: e.to_string(),
+    source: Some("json serialize".to_owned()),
+    severity: utils::SeverityKind::Error,
+  });
+  response::InnexgoHoursError::InternalServerError
+}
+
 async fn fill_subscription(
   _con: &mut impl GenericClient,
   subscription: Subscription,
@@ -159,6 +241,82 @@ async fn fill_school_duration_data(
   })
 }
 
+async fn fill_term(
+  con: &mut impl GenericClient,
+  term: Term,
+) -> Result<response::Term, response::InnexgoHoursError> {
+  let school = school_service::get_by_school_id(con, term.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  Ok(response::Term {
+    term_id: term.term_id,
+    creation_time: term.creation_time,
+    creator_user_id: term.creator_user_id,
+    school: fill_school(con, school).await?,
+  })
+}
+
+async fn fill_term_data(
+  con: &mut impl GenericClient,
+  term_data: TermData,
+) -> Result<response::TermData, response::InnexgoHoursError> {
+  let term = term_service::get_by_term_id(con, term_data.term_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::TermNonexistent)?;
+
+  Ok(response::TermData {
+    term_data_id: term_data.term_data_id,
+    creation_time: term_data.creation_time,
+    creator_user_id: term_data.creator_user_id,
+    term: fill_term(con, term).await?,
+    name: term_data.name,
+    start_time: term_data.start_time,
+    end_time: term_data.end_time,
+    active: term_data.active,
+  })
+}
+
+async fn fill_closure(
+  con: &mut impl GenericClient,
+  closure: Closure,
+) -> Result<response::Closure, response::InnexgoHoursError> {
+  let school = school_service::get_by_school_id(con, closure.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  Ok(response::Closure {
+    closure_id: closure.closure_id,
+    creation_time: closure.creation_time,
+    creator_user_id: closure.creator_user_id,
+    school: fill_school(con, school).await?,
+  })
+}
+
+async fn fill_closure_data(
+  con: &mut impl GenericClient,
+  closure_data: ClosureData,
+) -> Result<response::ClosureData, response::InnexgoHoursError> {
+  let closure = closure_service::get_by_closure_id(con, closure_data.closure_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::ClosureNonexistent)?;
+
+  Ok(response::ClosureData {
+    closure_data_id: closure_data.closure_data_id,
+    creation_time: closure_data.creation_time,
+    creator_user_id: closure_data.creator_user_id,
+    closure: fill_closure(con, closure).await?,
+    name: closure_data.name,
+    start_time: closure_data.start_time,
+    end_time: closure_data.end_time,
+    active: closure_data.active,
+  })
+}
+
 async fn fill_school_key(
   con: &mut impl GenericClient,
   school_key: SchoolKey,
@@ -175,6 +333,7 @@ async fn fill_school_key(
     school: fill_school(con, school).await?,
     start_time: school_key.start_time,
     end_time: school_key.end_time,
+    short_code: school_key.short_code,
   })
 }
 
@@ -196,6 +355,25 @@ async fn fill_school_key_data(
   })
 }
 
+async fn fill_school_invitation(
+  con: &mut impl GenericClient,
+  school_invitation: SchoolInvitation,
+) -> Result<response::SchoolInvitation, response::InnexgoHoursError> {
+  let school = school_service::get_by_school_id(con, school_invitation.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  Ok(response::SchoolInvitation {
+    school_invitation_id: school_invitation.school_invitation_id,
+    creation_time: school_invitation.creation_time,
+    creator_user_id: school_invitation.creator_user_id,
+    school: fill_school(con, school).await?,
+    email: school_invitation.email,
+    end_time: school_invitation.end_time,
+  })
+}
+
 async fn fill_adminship(
   con: &mut impl GenericClient,
   adminship: Adminship,
@@ -211,6 +389,18 @@ async fn fill_adminship(
     _ => None,
   };
 
+  let school_invitation = match adminship.school_invitation_id {
+    Some(school_invitation_id) => {
+      let school_invitation =
+        school_invitation_service::get_by_school_invitation_id(con, school_invitation_id)
+          .await
+          .map_err(report_postgres_err)?
+          .ok_or(response::InnexgoHoursError::SchoolInvitationNonexistent)?;
+      Some(fill_school_invitation(con, school_invitation).await?)
+    }
+    _ => None,
+  };
+
   let school = school_service::get_by_school_id(con, adminship.school_id)
     .await
     .map_err(report_postgres_err)?
@@ -224,6 +414,7 @@ async fn fill_adminship(
     school: fill_school(con, school).await?,
     adminship_kind: adminship.adminship_kind,
     school_key,
+    school_invitation,
   })
 }
 
@@ -265,6 +456,25 @@ async fn fill_location_data(
   })
 }
 
+// never expose the secret, only the codes derived from it
+async fn fill_location_checkin(
+  con: &mut impl GenericClient,
+  location_checkin: LocationCheckin,
+) -> Result<response::LocationCheckin, response::InnexgoHoursError> {
+  let location = location_service::get_by_location_id(con, location_checkin.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  Ok(response::LocationCheckin {
+    location_checkin_id: location_checkin.location_checkin_id,
+    creation_time: location_checkin.creation_time,
+    creator_user_id: location_checkin.creator_user_id,
+    location: fill_location(con, location).await?,
+    active: location_checkin.active,
+  })
+}
+
 async fn fill_course(
   con: &mut impl GenericClient,
   course: Course,
@@ -296,12 +506,24 @@ async fn fill_course_data(
     .map_err(report_postgres_err)?
     .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
 
+  let term = match course_data.term_id {
+    Some(term_id) => {
+      let term = term_service::get_by_term_id(con, term_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::TermNonexistent)?;
+      Some(fill_term(con, term).await?)
+    }
+    None => None,
+  };
+
   Ok(response::CourseData {
     course_data_id: course_data.course_data_id,
     creation_time: course_data.creation_time,
     creator_user_id: course_data.creator_user_id,
     course: fill_course(con, course).await?,
     location: fill_location(con, location).await?,
+    term,
     name: course_data.name,
     description: course_data.description,
     homeroom: course_data.homeroom,
@@ -327,6 +549,7 @@ async fn fill_course_key(
     course_membership_kind: course_key.course_membership_kind,
     start_time: course_key.start_time,
     end_time: course_key.end_time,
+    short_code: course_key.short_code,
   })
 }
 
@@ -348,6 +571,26 @@ async fn fill_course_key_data(
   })
 }
 
+async fn fill_course_invitation(
+  con: &mut impl GenericClient,
+  course_invitation: CourseInvitation,
+) -> Result<response::CourseInvitation, response::InnexgoHoursError> {
+  let course = course_service::get_by_course_id(con, course_invitation.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  Ok(response::CourseInvitation {
+    course_invitation_id: course_invitation.course_invitation_id,
+    creation_time: course_invitation.creation_time,
+    creator_user_id: course_invitation.creator_user_id,
+    course: fill_course(con, course).await?,
+    email: course_invitation.email,
+    course_membership_kind: course_invitation.course_membership_kind,
+    end_time: course_invitation.end_time,
+  })
+}
+
 async fn fill_course_membership(
   con: &mut impl GenericClient,
   course_membership: CourseMembership,
@@ -369,6 +612,19 @@ async fn fill_course_membership(
     _ => None,
   };
 
+  let course_invitation = match course_membership.course_invitation_id {
+    Some(course_invitation_id) => {
+      let course_invitation =
+        course_invitation_service::get_by_course_invitation_id(con, course_invitation_id)
+          .await
+          .map_err(report_postgres_err)?
+          .ok_or(response::InnexgoHoursError::CourseInvitationNonexistent)?;
+
+      Some(fill_course_invitation(con, course_invitation).await?)
+    }
+    _ => None,
+  };
+
   Ok(response::CourseMembership {
     course_membership_id: course_membership.course_membership_id,
     creation_time: course_membership.creation_time,
@@ -377,6 +633,7 @@ async fn fill_course_membership(
     course: fill_course(con, course).await?,
     course_membership_kind: course_membership.course_membership_kind,
     course_key,
+    course_invitation,
   })
 }
 
@@ -418,6 +675,185 @@ async fn fill_session_data(
   })
 }
 
+async fn fill_session_change(
+  con: &mut impl GenericClient,
+  session_change: SessionChange,
+) -> Result<response::SessionChange, response::InnexgoHoursError> {
+  let session = session_service::get_by_session_id(con, session_change.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  Ok(response::SessionChange {
+    session_change_id: session_change.session_change_id,
+    creation_time: session_change.creation_time,
+    creator_user_id: session_change.creator_user_id,
+    session: fill_session(con, session).await?,
+    session_change_kind: session_change.session_change_kind,
+    reason: session_change.reason,
+    old_start_time: session_change.old_start_time,
+    old_end_time: session_change.old_end_time,
+    new_start_time: session_change.new_start_time,
+    new_end_time: session_change.new_end_time,
+  })
+}
+
+async fn fill_session_note(
+  con: &mut impl GenericClient,
+  session_note: SessionNote,
+) -> Result<response::SessionNote, response::InnexgoHoursError> {
+  let session = session_service::get_by_session_id(con, session_note.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  Ok(response::SessionNote {
+    session_note_id: session_note.session_note_id,
+    creation_time: session_note.creation_time,
+    creator_user_id: session_note.creator_user_id,
+    session: fill_session(con, session).await?,
+  })
+}
+
+async fn fill_session_note_data(
+  con: &mut impl GenericClient,
+  session_note_data: SessionNoteData,
+) -> Result<response::SessionNoteData, response::InnexgoHoursError> {
+  let session_note =
+    session_note_service::get_by_session_note_id(con, session_note_data.session_note_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SessionNoteNonexistent)?;
+
+  Ok(response::SessionNoteData {
+    session_note_data_id: session_note_data.session_note_data_id,
+    creation_time: session_note_data.creation_time,
+    creator_user_id: session_note_data.creator_user_id,
+    session_note: fill_session_note(con, session_note).await?,
+    session_note_kind: session_note_data.session_note_kind,
+    session_note_visibility: session_note_data.session_note_visibility,
+    content: session_note_data.content,
+    active: session_note_data.active,
+  })
+}
+
+async fn fill_attachment(
+  con: &mut impl GenericClient,
+  attachment: Attachment,
+) -> Result<response::Attachment, response::InnexgoHoursError> {
+  let session_request = match attachment.session_request_id {
+    Some(session_request_id) => {
+      let session_request =
+        session_request_service::get_by_session_request_id(con, session_request_id)
+          .await
+          .map_err(report_postgres_err)?
+          .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
+
+      Some(fill_session_request(con, session_request).await?)
+    }
+    _ => None,
+  };
+
+  let session = match attachment.session_id {
+    Some(session_id) => {
+      let session = session_service::get_by_session_id(con, session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+      Some(fill_session(con, session).await?)
+    }
+    _ => None,
+  };
+
+  Ok(response::Attachment {
+    attachment_id: attachment.attachment_id,
+    creation_time: attachment.creation_time,
+    creator_user_id: attachment.creator_user_id,
+    session_request,
+    session,
+    name: attachment.name,
+    media_type: attachment.media_type,
+    size: attachment.size,
+    checksum: attachment.checksum,
+  })
+}
+
+async fn fill_meeting_link(
+  con: &mut impl GenericClient,
+  meeting_link: MeetingLink,
+) -> Result<response::MeetingLink, response::InnexgoHoursError> {
+  let location = match meeting_link.location_id {
+    Some(location_id) => {
+      let location = location_service::get_by_location_id(con, location_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+      Some(fill_location(con, location).await?)
+    }
+    _ => None,
+  };
+
+  let session = match meeting_link.session_id {
+    Some(session_id) => {
+      let session = session_service::get_by_session_id(con, session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+      Some(fill_session(con, session).await?)
+    }
+    _ => None,
+  };
+
+  Ok(response::MeetingLink {
+    meeting_link_id: meeting_link.meeting_link_id,
+    creation_time: meeting_link.creation_time,
+    creator_user_id: meeting_link.creator_user_id,
+    location,
+    session,
+    url: meeting_link.url,
+    active: meeting_link.active,
+  })
+}
+
+// doesn't say where the pass leads, that's only revealed by following it
+async fn fill_meeting_pass(
+  con: &mut impl GenericClient,
+  meeting_pass: MeetingPass,
+) -> Result<response::MeetingPass, response::InnexgoHoursError> {
+  let session = session_service::get_by_session_id(con, meeting_pass.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  Ok(response::MeetingPass {
+    meeting_pass_id: meeting_pass.meeting_pass_id,
+    creation_time: meeting_pass.creation_time,
+    creator_user_id: meeting_pass.creator_user_id,
+    session: fill_session(con, session).await?,
+    meeting_pass_token: meeting_pass.meeting_pass_token,
+    expiration_time: meeting_pass.expiration_time,
+  })
+}
+
+async fn fill_meeting_join(
+  con: &mut impl GenericClient,
+  meeting_join: MeetingJoin,
+) -> Result<response::MeetingJoin, response::InnexgoHoursError> {
+  let meeting_pass =
+    meeting_pass_service::get_by_meeting_pass_id(con, meeting_join.meeting_pass_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::MeetingPassNonexistent)?;
+
+  Ok(response::MeetingJoin {
+    meeting_pass: fill_meeting_pass(con, meeting_pass).await?,
+    creation_time: meeting_join.creation_time,
+  })
+}
+
 async fn fill_session_request(
   con: &mut impl GenericClient,
   session_request: SessionRequest,
@@ -490,2230 +926,8058 @@ async fn fill_commitment(
   })
 }
 
-async fn fill_encounter(
-  _con: &mut impl GenericClient,
-  encounter: Encounter,
-) -> Result<response::Encounter, response::InnexgoHoursError> {
-  Ok(response::Encounter {
-    encounter_id: encounter.encounter_id,
-    creation_time: encounter.creation_time,
-    creator_user_id: encounter.creator_user_id,
-    attendee_user_id: encounter.attendee_user_id,
-    location_id: encounter.location_id,
-    encounter_kind: encounter.encounter_kind,
-  })
-}
-
-async fn fill_stay(
+async fn fill_feedback(
   con: &mut impl GenericClient,
-  stay: Stay,
-) -> Result<response::Stay, response::InnexgoHoursError> {
-  let location = location_service::get_by_location_id(con, stay.location_id)
+  feedback: Feedback,
+) -> Result<response::Feedback, response::InnexgoHoursError> {
+  let commitment = commitment_service::get_by_commitment_id(con, feedback.commitment_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+    .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
 
-  Ok(response::Stay {
-    stay_id: stay.stay_id,
-    creation_time: stay.creation_time,
-    creator_user_id: stay.creator_user_id,
-    attendee_user_id: stay.attendee_user_id,
-    location: fill_location(con, location).await?,
+  Ok(response::Feedback {
+    feedback_id: feedback.feedback_id,
+    creation_time: feedback.creation_time,
+    creator_user_id: feedback.creator_user_id,
+    commitment: fill_commitment(con, commitment).await?,
   })
 }
 
-async fn fill_stay_data(
+async fn fill_feedback_data(
   con: &mut impl GenericClient,
-  stay_data: StayData,
-) -> Result<response::StayData, response::InnexgoHoursError> {
-  let stay = stay_service::get_by_stay_id(con, stay_data.stay_id)
+  feedback_data: FeedbackData,
+) -> Result<response::FeedbackData, response::InnexgoHoursError> {
+  let feedback = feedback_service::get_by_feedback_id(con, feedback_data.feedback_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+    .ok_or(response::InnexgoHoursError::FeedbackNonexistent)?;
+
+  Ok(response::FeedbackData {
+    feedback_data_id: feedback_data.feedback_data_id,
+    creation_time: feedback_data.creation_time,
+    creator_user_id: feedback_data.creator_user_id,
+    feedback: fill_feedback(con, feedback).await?,
+    rating: feedback_data.rating,
+    comment: feedback_data.comment,
+    active: feedback_data.active,
+  })
+}
 
-  let fst = match stay_data.fst {
-    Left(encounter_id) => {
-      let encounter = encounter_service::get_by_encounter_id(con, encounter_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
-      Left(fill_encounter(con, encounter).await?)
-    }
-    Right(timestamp) => Right(timestamp),
-  };
+// tallies up the feedback one instructor got in one course, leaving out who said what if asked to
+async fn fill_feedback_summary(
+  con: &mut impl GenericClient,
+  course: Course,
+  instructor_user_id: i64,
+  entries: Vec<FeedbackEntry>,
+  anonymize: bool,
+) -> Result<response::FeedbackSummary, response::InnexgoHoursError> {
+  let mut rating_counts = vec![0; FEEDBACK_MAX_RATING as usize];
+  for x in entries.iter() {
+    rating_counts[(x.feedback_data.rating - 1) as usize] += 1;
+  }
 
-  let snd = match stay_data.snd {
-    Left(encounter_id) => {
-      let encounter = encounter_service::get_by_encounter_id(con, encounter_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
-      Left(fill_encounter(con, encounter).await?)
-    }
-    Right(timestamp) => Right(timestamp),
+  let feedback_count = entries.len() as i64;
+  let average_rating = if feedback_count == 0 {
+    0.0
+  } else {
+    entries.iter().map(|x| x.feedback_data.rating).sum::<i64>() as f64 / feedback_count as f64
   };
 
-  Ok(response::StayData {
-    stay_data_id: stay_data.stay_data_id,
-    creation_time: stay_data.creation_time,
-    creator_user_id: stay_data.creator_user_id,
-    stay: fill_stay(con, stay).await?,
-    fst,
-    snd,
-    active: stay_data.active,
+  let mut comments = entries
+    .into_iter()
+    .filter(|x| !x.feedback_data.comment.is_empty())
+    .map(|x| response::FeedbackComment {
+      attendee_user_id: if anonymize {
+        None
+      } else {
+        Some(x.attendee_user_id)
+      },
+      session_id: x.session_id,
+      rating: x.feedback_data.rating,
+      comment: x.feedback_data.comment,
+    })
+    .collect::<Vec<_>>();
+
+  // the order they came in could give away who wrote them
+  if anonymize {
+    comments.sort_by(|a, b| {
+      a.session_id
+        .cmp(&b.session_id)
+        .then(a.comment.cmp(&b.comment))
+    });
+  }
+
+  Ok(response::FeedbackSummary {
+    course: fill_course(con, course).await?,
+    instructor_user_id,
+    feedback_count,
+    average_rating,
+    rating_counts,
+    comments,
   })
 }
 
-pub async fn get_user_if_api_key_valid(
-  auth_service: &auth_service_api::client::AuthService,
-  api_key: String,
-) -> Result<User, response::InnexgoHoursError> {
-  auth_service
-    .get_user_by_api_key_if_valid(api_key)
-    .await
-    .map_err(report_auth_err)
+// only says when the other session is, since the caller may not be able to see its course
+fn fill_conflict(conflict: Conflict) -> response::Conflict {
+  response::Conflict {
+    conflict_kind: conflict.conflict_kind,
+    user_id: conflict.user_id,
+    location_id: conflict.location_id,
+    session_id: conflict.session_data.session_id,
+    start_time: conflict.session_data.start_time,
+    end_time: conflict.session_data.end_time,
+  }
 }
 
-pub async fn subscription_new(
-  _config: Config,
-  db: Db,
+// everything that would be double booked by holding a session of the course from start_time to end_time
+async fn get_session_conflicts(
+  con: &mut impl GenericClient,
+  course_id: i64,
+  session_id: Option<i64>,
+  attendee_user_ids: &[i64],
+  start_time: i64,
+  end_time: i64,
+) -> Result<Vec<Conflict>, response::InnexgoHoursError> {
+  let mut conflicts = conflict_service::get_by_instructors_of_course_id(
+    con, course_id, session_id, start_time, end_time,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  conflicts.extend(
+    conflict_service::get_by_location_of_course_id(
+      con, course_id, session_id, start_time, end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?,
+  );
+
+  conflicts.extend(
+    conflict_service::get_by_attendee_user_ids(
+      con,
+      attendee_user_ids,
+      session_id,
+      start_time,
+      end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?,
+  );
+
+  Ok(conflicts)
+}
+
+// strict mode refuses to double book anyone, lenient mode (the default) goes ahead
+// and hands the conflicts back with the result
+fn check_conflicts(
+  conflict_mode: &Option<request::ConflictMode>,
+  conflicts: &[Conflict],
+) -> Result<(), response::InnexgoHoursError> {
+  if !matches!(conflict_mode, Some(request::ConflictMode::Strict)) {
+    return Ok(());
+  }
+
+  match conflicts.first().map(|x| &x.conflict_kind) {
+    None => Ok(()),
+    Some(request::ConflictKind::Instructor) => {
+      Err(response::InnexgoHoursError::InstructorDoubleBooked)
+    }
+    Some(request::ConflictKind::Attendee) => Err(response::InnexgoHoursError::AttendeeDoubleBooked),
+    Some(request::ConflictKind::Location) => Err(response::InnexgoHoursError::LocationDoubleBooked),
+  }
+}
+
+// nothing may be scheduled at a school while it's closed
+async fn check_school_open(
+  con: &mut impl GenericClient,
+  school_id: i64,
+  start_time: i64,
+  end_time: i64,
+) -> Result<(), response::InnexgoHoursError> {
+  let closures =
+    closure_data_service::get_active_by_school_id_in_range(con, school_id, start_time, end_time)
+      .await
+      .map_err(report_postgres_err)?;
+
+  if !closures.is_empty() {
+    return Err(response::InnexgoHoursError::SchoolClosed);
+  }
+
+  Ok(())
+}
+
+// deactivates the session and every commitment to it, and records why
+// returns the change, the new session data, and the withdrawn commitments
+async fn cancel_session(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_data: SessionData,
+  reason: String,
+) -> Result<(SessionChange, SessionData, Vec<Commitment>), response::InnexgoHoursError> {
+  let mut cancelled_commitments = vec![];
+
+  for commitment in commitment_service::get_active_by_session_id(con, session_data.session_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    let commitment = commitment_service::add(
+      con,
+      user_id,
+      commitment.attendee_user_id,
+      commitment.session_id,
+      false,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    cancelled_commitments.push(commitment);
+  }
+
+  let session_change = session_change_service::add(
+    con,
+    user_id,
+    session_data.session_id,
+    request::SessionChangeKind::Cancel,
+    reason,
+    session_data.start_time,
+    session_data.end_time,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let session_data = session_data_service::add(
+    con,
+    user_id,
+    session_data.session_id,
+    session_data.name,
+    session_data.start_time,
+    session_data.end_time,
+    false,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok((session_change, session_data, cancelled_commitments))
+}
+
+// cancels every session during the closure, along with the commitments to them
+// returns what was cancelled, and the events to publish once the transaction commits
+async fn apply_closure(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  school_id: i64,
+  closure_data: ClosureData,
+) -> Result<(response::ClosureReport, Vec<events::Event>), response::InnexgoHoursError> {
+  let mut cancelled_session_data = vec![];
+  let mut cancelled_commitments = vec![];
+  let mut new_events = vec![];
+
+  let affected = if closure_data.active {
+    session_data_service::get_active_by_school_id_in_range(
+      con,
+      school_id,
+      closure_data.start_time,
+      closure_data.end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?
+  } else {
+    vec![]
+  };
+
+  for session_data in affected.into_iter() {
+    let (session_change, session_data, commitments) =
+      cancel_session(con, user_id, session_data, closure_data.name.clone()).await?;
+
+    for commitment in commitments.into_iter() {
+      new_events.push(events::Event::Commitment(commitment.clone()));
+      cancelled_commitments.push(fill_commitment(con, commitment).await?);
+    }
+    new_events.push(events::Event::SessionChange(session_change));
+
+    cancelled_session_data.push(fill_session_data(con, session_data).await?);
+  }
+
+  let report = response::ClosureReport {
+    closure_data: fill_closure_data(con, closure_data).await?,
+    cancelled_session_data,
+    cancelled_commitments,
+  };
+
+  Ok((report, new_events))
+}
+
+async fn fill_availability(
+  con: &mut impl GenericClient,
+  availability: Availability,
+) -> Result<response::Availability, response::InnexgoHoursError> {
+  let course = course_service::get_by_course_id(con, availability.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  Ok(response::Availability {
+    availability_id: availability.availability_id,
+    creation_time: availability.creation_time,
+    creator_user_id: availability.creator_user_id,
+    course: fill_course(con, course).await?,
+  })
+}
+
+async fn fill_availability_data(
+  con: &mut impl GenericClient,
+  availability_data: AvailabilityData,
+) -> Result<response::AvailabilityData, response::InnexgoHoursError> {
+  let availability =
+    availability_service::get_by_availability_id(con, availability_data.availability_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
+
+  Ok(response::AvailabilityData {
+    availability_data_id: availability_data.availability_data_id,
+    creation_time: availability_data.creation_time,
+    creator_user_id: availability_data.creator_user_id,
+    availability: fill_availability(con, availability).await?,
+    name: availability_data.name,
+    start_time: availability_data.start_time,
+    end_time: availability_data.end_time,
+    slot_duration: availability_data.slot_duration,
+    active: availability_data.active,
+  })
+}
+
+async fn fill_availability_booking(
+  con: &mut impl GenericClient,
+  availability_booking: AvailabilityBooking,
+) -> Result<response::AvailabilityBooking, response::InnexgoHoursError> {
+  let availability =
+    availability_service::get_by_availability_id(con, availability_booking.availability_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
+
+  let commitment =
+    commitment_service::get_by_commitment_id(con, availability_booking.commitment_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
+
+  Ok(response::AvailabilityBooking {
+    availability_booking_id: availability_booking.availability_booking_id,
+    creation_time: availability_booking.creation_time,
+    creator_user_id: availability_booking.creator_user_id,
+    availability: fill_availability(con, availability).await?,
+    start_time: availability_booking.start_time,
+    commitment: fill_commitment(con, commitment).await?,
+  })
+}
+
+async fn fill_irregularity(
+  con: &mut impl GenericClient,
+  irregularity: Irregularity,
+) -> Result<response::Irregularity, response::InnexgoHoursError> {
+  let commitment = commitment_service::get_by_commitment_id(con, irregularity.commitment_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
+
+  Ok(response::Irregularity {
+    irregularity_id: irregularity.irregularity_id,
+    creation_time: irregularity.creation_time,
+    creator_user_id: irregularity.creator_user_id,
+    commitment: fill_commitment(con, commitment).await?,
+    irregularity_kind: irregularity.irregularity_kind,
+    start_time: irregularity.start_time,
+    end_time: irregularity.end_time,
+    active: irregularity.active,
+  })
+}
+
+// every active commitment of the session, with any outstanding irregularity
+async fn fill_attendance_sheet(
+  con: &mut impl GenericClient,
+  session_id: i64,
+) -> Result<Vec<response::AttendanceSheetEntry>, response::InnexgoHoursError> {
+  let commitments = commitment_service::get_active_by_session_id(con, session_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let mut entries = vec![];
+  for commitment in commitments.into_iter() {
+    let irregularity =
+      match irregularity_service::get_by_commitment_id(con, commitment.commitment_id)
+        .await
+        .map_err(report_postgres_err)?
+      {
+        Some(irregularity) if irregularity.active => {
+          Some(fill_irregularity(con, irregularity).await?)
+        }
+        _ => None,
+      };
+
+    entries.push(response::AttendanceSheetEntry {
+      commitment: fill_commitment(con, commitment).await?,
+      irregularity,
+    });
+  }
+
+  Ok(entries)
+}
+
+async fn fill_encounter(
+  _con: &mut impl GenericClient,
+  encounter: Encounter,
+) -> Result<response::Encounter, response::InnexgoHoursError> {
+  Ok(response::Encounter {
+    encounter_id: encounter.encounter_id,
+    creation_time: encounter.creation_time,
+    creator_user_id: encounter.creator_user_id,
+    attendee_user_id: encounter.attendee_user_id,
+    location_id: encounter.location_id,
+    encounter_kind: encounter.encounter_kind,
+    received_time: encounter.received_time,
+    idempotency_key: encounter.idempotency_key,
+  })
+}
+
+async fn fill_stay(
+  con: &mut impl GenericClient,
+  stay: Stay,
+) -> Result<response::Stay, response::InnexgoHoursError> {
+  let location = location_service::get_by_location_id(con, stay.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  Ok(response::Stay {
+    stay_id: stay.stay_id,
+    creation_time: stay.creation_time,
+    creator_user_id: stay.creator_user_id,
+    attendee_user_id: stay.attendee_user_id,
+    location: fill_location(con, location).await?,
+  })
+}
+
+async fn fill_stay_data(
+  con: &mut impl GenericClient,
+  stay_data: StayData,
+) -> Result<response::StayData, response::InnexgoHoursError> {
+  let stay = stay_service::get_by_stay_id(con, stay_data.stay_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+
+  let fst = match stay_data.fst {
+    Left(encounter_id) => {
+      let encounter = encounter_service::get_by_encounter_id(con, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      Left(fill_encounter(con, encounter).await?)
+    }
+    Right(timestamp) => Right(timestamp),
+  };
+
+  let snd = match stay_data.snd {
+    Left(encounter_id) => {
+      let encounter = encounter_service::get_by_encounter_id(con, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      Left(fill_encounter(con, encounter).await?)
+    }
+    Right(timestamp) => Right(timestamp),
+  };
+
+  Ok(response::StayData {
+    stay_data_id: stay_data.stay_data_id,
+    creation_time: stay_data.creation_time,
+    creator_user_id: stay_data.creator_user_id,
+    stay: fill_stay(con, stay).await?,
+    fst,
+    snd,
+    active: stay_data.active,
+  })
+}
+
+async fn fill_guardianship(
+  _con: &mut impl GenericClient,
+  guardianship: Guardianship,
+) -> Result<response::Guardianship, response::InnexgoHoursError> {
+  Ok(response::Guardianship {
+    guardianship_id: guardianship.guardianship_id,
+    creation_time: guardianship.creation_time,
+    creator_user_id: guardianship.creator_user_id,
+    guardian_user_id: guardianship.guardian_user_id,
+    student_user_id: guardianship.student_user_id,
+    guardianship_kind: guardianship.guardianship_kind,
+    weekly_summary: guardianship.weekly_summary,
+  })
+}
+
+async fn fill_occupant(
+  con: &mut impl GenericClient,
+  occupant: Occupant,
+  time: i64,
+) -> Result<response::Occupant, response::InnexgoHoursError> {
+  let location = location_service::get_by_location_id(con, occupant.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  let source = match occupant.source {
+    Left(stay_id) => {
+      let stay = stay_service::get_by_stay_id(con, stay_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+      Left(fill_stay(con, stay).await?)
+    }
+    Right(encounter_id) => {
+      let encounter = encounter_service::get_by_encounter_id(con, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      Right(fill_encounter(con, encounter).await?)
+    }
+  };
+
+  Ok(response::Occupant {
+    attendee_user_id: occupant.attendee_user_id,
+    location: fill_location(con, location).await?,
+    arrival_time: occupant.arrival_time,
+    duration: time - occupant.arrival_time,
+    source,
+  })
+}
+
+// attendees, their guardians, and staff who can view records at the location may see an encounter
+async fn can_view_encounter(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  encounter: &Encounter,
+) -> Result<bool, response::InnexgoHoursError> {
+  if encounter.attendee_user_id == user_id {
+    return Ok(true);
+  }
+
+  let is_guardian = guardianship_service::is_guardian(con, user_id, encounter.attendee_user_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let can_view_records = course_membership_service::has_capability_at(
+    con,
+    user_id,
+    encounter.location_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(is_guardian || can_view_records)
+}
+
+// the requester, their guardians, and staff who can view records in the course may see a session request
+async fn can_view_session_request(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_request: &SessionRequest,
+) -> Result<bool, response::InnexgoHoursError> {
+  if session_request.creator_user_id == user_id {
+    return Ok(true);
+  }
+
+  let is_guardian =
+    guardianship_service::is_guardian(con, user_id, session_request.creator_user_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+  let can_view_records = course_membership_service::has_capability(
+    con,
+    user_id,
+    session_request.course_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(is_guardian || can_view_records)
+}
+
+// responses are visible to whoever may see the request they answer
+async fn can_view_session_request_response(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_request_response: &SessionRequestResponse,
+) -> Result<bool, response::InnexgoHoursError> {
+  let session_request = session_request_service::get_by_session_request_id(
+    con,
+    session_request_response.session_request_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
+
+  can_view_session_request(con, user_id, &session_request).await
+}
+
+// attendees, their guardians, and staff who can view records in the course may see a commitment
+async fn can_view_commitment(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  commitment: &Commitment,
+) -> Result<bool, response::InnexgoHoursError> {
+  if commitment.attendee_user_id == user_id {
+    return Ok(true);
+  }
+
+  let is_guardian = guardianship_service::is_guardian(con, user_id, commitment.attendee_user_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let session = session_service::get_by_session_id(con, commitment.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  let can_view_records = course_membership_service::has_capability(
+    con,
+    user_id,
+    session.course_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(is_guardian || can_view_records)
+}
+
+// feedback is only for its author
+// staff see it through the summaries, which can leave out who said what
+async fn can_view_feedback_data(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  feedback_data: &FeedbackData,
+) -> Result<bool, response::InnexgoHoursError> {
+  let feedback = feedback_service::get_by_feedback_id(con, feedback_data.feedback_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::FeedbackNonexistent)?;
+
+  Ok(feedback.creator_user_id == user_id)
+}
+
+// changes are visible to whoever may see the session
+async fn can_view_session_change(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_change: &SessionChange,
+) -> Result<bool, response::InnexgoHoursError> {
+  let session = session_service::get_by_session_id(con, session_change.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  course_membership_service::has_capability(con, user_id, session.course_id, CourseCapability::View)
+    .await
+    .map_err(report_postgres_err)
+}
+
+// members of the course can see that a note exists, though maybe not what it says
+async fn can_view_session_note(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_note: &SessionNote,
+) -> Result<bool, response::InnexgoHoursError> {
+  let session = session_service::get_by_session_id(con, session_note.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  course_membership_service::has_capability(con, user_id, session.course_id, CourseCapability::View)
+    .await
+    .map_err(report_postgres_err)
+}
+
+// a version must be visible both by itself and by the note's current visibility
+// so that making a note private also hides what it used to say
+async fn can_view_session_note_data(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_note_data: &SessionNoteData,
+) -> Result<bool, response::InnexgoHoursError> {
+  let session_note =
+    session_note_service::get_by_session_note_id(con, session_note_data.session_note_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SessionNoteNonexistent)?;
+
+  let session = session_service::get_by_session_id(con, session_note.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  let current =
+    session_note_data_service::get_by_session_note_id(con, session_note.session_note_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SessionNoteNonexistent)?;
+
+  let private = [session_note_data, &current].iter().any(|x| {
+    matches!(
+      x.session_note_visibility,
+      request::SessionNoteVisibility::Instructors
+    )
+  });
+
+  // private notes are only for those who run the sessions
+  let capability = if private {
+    CourseCapability::RunSessions
+  } else {
+    CourseCapability::View
+  };
+
+  course_membership_service::has_capability(con, user_id, session.course_id, capability)
+    .await
+    .map_err(report_postgres_err)
+}
+
+// attachments are as visible as whatever they're attached to
+async fn can_view_attachment(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  attachment: &Attachment,
+) -> Result<bool, response::InnexgoHoursError> {
+  match (attachment.session_request_id, attachment.session_id) {
+    (Some(session_request_id), _) => {
+      let session_request =
+        session_request_service::get_by_session_request_id(con, session_request_id)
+          .await
+          .map_err(report_postgres_err)?
+          .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
+
+      can_view_session_request(con, user_id, &session_request).await
+    }
+    (None, Some(session_id)) => {
+      let session = session_service::get_by_session_id(con, session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+      // members of the course can see sessions
+      course_membership_service::has_capability(
+        con,
+        user_id,
+        session.course_id,
+        CourseCapability::View,
+      )
+      .await
+      .map_err(report_postgres_err)
+    }
+    (None, None) => Ok(false),
+  }
+}
+
+// links to a location's meetings are managed by the school's admins, and a session's by those who run it
+async fn can_manage_meeting_link(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  location_id: Option<i64>,
+  session_id: Option<i64>,
+) -> Result<bool, response::InnexgoHoursError> {
+  match (location_id, session_id) {
+    (Some(location_id), None) => {
+      let location = location_service::get_by_location_id(con, location_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+      adminship_service::is_admin(con, user_id, location.school_id)
+        .await
+        .map_err(report_postgres_err)
+    }
+    (None, Some(session_id)) => {
+      let session = session_service::get_by_session_id(con, session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+      course_membership_service::has_capability(
+        con,
+        user_id,
+        session.course_id,
+        CourseCapability::RunSessions,
+      )
+      .await
+      .map_err(report_postgres_err)
+    }
+    _ => Err(response::InnexgoHoursError::MeetingLinkTargetInvalid),
+  }
+}
+
+// those committed to the session, and members of its course, may join its meeting
+async fn can_join_session(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session: &Session,
+) -> Result<bool, response::InnexgoHoursError> {
+  let committed =
+    commitment_service::get_by_attendee_user_id_session_id(con, user_id, session.session_id)
+      .await
+      .map_err(report_postgres_err)?
+      .map_or(false, |x| x.active);
+
+  let is_member = course_membership_service::has_capability(
+    con,
+    user_id,
+    session.course_id,
+    CourseCapability::View,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(committed || is_member)
+}
+
+// joins are attendance records, so they're as visible as commitments
+async fn can_view_meeting_join(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  meeting_join: &MeetingJoin,
+) -> Result<bool, response::InnexgoHoursError> {
+  let meeting_pass =
+    meeting_pass_service::get_by_meeting_pass_id(con, meeting_join.meeting_pass_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::MeetingPassNonexistent)?;
+
+  if meeting_pass.creator_user_id == user_id {
+    return Ok(true);
+  }
+
+  let is_guardian = guardianship_service::is_guardian(con, user_id, meeting_pass.creator_user_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let session = session_service::get_by_session_id(con, meeting_pass.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  let can_view_records = course_membership_service::has_capability(
+    con,
+    user_id,
+    session.course_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(is_guardian || can_view_records)
+}
+
+// bookkeeping fields that differ between every pair of versions, responses are camelCase
+fn is_version_bookkeeping(field: &str) -> bool {
+  field == "creationTime" || field == "creatorUserId" || field.ends_with("DataId")
+}
+
+// pairs each version (oldest first) with the fields that differ from the one before it
+// every field counts as changed in the first version
+fn fill_history<T: serde::Serialize>(
+  versions: Vec<T>,
+) -> Result<Vec<response::Version<T>>, response::InnexgoHoursError> {
+  let mut history = vec![];
+  let mut previous: Option<serde_json::Map<String, serde_json::Value>> = None;
+
+  for data in versions.into_iter() {
+    let current = match serde_json::to_value(&data).map_err(report_serde_err)? {
+      serde_json::Value::Object(fields) => fields,
+      _ => serde_json::Map::new(),
+    };
+
+    let changed_fields = current
+      .iter()
+      .filter(|(k, _)| !is_version_bookkeeping(k))
+      .filter(|(k, v)| previous.as_ref().map_or(true, |p| p.get(*k) != Some(*v)))
+      .map(|(k, _)| k.clone())
+      .collect();
+
+    history.push(response::Version {
+      data,
+      changed_fields,
+    });
+
+    previous = Some(current);
+  }
+
+  Ok(history)
+}
+
+// notifies any live subscribers, it's fine if nobody is listening
+fn publish(config: &Config, event: events::Event) {
+  let _ = config.events.send(event);
+}
+
+// returns the school, and if applicable the course and location, that an event belongs to
+async fn get_event_scope(
+  con: &mut impl GenericClient,
+  event: &events::Event,
+) -> Result<(i64, Option<i64>, Option<i64>), response::InnexgoHoursError> {
+  let course_id = match event {
+    // encounters and hall passes belong to a location rather than a course
+    events::Event::Encounter(x) => {
+      let location = location_service::get_by_location_id(con, x.location_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+      return Ok((location.school_id, None, Some(location.location_id)));
+    }
+    events::Event::HallPassOverdue(x) => {
+      let location = location_service::get_by_location_id(con, x.origin_location_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+      return Ok((location.school_id, None, Some(location.location_id)));
+    }
+    events::Event::SessionRequest(x) => x.course_id,
+    events::Event::SessionRequestResponse(x) => {
+      session_request_service::get_by_session_request_id(con, x.session_request_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?
+        .course_id
+    }
+    events::Event::Commitment(x) => {
+      session_service::get_by_session_id(con, x.session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?
+        .course_id
+    }
+    events::Event::SessionChange(x) => {
+      session_service::get_by_session_id(con, x.session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?
+        .course_id
+    }
+  };
+
+  let course = course_service::get_by_course_id(con, course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  Ok((course.school_id, Some(course.course_id), None))
+}
+
+// whether a filter of ids admits the given id, no filter admits everything
+fn filter_admits(filter: &Option<Vec<i64>>, id: Option<i64>) -> bool {
+  match filter {
+    None => true,
+    Some(ids) => id.map_or(false, |id| ids.contains(&id)),
+  }
+}
+
+// everyone who could see the event through the view endpoints, mirroring the can_view_* checks
+async fn get_event_audience(
+  con: &mut impl GenericClient,
+  event: &events::Event,
+) -> Result<HashSet<i64>, response::InnexgoHoursError> {
+  // the student the event is about, who may always see it along with their guardians
+  let (attendee_user_id, course_id, capability, location_ids) = match event {
+    events::Event::Encounter(x) => (
+      Some(x.attendee_user_id),
+      None,
+      CourseCapability::ViewRecords,
+      vec![x.location_id],
+    ),
+    events::Event::SessionRequest(x) => (
+      Some(x.creator_user_id),
+      Some(x.course_id),
+      CourseCapability::ViewRecords,
+      vec![],
+    ),
+    events::Event::SessionRequestResponse(x) => {
+      let session_request =
+        session_request_service::get_by_session_request_id(con, x.session_request_id)
+          .await
+          .map_err(report_postgres_err)?
+          .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
+      (
+        Some(session_request.creator_user_id),
+        Some(session_request.course_id),
+        CourseCapability::ViewRecords,
+        vec![],
+      )
+    }
+    events::Event::Commitment(x) => {
+      let session = session_service::get_by_session_id(con, x.session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+      (
+        Some(x.attendee_user_id),
+        Some(session.course_id),
+        CourseCapability::ViewRecords,
+        vec![],
+      )
+    }
+    events::Event::SessionChange(x) => {
+      let session = session_service::get_by_session_id(con, x.session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+      (
+        None,
+        Some(session.course_id),
+        CourseCapability::View,
+        vec![],
+      )
+    }
+    events::Event::HallPassOverdue(x) => (
+      Some(x.attendee_user_id),
+      None,
+      CourseCapability::ViewRecords,
+      vec![x.origin_location_id, x.destination_location_id],
+    ),
+  };
+
+  let mut audience = HashSet::new();
+
+  if let Some(attendee_user_id) = attendee_user_id {
+    audience.insert(attendee_user_id);
+    audience.extend(
+      guardianship_service::get_guardian_user_ids(con, attendee_user_id)
+        .await
+        .map_err(report_postgres_err)?,
+    );
+  }
+
+  if let Some(course_id) = course_id {
+    audience.extend(
+      course_membership_service::get_user_ids_with_capability(con, course_id, capability)
+        .await
+        .map_err(report_postgres_err)?,
+    );
+  }
+
+  for location_id in location_ids {
+    audience.extend(
+      course_membership_service::get_user_ids_with_capability_at(con, location_id, capability)
+        .await
+        .map_err(report_postgres_err)?,
+    );
+  }
+
+  Ok(audience)
+}
+
+// resolves the scope, audience and rendering of an event once, so that subscribers only have to
+// compare ids
+pub async fn dispatch_event(
+  con: &mut impl GenericClient,
+  event: events::Event,
+) -> Result<events::Dispatched, response::InnexgoHoursError> {
+  let (school_id, course_id, location_id) = get_event_scope(con, &event).await?;
+  let audience = get_event_audience(con, &event).await?;
+
+  let (name, data) = match event {
+    events::Event::Encounter(x) => (
+      "encounter",
+      serde_json::to_value(fill_encounter(con, x).await?),
+    ),
+    events::Event::SessionRequest(x) => (
+      "session_request",
+      serde_json::to_value(fill_session_request(con, x).await?),
+    ),
+    events::Event::SessionRequestResponse(x) => (
+      "session_request_response",
+      serde_json::to_value(fill_session_request_response(con, x).await?),
+    ),
+    events::Event::Commitment(x) => (
+      "commitment",
+      serde_json::to_value(fill_commitment(con, x).await?),
+    ),
+    events::Event::SessionChange(x) => (
+      "session_change",
+      serde_json::to_value(fill_session_change(con, x).await?),
+    ),
+    events::Event::HallPassOverdue(x) => (
+      "hall_pass_overdue",
+      serde_json::to_value(fill_hall_pass(con, x).await?),
+    ),
+  };
+
+  Ok(events::Dispatched::Visible {
+    school_id,
+    course_id,
+    location_id,
+    audience,
+    name,
+    data: data.map_err(|_| response::InnexgoHoursError::Unknown)?,
+  })
+}
+
+async fn fill_hall_pass(
+  con: &mut impl GenericClient,
+  hall_pass: HallPass,
+) -> Result<response::HallPass, response::InnexgoHoursError> {
+  let origin_location = location_service::get_by_location_id(con, hall_pass.origin_location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  let destination_location =
+    location_service::get_by_location_id(con, hall_pass.destination_location_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  let return_time = hall_pass_service::get_return_time(con, &hall_pass)
+    .await
+    .map_err(report_postgres_err)?;
+
+  Ok(response::HallPass {
+    hall_pass_id: hall_pass.hall_pass_id,
+    creation_time: hall_pass.creation_time,
+    creator_user_id: hall_pass.creator_user_id,
+    attendee_user_id: hall_pass.attendee_user_id,
+    origin_location: fill_location(con, origin_location).await?,
+    destination_location: fill_location(con, destination_location).await?,
+    hall_pass_reason_kind: hall_pass.hall_pass_reason_kind,
+    expected_return_time: hall_pass.expected_return_time,
+    return_time,
+  })
+}
+
+// the student, their guardians, and staff who can view records at either end may see a hall pass
+async fn can_view_hall_pass(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  hall_pass: &HallPass,
+) -> Result<bool, response::InnexgoHoursError> {
+  if hall_pass.attendee_user_id == user_id {
+    return Ok(true);
+  }
+
+  let is_guardian = guardianship_service::is_guardian(con, user_id, hall_pass.attendee_user_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let can_view_records_at_origin = course_membership_service::has_capability_at(
+    con,
+    user_id,
+    hall_pass.origin_location_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let can_view_records_at_destination = course_membership_service::has_capability_at(
+    con,
+    user_id,
+    hall_pass.destination_location_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(is_guardian || can_view_records_at_origin || can_view_records_at_destination)
+}
+
+// signs the attendee out of a location if they're currently there
+async fn close_presence_at(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  attendee_user_id: i64,
+  location_id: i64,
+  time: i64,
+) -> Result<Option<Encounter>, response::InnexgoHoursError> {
+  let occupant = occupancy_service::get_by_location_id(con, location_id, time)
+    .await
+    .map_err(report_postgres_err)?
+    .into_iter()
+    .find(|x| x.attendee_user_id == attendee_user_id);
+
+  // nothing to close, and a lone sign out would look like a sign in
+  let occupant = match occupant {
+    Some(occupant) => occupant,
+    None => return Ok(None),
+  };
+
+  let sign_out = encounter_service::add(
+    con,
+    creator_user_id,
+    location_id,
+    attendee_user_id,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  match occupant.source {
+    // end the open stay now
+    Left(stay_id) => {
+      let stay_data = stay_data_service::get_by_stay_id(con, stay_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+
+      let _ = stay_data_service::add(
+        con,
+        creator_user_id,
+        stay_id,
+        stay_data.fst,
+        Left(sign_out.encounter_id),
+        true,
+      )
+      .await
+      .map_err(report_postgres_err)?;
+    }
+    // pair the dangling sign in with the sign out
+    Right(sign_in_encounter_id) => {
+      let stay = stay_service::add(con, creator_user_id, attendee_user_id, location_id, None)
+        .await
+        .map_err(report_postgres_err)?;
+
+      let _ = stay_data_service::add(
+        con,
+        creator_user_id,
+        stay.stay_id,
+        Left(sign_in_encounter_id),
+        Left(sign_out.encounter_id),
+        true,
+      )
+      .await
+      .map_err(report_postgres_err)?;
+    }
+  }
+
+  Ok(Some(sign_out))
+}
+
+// checks that a course key could be redeemed right now
+async fn check_course_key_redeemable(
+  con: &mut impl GenericClient,
+  course_key: &CourseKey,
+) -> Result<(), response::InnexgoHoursError> {
+  // time is within range
+  let now = utils::current_time_millis();
+  if now < course_key.start_time || now > course_key.end_time {
+    return Err(response::InnexgoHoursError::CourseKeyExpired);
+  }
+
+  // key hasn't been deactivated
+  if !course_key_data_service::is_active_by_course_key_key(con, &course_key.course_key_key)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseKeyExpired);
+  }
+
+  // it fits under max uses
+  let uses = course_membership_service::count_course_key_uses(con, &course_key.course_key_key)
+    .await
+    .map_err(report_postgres_err)?;
+
+  if uses >= course_key.max_uses {
+    return Err(response::InnexgoHoursError::CourseKeyUsed);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(con, course_key.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  Ok(())
+}
+
+// a course may only be put in a term of its own school that hasn't been archived
+async fn check_term_assignable(
+  con: &mut impl GenericClient,
+  term_id: i64,
+  school_id: i64,
+) -> Result<(), response::InnexgoHoursError> {
+  let term = term_service::get_by_term_id(con, term_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::TermNonexistent)?;
+
+  if term.school_id != school_id {
+    return Err(response::InnexgoHoursError::TermNonexistent);
+  }
+
+  if !term_data_service::is_active_by_term_id(con, term_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::TermArchived);
+  }
+
+  Ok(())
+}
+
+// students may only be in one homeroom per school
+// with student_user_id only that student is checked, otherwise every student of the course is
+async fn check_homeroom_unique(
+  con: &mut impl GenericClient,
+  course_id: i64,
+  student_user_id: Option<i64>,
+) -> Result<(), response::InnexgoHoursError> {
+  if homeroom_service::count_students_in_other_homerooms(con, course_id, student_user_id)
+    .await
+    .map_err(report_postgres_err)?
+    > 0
+  {
+    return Err(response::InnexgoHoursError::HomeroomExists);
+  }
+
+  Ok(())
+}
+
+// joining a homeroom as a student mustn't give the student a second one
+async fn check_homeroom_membership(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  course_id: i64,
+  course_membership_kind: &request::CourseMembershipKind,
+) -> Result<(), response::InnexgoHoursError> {
+  if !matches!(
+    course_membership_kind,
+    request::CourseMembershipKind::Student
+  ) {
+    return Ok(());
+  }
+
+  let course_data = course_data_service::get_by_course_id(con, course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  if course_data.homeroom && course_data.active {
+    check_homeroom_unique(con, course_id, Some(user_id)).await?;
+  }
+
+  Ok(())
+}
+
+// where the student takes daily attendance
+// students in homerooms at more than one school have to say which location they mean
+async fn get_homeroom_location_id(
+  con: &mut impl GenericClient,
+  student_user_id: i64,
+) -> Result<i64, response::InnexgoHoursError> {
+  let homerooms = homeroom_service::get_by_student_user_id(con, student_user_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  match homerooms.as_slice() {
+    [homeroom] => Ok(homeroom.location_id),
+    _ => Err(response::InnexgoHoursError::HomeroomNonexistent),
+  }
+}
+
+// checks that a school key could be redeemed right now
+async fn check_school_key_redeemable(
+  con: &mut impl GenericClient,
+  school_key: &SchoolKey,
+) -> Result<(), response::InnexgoHoursError> {
+  // time is within range
+  let now = utils::current_time_millis();
+  if now < school_key.start_time || now > school_key.end_time {
+    return Err(response::InnexgoHoursError::SchoolKeyExpired);
+  }
+
+  // key hasn't been deactivated
+  if !school_key_data_service::is_active_by_school_key_key(con, &school_key.school_key_key)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolKeyExpired);
+  }
+
+  // school keys are single use
+  if adminship_service::count_school_key_uses(con, &school_key.school_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    >= 1
+  {
+    return Err(response::InnexgoHoursError::SchoolKeyUsed);
+  }
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(con, school_key.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  Ok(())
+}
+
+// invitations are bound to an email address, so we compare against the one the auth service verified
+async fn get_user_email(
+  auth_service: &AuthService,
+  user_id: i64,
+) -> Result<String, response::InnexgoHoursError> {
+  let email = auth_service
+    .get_email_by_user_id(user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  Ok(normalize_email(&email))
+}
+
+fn normalize_email(email: &str) -> String {
+  email.trim().to_lowercase()
+}
+
+async fn send_mail(
+  config: &Config,
+  destination: String,
+  topic: &str,
+  title: String,
+  content: String,
+) -> Result<(), response::InnexgoHoursError> {
+  let _ = config
+    .mail_service
+    .mail_new(mail_service_api::request::MailNewProps {
+      request_id: rand::random(),
+      destination,
+      topic: topic.to_owned(),
+      title,
+      content,
+    })
+    .await
+    .map_err(report_mail_err)?;
+
+  Ok(())
+}
+
+pub async fn get_user_if_api_key_valid(
+  auth_service: &auth_service_api::client::AuthService,
+  api_key: String,
+) -> Result<User, response::InnexgoHoursError> {
+  auth_service
+    .get_user_by_api_key_if_valid(api_key)
+    .await
+    .map_err(report_auth_err)
+}
+
+pub async fn subscription_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SubscriptionNewProps,
+) -> Result<response::Subscription, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  // create event
+  let subscription = subscription_service::add(con, user.user_id, props.subscription_kind, 1, 0)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // return json
+  fill_subscription(con, subscription).await
+}
+
+pub async fn location_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::LocationNewProps,
+) -> Result<response::LocationData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate school exists and that key creator is admin
+  let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  // create location
+  let location = location_service::add(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create location data
+  let location_data = location_data_service::add(
+    &mut sp,
+    user.user_id,
+    location.location_id,
+    props.name,
+    props.address,
+    props.phone,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_location_data(con, location_data).await
+}
+
+pub async fn location_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::LocationDataNewProps,
+) -> Result<response::LocationData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate location
+  let location = location_service::get_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // only let us modify if we manage a course at this location or are an admin
+
+  let can_manage_at = course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    props.location_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let admin_at = adminship_service::is_admin(&mut sp, user.user_id, location.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  if !(can_manage_at || admin_at) {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // now we can update data
+  let location_data = location_data_service::add(
+    &mut sp,
+    user.user_id,
+    props.location_id,
+    props.name,
+    props.address,
+    props.phone,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_location_data(con, location_data).await
+}
+
+// enables, disables, or rotates the secret for self check in at a location
+pub async fn location_checkin_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::LocationCheckinNewProps,
+) -> Result<response::LocationCheckin, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate location
+  let location = location_service::get_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // only let us modify if we manage a course at this location or are an admin
+  let can_manage_at = course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    props.location_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let admin_at = adminship_service::is_admin(&mut sp, user.user_id, location.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  if !(can_manage_at || admin_at) {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // a fresh secret every time invalidates any codes that leaked
+  let location_checkin = location_checkin_service::add(
+    &mut sp,
+    user.user_id,
+    props.location_id,
+    utils::gen_random_string(),
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_location_checkin(con, location_checkin).await
+}
+
+// the code that the display at the location should be showing right now
+pub async fn location_checkin_code(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::LocationCheckinCodeProps,
+) -> Result<response::LocationCheckinCode, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let location = location_service::get_by_location_id(con, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // only staff who could take attendance here may display the code
+  let can_take_attendance_at = course_membership_service::has_capability_at(
+    con,
+    user.user_id,
+    location.location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let admin_at = adminship_service::is_admin(con, user.user_id, location.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  if !(can_take_attendance_at || admin_at) {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let location_checkin =
+    location_checkin_service::get_active_by_location_id(con, location.location_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::LocationCheckinNonexistent)?;
+
+  let time = utils::current_time_millis();
+
+  Ok(response::LocationCheckinCode {
+    location_id: location.location_id,
+    code: utils::gen_checkin_code(&location_checkin.secret, time),
+    expiry_time: (time.div_euclid(utils::CHECKIN_CODE_PERIOD) + 1) * utils::CHECKIN_CODE_PERIOD,
+  })
+}
+
+pub async fn course_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseNewProps,
+) -> Result<response::CourseData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate school exists and that key creator is admin
+  let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  // check that location is valid
+  let _ = location_service::get_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that location is not archived
+  if !location_data_service::is_active_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::LocationArchived);
+  }
+
+  // if a term was given, it must belong to this school and not be archived
+  if let Some(term_id) = props.term_id {
+    check_term_assignable(&mut sp, term_id, props.school_id).await?;
+  }
+
+  // create course
+  let course = course_service::add(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create course data
+  let course_data = course_data_service::add(
+    &mut sp,
+    user.user_id,
+    course.course_id,
+    props.location_id,
+    props.term_id,
+    props.name,
+    props.description,
+    props.homeroom, // the course has no students yet, so it can't give anyone a second homeroom
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // give the creator a course membership
+  let _ = course_membership_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    course.course_id,
+    request::CourseMembershipKind::Instructor,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_data(con, course_data).await
+}
+
+pub async fn course_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseDataNewProps,
+) -> Result<response::CourseData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let course = course_service::get_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    props.course_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that location is valid
+  let _ = location_service::get_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that location is not archived
+  if !location_data_service::is_active_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::LocationArchived);
+  }
+
+  // becoming a homeroom (or coming back as one) mustn't give any of its students a second one
+  if props.homeroom && props.active {
+    check_homeroom_unique(&mut sp, course.course_id, None).await?;
+  }
+
+  // moving the course into another term, keeping it where it is is always fine
+  let old_course_data = course_data_service::get_by_course_id(&mut sp, course.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  if let Some(term_id) = props.term_id {
+    if old_course_data.term_id != Some(term_id) {
+      check_term_assignable(&mut sp, term_id, course.school_id).await?;
+    }
+  }
+
+  // now we can update data
+  let course_data = course_data_service::add(
+    &mut sp,
+    user.user_id,
+    course.course_id,
+    props.location_id,
+    props.term_id,
+    props.name,
+    props.description,
+    props.homeroom,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_data(con, course_data).await
+}
+
+// how many fresh short codes to try before giving up on a key
+static SHORT_CODE_MAX_ATTEMPTS: usize = 5;
+
+pub async fn course_key_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseKeyNewProps,
+) -> Result<response::CourseKeyData, response::InnexgoHoursError> {
+  if props.start_time > props.end_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let _ = course_service::get_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // get course manager
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    props.course_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // now create key, short codes are short enough that they sometimes collide
+  let mut attempts = 0;
+  let course_key = loop {
+    // a failed insert spoils the transaction, so try inside a savepoint
+    let mut attempt = sp.transaction().await.map_err(report_postgres_err)?;
+    match course_key_service::add(
+      &mut attempt,
+      &utils::gen_random_string(),
+      user.user_id,
+      props.course_id,
+      props.max_uses,
+      props.course_membership_kind.clone(),
+      props.start_time,
+      props.end_time,
+      props.with_short_code.then(utils::gen_short_code),
+    )
+    .await
+    {
+      Ok(course_key) => {
+        attempt.commit().await.map_err(report_postgres_err)?;
+        break course_key;
+      }
+      Err(e) if is_unique_violation(&e) && attempts < SHORT_CODE_MAX_ATTEMPTS => {
+        attempt.rollback().await.map_err(report_postgres_err)?;
+        attempts += 1;
+      }
+      Err(e) => return Err(report_postgres_err(e)),
+    }
+  };
+
+  // create key data
+  let course_key_data =
+    course_key_data_service::add(&mut sp, user.user_id, course_key.course_key_key, true)
+      .await
+      .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_key_data(con, course_key_data).await
+}
+
+pub async fn course_key_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseKeyDataNewProps,
+) -> Result<response::CourseKeyData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get course key
+  let course_key = course_key_service::get_by_course_key_key(&mut sp, &props.course_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
+
+  // get corresponding course
+  let course = course_service::get_by_course_id(&mut sp, course_key.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, course_key.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // is valid course manager
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    course.course_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create key data
+  let course_key_data = course_key_data_service::add(
+    &mut sp,
+    user.user_id,
+    course_key.course_key_key,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_key_data(con, course_key_data).await
+}
+
+pub async fn course_invitation_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseInvitationNewProps,
+) -> Result<response::Mailed<response::CourseInvitation>, response::InnexgoHoursError> {
+  // leaving a course isn't something you can be invited to do
+  if matches!(
+    props.course_membership_kind,
+    request::CourseMembershipKind::Cancel
+  ) {
+    return Err(response::InnexgoHoursError::CourseMembershipKindInvalid);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // mail goes out once the lock is released, so a slow mail server doesn't hold everyone up
+  let (course_invitation, course_data, resp_course_invitation) = {
+    let con = &mut *db.lock().await;
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    let _ = course_service::get_by_course_id(&mut sp, props.course_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+    // check that course isn't archived
+    let course_data = course_data_service::get_by_course_id(&mut sp, props.course_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+    if !course_data.active {
+      return Err(response::InnexgoHoursError::CourseArchived);
+    }
+
+    // only course managers may invite
+    if !course_membership_service::has_capability(
+      &mut sp,
+      user.user_id,
+      props.course_id,
+      CourseCapability::Manage,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+      return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+    }
+
+    // create invitation
+    let course_invitation = course_invitation_service::add(
+      &mut sp,
+      user.user_id,
+      props.course_id,
+      normalize_email(&props.email),
+      props.course_membership_kind,
+      props.end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    let resp_course_invitation = fill_course_invitation(con, course_invitation.clone()).await?;
+    (course_invitation, course_data, resp_course_invitation)
+  };
+
+  // the invitation stands either way, so a failed mail is logged and returned alongside it
+  // an error here would just get a retrying client a second invitation
+  let mail_error = send_mail(
+    &config,
+    course_invitation.email,
+    "course_invitation",
+    format!("Invitation to join {}", course_data.name),
+    format!(
+      "<p>You've been invited to join {}.</p><p><a href=\"{}/join_course?invitation={}\">Click here to accept.</a></p>",
+      utils::escape_html(&course_data.name),
+      config.site_external_url,
+      course_invitation.course_invitation_id
+    ),
+  )
+  .await
+  .err();
+
+  // return json
+  Ok(response::Mailed {
+    data: resp_course_invitation,
+    mail_error,
+  })
+}
+
+pub async fn course_membership_new_invitation(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseMembershipNewInvitationProps,
+) -> Result<response::CourseMembership, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // invitation must be redeemed by the owner of the email
+  let email = get_user_email(&auth_service, user.user_id).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get invitation
+  let course_invitation =
+    course_invitation_service::get_by_course_invitation_id(&mut sp, props.course_invitation_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseInvitationNonexistent)?;
+
+  if course_invitation.email != email {
+    return Err(response::InnexgoHoursError::CourseInvitationWrongEmail);
+  }
+
+  if utils::current_time_millis() > course_invitation.end_time {
+    return Err(response::InnexgoHoursError::CourseInvitationExpired);
+  }
+
+  // invitations are single use
+  if course_membership_service::count_course_invitation_uses(
+    &mut sp,
+    course_invitation.course_invitation_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+    >= 1
+  {
+    return Err(response::InnexgoHoursError::CourseInvitationUsed);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, course_invitation.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  check_homeroom_membership(
+    &mut sp,
+    user.user_id,
+    course_invitation.course_id,
+    &course_invitation.course_membership_kind,
+  )
+  .await?;
+
+  // now create membership
+  let course_membership = course_membership_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    course_invitation.course_id,
+    course_invitation.course_membership_kind,
+    None,
+    Some(course_invitation.course_invitation_id),
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_membership(con, course_membership).await
+}
+
+pub async fn course_membership_new_key(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseMembershipNewKeyProps,
+) -> Result<response::CourseMembership, response::InnexgoHoursError> {
+  // validate api membership
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get course key (students may type in the short code instead)
+  let course_key = course_key_service::get_by_key_or_short_code(&mut sp, &props.course_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
+
+  // check time, uses, and that the key and course are still active
+  check_course_key_redeemable(&mut sp, &course_key).await?;
+
+  check_homeroom_membership(
+    &mut sp,
+    user.user_id,
+    course_key.course_id,
+    &course_key.course_membership_kind,
+  )
+  .await?;
+
+  // now create membership
+  let course_membership = course_membership_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    course_key.course_id,
+    course_key.course_membership_kind,
+    Some(course_key.course_key_key),
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_membership(con, course_membership).await
+}
+
+pub async fn course_membership_new_cancel(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseMembershipNewCancelProps,
+) -> Result<response::CourseMembership, response::InnexgoHoursError> {
+  // validate api membership
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // validate target exists
+  let target = auth_service
+    .get_user_by_id(props.user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get corresponding course
+  let course = course_service::get_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  let can_manage = course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    course.course_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let is_self_cancel = user.user_id == props.user_id;
+
+  // if user_id == creator_user_id then authorize
+  // otherwise creator_user_id must be an admin or instructor
+  if !(is_self_cancel || can_manage) {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // make sure we cannot leave the course without an instructor
+  if course_membership_service::is_instructor(&mut sp, props.user_id, course.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    let num_instructors = course_membership_service::count_instructors(&mut sp, course.course_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+    if num_instructors <= 1 {
+      return Err(response::InnexgoHoursError::CourseMembershipCannotLeaveEmpty);
+    }
+  }
+
+  // now create membership
+  let course_membership = course_membership_service::add(
+    &mut sp,
+    user.user_id,
+    props.user_id,
+    course.course_id,
+    request::CourseMembershipKind::Cancel,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_course_membership(con, course_membership).await
+}
+
+pub async fn school_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolNewProps,
+) -> Result<response::SchoolData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get subscription
+  let subscription = subscription_service::get_by_user_id(&mut sp, user.user_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SubscriptionNonexistent)?;
+
+  // check if subscription is authorized
+  if adminship_service::count_valid_adminships_by_user_id(&mut sp, user.user_id)
+    .await
+    .map_err(report_postgres_err)?
+    >= subscription.max_uses
+  {
+    return Err(response::InnexgoHoursError::SubscriptionLimited);
+  }
+
+  // create school
+  let school = school_service::add(&mut sp, user.user_id, props.whole)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create data
+  let school_data = school_data_service::add(
+    &mut sp,
+    user.user_id,
+    school.school_id,
+    props.name,
+    props.description,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // create adminship
+  let adminship = adminship_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    school.school_id,
+    request::AdminshipKind::Admin,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_school_data(con, school_data).await
+}
+
+pub async fn school_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDataNewProps,
+) -> Result<response::SchoolData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create data
+  let school_data = school_data_service::add(
+    &mut sp,
+    user.user_id,
+    props.school_id,
+    props.name,
+    props.description,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_school_data(con, school_data).await
+}
+
+pub async fn school_duration_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDurationNewProps,
+) -> Result<response::SchoolDuration, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let school = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create duration
+  let school_duration = school_duration_service::add(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_school_duration(con, school_duration).await
+}
+
+pub async fn school_duration_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDurationDataNewProps,
+) -> Result<response::SchoolDurationData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // durations repeat weekly in utc: day 0 is sunday, and minutes count from midnight utc
+  // schools away from utc have to shift their durations themselves
+  if !(0..7).contains(&props.day)
+    || props.minute_start < 0
+    || props.minute_start >= props.minute_end
+    || props.minute_end > 24 * 60
+  {
+    return Err(response::InnexgoHoursError::SchoolDurationInvalidTimes);
+  }
+
+  let con = &mut *db.lock().await;
+
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // fetch school duration
+  let school_duration =
+    school_duration_service::get_by_school_duration_id(&mut sp, props.school_duration_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SchoolDurationNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, school_duration.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, school_duration.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create data
+  let school_duration_data = school_duration_data_service::add(
+    &mut sp,
+    user.user_id,
+    props.school_duration_id,
+    props.day,
+    props.minute_start,
+    props.minute_end,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_school_duration_data(con, school_duration_data).await
+}
+
+pub async fn term_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::TermNewProps,
+) -> Result<response::TermData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  if props.start_time >= props.end_time {
+    return Err(response::InnexgoHoursError::TermInvalidTimes);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create term
+  let term = term_service::add(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create term data
+  let term_data = term_data_service::add(
+    &mut sp,
+    user.user_id,
+    term.term_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_term_data(con, term_data).await
+}
+
+pub async fn term_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::TermDataNewProps,
+) -> Result<response::TermData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  if props.start_time >= props.end_time {
+    return Err(response::InnexgoHoursError::TermInvalidTimes);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let term = term_service::get_by_term_id(&mut sp, props.term_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::TermNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, term.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, term.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // now we can update data
+  let term_data = term_data_service::add(
+    &mut sp,
+    user.user_id,
+    term.term_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_term_data(con, term_data).await
+}
+
+pub async fn closure_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::ClosureNewProps,
+) -> Result<response::ClosureReport, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  if props.start_time >= props.end_time {
+    return Err(response::InnexgoHoursError::ClosureInvalidTimes);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create closure
+  let closure = closure_service::add(&mut sp, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create closure data
+  let closure_data = closure_data_service::add(
+    &mut sp,
+    user.user_id,
+    closure.closure_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let (report, new_events) =
+    apply_closure(&mut sp, user.user_id, closure.school_id, closure_data).await?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  for event in new_events {
+    publish(&config, event);
+  }
+
+  // return json
+  Ok(report)
+}
+
+// moving or extending a closure cancels whatever it newly covers
+// reopening doesn't bring back sessions that were already cancelled
+pub async fn closure_data_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::ClosureDataNewProps,
+) -> Result<response::ClosureReport, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  if props.start_time >= props.end_time {
+    return Err(response::InnexgoHoursError::ClosureInvalidTimes);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let closure = closure_service::get_by_closure_id(&mut sp, props.closure_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::ClosureNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, closure.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, closure.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // now we can update data
+  let closure_data = closure_data_service::add(
+    &mut sp,
+    user.user_id,
+    closure.closure_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let (report, new_events) =
+    apply_closure(&mut sp, user.user_id, closure.school_id, closure_data).await?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  for event in new_events {
+    publish(&config, event);
+  }
+
+  // return json
+  Ok(report)
+}
+
+// creates a new term, copies every active course of the old term (with its data and staff)
+// into it, and then archives the old courses and the old term
+pub async fn term_rollover(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::TermRolloverProps,
+) -> Result<response::TermData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  if props.start_time >= props.end_time {
+    return Err(response::InnexgoHoursError::TermInvalidTimes);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let old_term = term_service::get_by_term_id(&mut sp, props.term_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::TermNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, old_term.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, old_term.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // an archived term has already been rolled over, or was never meant to be
+  let old_term_data = term_data_service::get_by_term_id(&mut sp, old_term.term_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::TermNonexistent)?;
+
+  if !old_term_data.active {
+    return Err(response::InnexgoHoursError::TermArchived);
+  }
+
+  // create the new term
+  let new_term = term_service::add(&mut sp, user.user_id, old_term.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let new_term_data = term_data_service::add(
+    &mut sp,
+    user.user_id,
+    new_term.term_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let old_course_datas = course_data_service::get_active_by_term_id(&mut sp, old_term.term_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  for old_course_data in old_course_datas {
+    // clone course into the new term
+    let new_course = course_service::add(&mut sp, user.user_id, old_term.school_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+    let _ = course_data_service::add(
+      &mut sp,
+      user.user_id,
+      new_course.course_id,
+      old_course_data.location_id,
+      Some(new_term.term_id),
+      old_course_data.name.clone(),
+      old_course_data.description.clone(),
+      old_course_data.homeroom,
+      true,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    // carry over all staff in their roles, students must join the new course themselves
+    let old_memberships =
+      course_membership_service::get_by_course_id(&mut sp, old_course_data.course_id)
+        .await
+        .map_err(report_postgres_err)?;
+
+    for old_membership in old_memberships {
+      if matches!(
+        old_membership.course_membership_kind,
+        request::CourseMembershipKind::Instructor
+          | request::CourseMembershipKind::TeachingAssistant
+          | request::CourseMembershipKind::Observer
+      ) {
+        let _ = course_membership_service::add(
+          &mut sp,
+          user.user_id,
+          old_membership.user_id,
+          new_course.course_id,
+          old_membership.course_membership_kind,
+          None,
+          None,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+      }
+    }
+
+    // archive the old course, it stays in the old term
+    let _ = course_data_service::add(
+      &mut sp,
+      user.user_id,
+      old_course_data.course_id,
+      old_course_data.location_id,
+      old_course_data.term_id,
+      old_course_data.name,
+      old_course_data.description,
+      old_course_data.homeroom,
+      false,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+  }
+
+  // archive the old term
+  let _ = term_data_service::add(
+    &mut sp,
+    user.user_id,
+    old_term.term_id,
+    old_term_data.name,
+    old_term_data.start_time,
+    old_term_data.end_time,
+    false,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_term_data(con, new_term_data).await
+}
+
+pub async fn school_key_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolKeyNewProps,
+) -> Result<response::SchoolKeyData, response::InnexgoHoursError> {
+  if props.start_time > props.end_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let school = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  // get is admin
+  if !adminship_service::is_admin(&mut sp, user.user_id, school.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // now create key, short codes are short enough that they sometimes collide
+  let mut attempts = 0;
+  let school_key = loop {
+    // a failed insert spoils the transaction, so try inside a savepoint
+    let mut attempt = sp.transaction().await.map_err(report_postgres_err)?;
+    match school_key_service::add(
+      &mut attempt,
+      &utils::gen_random_string(),
+      user.user_id,
+      props.school_id,
+      props.start_time,
+      props.end_time,
+      props.with_short_code.then(utils::gen_short_code),
+    )
+    .await
+    {
+      Ok(school_key) => {
+        attempt.commit().await.map_err(report_postgres_err)?;
+        break school_key;
+      }
+      Err(e) if is_unique_violation(&e) && attempts < SHORT_CODE_MAX_ATTEMPTS => {
+        attempt.rollback().await.map_err(report_postgres_err)?;
+        attempts += 1;
+      }
+      Err(e) => return Err(report_postgres_err(e)),
+    }
+  };
+
+  // create key data
+  let school_key_data =
+    school_key_data_service::add(&mut sp, user.user_id, school_key.school_key_key, true)
+      .await
+      .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_school_key_data(con, school_key_data).await
+}
+
+pub async fn school_key_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolKeyDataNewProps,
+) -> Result<response::SchoolKeyData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get school key
+  let school_key = school_key_service::get_by_school_key_key(&mut sp, &props.school_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolKeyNonexistent)?;
+
+  // get corresponding school
+  let school = school_service::get_by_school_id(&mut sp, school_key.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, school_key.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  // get admin
+  if !adminship_service::is_admin(&mut sp, user.user_id, school.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create key data
+  let school_key_data = school_key_data_service::add(
+    &mut sp,
+    user.user_id,
+    school_key.school_key_key,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_school_key_data(con, school_key_data).await
+}
+
+pub async fn course_key_link(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseKeyLinkProps,
+) -> Result<response::CourseKeyLink, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  // get course key
+  let course_key = course_key_service::get_by_course_key_key(con, &props.course_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
+
+  // only course managers may share course keys
+  if !course_membership_service::has_capability(
+    con,
+    user.user_id,
+    course_key.course_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // don't hand out a link that can't be used
+  check_course_key_redeemable(con, &course_key).await?;
+
+  // prefer the short code, since it makes for a smaller qr code
+  let join_url = format!(
+    "{}/join_course?key={}",
+    config.site_external_url,
+    course_key
+      .short_code
+      .as_ref()
+      .unwrap_or(&course_key.course_key_key)
+  );
+
+  let qr_svg = utils::render_qr_svg(&join_url).map_err(report_qr_err)?;
+
+  // return json
+  Ok(response::CourseKeyLink {
+    course_key: fill_course_key(con, course_key).await?,
+    join_url,
+    qr_svg,
+  })
+}
+
+pub async fn school_key_link(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolKeyLinkProps,
+) -> Result<response::SchoolKeyLink, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  // get school key
+  let school_key = school_key_service::get_by_school_key_key(con, &props.school_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolKeyNonexistent)?;
+
+  // only admins may share school keys
+  if !adminship_service::is_admin(con, user.user_id, school_key.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // don't hand out a link that can't be used
+  check_school_key_redeemable(con, &school_key).await?;
+
+  // prefer the short code, since it makes for a smaller qr code
+  let join_url = format!(
+    "{}/join_school?key={}",
+    config.site_external_url,
+    school_key
+      .short_code
+      .as_ref()
+      .unwrap_or(&school_key.school_key_key)
+  );
+
+  let qr_svg = utils::render_qr_svg(&join_url).map_err(report_qr_err)?;
+
+  // return json
+  Ok(response::SchoolKeyLink {
+    school_key: fill_school_key(con, school_key).await?,
+    join_url,
+    qr_svg,
+  })
+}
+
+pub async fn school_invitation_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolInvitationNewProps,
+) -> Result<response::Mailed<response::SchoolInvitation>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // mail goes out once the lock is released, so a slow mail server doesn't hold everyone up
+  let (school_invitation, school_data, resp_school_invitation) = {
+    let con = &mut *db.lock().await;
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+    // check that school isn't archived
+    let school_data = school_data_service::get_by_school_id(&mut sp, props.school_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+    if !school_data.active {
+      return Err(response::InnexgoHoursError::SchoolArchived);
+    }
+
+    // only admins may invite other admins
+    if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+      .await
+      .map_err(report_postgres_err)?
+    {
+      return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+    }
+
+    // create invitation
+    let school_invitation = school_invitation_service::add(
+      &mut sp,
+      user.user_id,
+      props.school_id,
+      normalize_email(&props.email),
+      props.end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    let resp_school_invitation = fill_school_invitation(con, school_invitation.clone()).await?;
+    (school_invitation, school_data, resp_school_invitation)
+  };
+
+  // the invitation stands either way, so a failed mail is logged and returned alongside it
+  // an error here would just get a retrying client a second invitation
+  let mail_error = send_mail(
+    &config,
+    school_invitation.email,
+    "school_invitation",
+    format!("Invitation to administer {}", school_data.name),
+    format!(
+      "<p>You've been invited to become an administrator of {}.</p><p><a href=\"{}/join_school?invitation={}\">Click here to accept.</a></p>",
+      utils::escape_html(&school_data.name),
+      config.site_external_url,
+      school_invitation.school_invitation_id
+    ),
+  )
+  .await
+  .err();
+
+  // return json
+  Ok(response::Mailed {
+    data: resp_school_invitation,
+    mail_error,
+  })
+}
+
+pub async fn adminship_new_invitation(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AdminshipNewInvitationProps,
+) -> Result<response::Adminship, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // invitation must be redeemed by the owner of the email
+  let email = get_user_email(&auth_service, user.user_id).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get invitation
+  let school_invitation =
+    school_invitation_service::get_by_school_invitation_id(&mut sp, props.school_invitation_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SchoolInvitationNonexistent)?;
+
+  if school_invitation.email != email {
+    return Err(response::InnexgoHoursError::SchoolInvitationWrongEmail);
+  }
+
+  if utils::current_time_millis() > school_invitation.end_time {
+    return Err(response::InnexgoHoursError::SchoolInvitationExpired);
+  }
+
+  // invitations are single use
+  if adminship_service::count_school_invitation_uses(
+    &mut sp,
+    school_invitation.school_invitation_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+    >= 1
+  {
+    return Err(response::InnexgoHoursError::SchoolInvitationUsed);
+  }
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, school_invitation.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  // now create adminship
+  let adminship = adminship_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    school_invitation.school_id,
+    request::AdminshipKind::Admin,
+    None,
+    Some(school_invitation.school_invitation_id),
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_adminship(con, adminship).await
+}
+
+pub async fn adminship_new_key(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AdminshipNewKeyProps,
+) -> Result<response::Adminship, response::InnexgoHoursError> {
+  // validate api membership
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // get school key (the short code may be typed in instead)
+  let school_key = school_key_service::get_by_key_or_short_code(&mut sp, &props.school_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolKeyNonexistent)?;
+
+  // check time, uses, and that the key and school are still active
+  check_school_key_redeemable(&mut sp, &school_key).await?;
+
+  // now create adminship
+  let adminship = adminship_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    school_key.school_id,
+    request::AdminshipKind::Admin,
+    Some(school_key.school_key_key),
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_adminship(con, adminship).await
+}
+
+pub async fn adminship_new_cancel(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AdminshipNewCancelProps,
+) -> Result<response::Adminship, response::InnexgoHoursError> {
+  // validate api membership
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // validate target exists
+  let target = auth_service
+    .get_user_by_id(props.user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // check that school isn't archived
+  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::SchoolArchived);
+  }
+
+  // get corresponding school
+  let school = school_service::get_by_school_id(&mut sp, props.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+
+  if !adminship_service::is_admin(&mut sp, user.user_id, school.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // make sure we cannot leave the school without an admin
+  if adminship_service::count_admins(&mut sp, school.school_id)
+    .await
+    .map_err(report_postgres_err)?
+    <= 1
+  {
+    return Err(response::InnexgoHoursError::AdminshipCannotLeaveEmpty);
+  }
+
+  // now create cancel
+  let adminship = adminship_service::add(
+    &mut sp,
+    user.user_id,
+    props.user_id,
+    school.school_id,
+    request::AdminshipKind::Cancel,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // commit changes
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_adminship(con, adminship).await
+}
+
+pub async fn guardianship_new_request(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::GuardianshipNewRequestProps,
+) -> Result<response::Guardianship, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // validate student exists
+  let student = auth_service
+    .get_user_by_id(props.student_user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  // you can't be your own guardian
+  if student.user_id == user.user_id {
+    return Err(response::InnexgoHoursError::GuardianshipSelf);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // don't re-request an existing guardianship
+  if guardianship_service::get_by_guardian_user_id_student_user_id(
+    &mut sp,
+    user.user_id,
+    student.user_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  .filter(|x| !matches!(x.guardianship_kind, request::GuardianshipKind::Cancel))
+  .is_some()
+  {
+    return Err(response::InnexgoHoursError::GuardianshipExistent);
+  }
+
+  // the request stays pending until the student approves it
+  let guardianship = guardianship_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    student.user_id,
+    request::GuardianshipKind::Requested,
+    props.weekly_summary,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_guardianship(con, guardianship).await
+}
+
+pub async fn guardianship_new_approve(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::GuardianshipNewApproveProps,
+) -> Result<response::Guardianship, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // only the student may approve a pending request made of them
+  let requested = guardianship_service::get_by_guardian_user_id_student_user_id(
+    &mut sp,
+    props.guardian_user_id,
+    user.user_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  .filter(|x| matches!(x.guardianship_kind, request::GuardianshipKind::Requested))
+  .ok_or(response::InnexgoHoursError::GuardianshipNonexistent)?;
+
+  let guardianship = guardianship_service::add(
+    &mut sp,
+    user.user_id,
+    requested.guardian_user_id,
+    requested.student_user_id,
+    request::GuardianshipKind::Active,
+    requested.weekly_summary,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_guardianship(con, guardianship).await
+}
+
+pub async fn guardianship_new_admin(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::GuardianshipNewAdminProps,
+) -> Result<response::Guardianship, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  // validate guardian and student exist
+  let guardian = auth_service
+    .get_user_by_id(props.guardian_user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  let student = auth_service
+    .get_user_by_id(props.student_user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  if guardian.user_id == student.user_id {
+    return Err(response::InnexgoHoursError::GuardianshipSelf);
+  }
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // only admins of a school the student attends may issue guardianships directly
+  if !guardianship_service::is_admin_of_student(&mut sp, user.user_id, student.user_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let guardianship = guardianship_service::add(
+    &mut sp,
+    user.user_id,
+    guardian.user_id,
+    student.user_id,
+    request::GuardianshipKind::Active,
+    props.weekly_summary,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_guardianship(con, guardianship).await
+}
+
+pub async fn guardianship_new_cancel(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::GuardianshipNewCancelProps,
+) -> Result<response::Guardianship, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let current = guardianship_service::get_by_guardian_user_id_student_user_id(
+    &mut sp,
+    props.guardian_user_id,
+    props.student_user_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  .filter(|x| !matches!(x.guardianship_kind, request::GuardianshipKind::Cancel))
+  .ok_or(response::InnexgoHoursError::GuardianshipNonexistent)?;
+
+  // the guardian, the student, or one of the student's admins may cancel
+  let is_party =
+    user.user_id == current.guardian_user_id || user.user_id == current.student_user_id;
+
+  let is_admin =
+    guardianship_service::is_admin_of_student(&mut sp, user.user_id, current.student_user_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+  if !is_party && !is_admin {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let guardianship = guardianship_service::add(
+    &mut sp,
+    user.user_id,
+    current.guardian_user_id,
+    current.student_user_id,
+    request::GuardianshipKind::Cancel,
+    false,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_guardianship(con, guardianship).await
+}
+
+pub async fn session_request_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionRequestNewProps,
+) -> Result<response::SessionRequest, response::InnexgoHoursError> {
+  if props.start_time > props.end_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // check course exists
+  let course = course_service::get_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // no point asking for time when the school is closed
+  check_school_open(&mut sp, course.school_id, props.start_time, props.end_time).await?;
+
+  // check is student of course
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    props.course_id,
+    CourseCapability::Attend,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create request
+  let session_request = session_request_service::add(
+    &mut sp,
+    user.user_id,
+    props.course_id,
+    props.message,
+    props.start_time,
+    props.end_time,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  publish(
+    &config,
+    events::Event::SessionRequest(session_request.clone()),
+  );
+
+  // return json
+  fill_session_request(con, session_request).await
+}
+
+pub async fn session_request_response_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionRequestResponseNewProps,
+) -> Result<response::SessionRequestResponse, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // check session request exists
+  let session_request =
+    session_request_service::get_by_session_request_id(&mut sp, props.session_request_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
+
+  // check that session request response doesn't exist
+  if session_request_response_service::get_by_session_request_id(&mut sp, props.session_request_id)
+    .await
+    .map_err(report_postgres_err)?
+    .is_some()
+  {
+    return Err(response::InnexgoHoursError::SessionRequestResponseExistent);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, session_request.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // check this user may run sessions (instructors and TAs)
+  let can_run_sessions = course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    session_request.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let is_creator = user.user_id == session_request.creator_user_id;
+
+  // a newly accepted request may also create a commitment
+  let mut new_commitment = None;
+
+  let commitment_id = match props.session_id {
+    None => {
+      // either the creator of the request or course staff may cancel
+      if !can_run_sessions && !is_creator {
+        return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+      }
+      None
+    }
+    // if accepted
+    Some(session_id) => {
+      // only course staff can create a commitment
+      if !can_run_sessions {
+        return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+      }
+
+      // check if session id is valid
+      let session = session_service::get_by_session_id(&mut sp, session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+      // check that session is compatible with session_request's course
+      if session_request.course_id != session.course_id {
+        return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+      }
+
+      // check if we already have a commitment
+      let maybe_commitment = commitment_service::get_by_attendee_user_id_session_id(
+        &mut sp,
+        session_request.creator_user_id,
+        session_id,
+      )
+      .await
+      .map_err(report_postgres_err)?;
+
+      let commitment = match maybe_commitment {
+        // use commitment if exists
+        Some(commitment) => commitment,
+
+        // otherwise add one
+        None => {
+          let commitment = commitment_service::add(
+            &mut sp,
+            user.user_id,
+            session_request.creator_user_id,
+            session_id,
+            true,
+          )
+          .await
+          .map_err(report_postgres_err)?;
+          new_commitment = Some(commitment.clone());
+          commitment
+        }
+      };
+      // return
+      Some(commitment.commitment_id)
+    }
+  };
+
+  // create request response
+  let session_request_response = session_request_response_service::add(
+    &mut sp,
+    props.session_request_id,
+    user.user_id,
+    props.message,
+    commitment_id,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  if let Some(commitment) = new_commitment {
+    publish(&config, events::Event::Commitment(commitment));
+  }
+  publish(
+    &config,
+    events::Event::SessionRequestResponse(session_request_response.clone()),
+  );
+
+  // return json
+  fill_session_request_response(con, session_request_response).await
+}
+
+pub async fn session_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionNewProps,
+) -> Result<response::Scheduled<response::SessionData>, response::InnexgoHoursError> {
+  if props.start_time > props.end_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate course exists
+  let course = course_service::get_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  // ensure session creator may run sessions
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    props.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  check_school_open(&mut sp, course.school_id, props.start_time, props.end_time).await?;
+
+  let conflicts = get_session_conflicts(
+    &mut sp,
+    props.course_id,
+    None,
+    &props.attendee_user_ids,
+    props.start_time,
+    props.end_time,
+  )
+  .await?;
+  check_conflicts(&props.conflict_mode, &conflicts)?;
+
+  // create session
+  let session = session_service::add(&mut sp, user.user_id, props.course_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create session data
+  let session_data = session_data_service::add(
+    &mut sp,
+    user.user_id,
+    session.session_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let mut new_events = vec![];
+
+  // create session from provided users automatically
+  for attendee_user_id in props.attendee_user_ids {
+    // ensure attendee is the student of the session's course
+    if !course_membership_service::has_capability(
+      &mut sp,
+      attendee_user_id,
+      session.course_id,
+      CourseCapability::Attend,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+      return Err(response::InnexgoHoursError::UserNonexistent);
+    }
+
+    // create a new committment
+    let commitment = commitment_service::add(
+      &mut sp,
+      user.user_id,
+      attendee_user_id,
+      session.session_id,
+      true,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    new_events.push(events::Event::Commitment(commitment));
+  }
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  for event in new_events {
+    publish(&config, event);
+  }
+
+  // return json
+  Ok(response::Scheduled {
+    data: fill_session_data(con, session_data).await?,
+    conflicts: conflicts.into_iter().map(fill_conflict).collect(),
+  })
+}
+
+// renames a session
+// moving or cancelling one has to go through session_reschedule or session_cancel,
+// so that attendees get a session change explaining why
+pub async fn session_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionDataNewProps,
+) -> Result<response::SessionData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let (session, session_data) =
+    get_running_session(&mut sp, user.user_id, props.session_id).await?;
+
+  if !props.active {
+    return Err(response::InnexgoHoursError::SessionDataCannotCancel);
+  }
+
+  if props.start_time != session_data.start_time || props.end_time != session_data.end_time {
+    return Err(response::InnexgoHoursError::SessionDataCannotMove);
+  }
+
+  // now we can update data
+  let session_data = session_data_service::add(
+    &mut sp,
+    user.user_id,
+    session.session_id,
+    props.name,
+    session_data.start_time,
+    session_data.end_time,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_session_data(con, session_data).await
+}
+
+// looks up a session that the user may run and that hasn't already been cancelled
+async fn get_running_session(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  session_id: i64,
+) -> Result<(Session, SessionData), response::InnexgoHoursError> {
+  let session = session_service::get_by_session_id(con, session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  // ensure user may run sessions
+  if !course_membership_service::has_capability(
+    con,
+    user_id,
+    session.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let session_data = session_data_service::get_by_session_id(con, session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  if !session_data.active {
+    return Err(response::InnexgoHoursError::SessionCancelled);
+  }
+
+  Ok((session, session_data))
+}
+
+pub async fn session_cancel(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionCancelProps,
+) -> Result<response::SessionChangeReport, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let (_, session_data) = get_running_session(&mut sp, user.user_id, props.session_id).await?;
+
+  let (session_change, session_data, commitments) =
+    cancel_session(&mut sp, user.user_id, session_data, props.reason).await?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // let attendees know their commitments are gone, and why
+  for commitment in commitments.iter() {
+    publish(&config, events::Event::Commitment(commitment.clone()));
+  }
+  publish(
+    &config,
+    events::Event::SessionChange(session_change.clone()),
+  );
+
+  let mut resp_commitments = vec![];
+  for x in commitments.into_iter() {
+    resp_commitments.push(fill_commitment(con, x).await?);
+  }
+
+  // return json
+  Ok(response::SessionChangeReport {
+    session_change: fill_session_change(con, session_change).await?,
+    session_data: fill_session_data(con, session_data).await?,
+    commitments: resp_commitments,
+  })
+}
+
+// moves a session, everyone committed to it stays committed
+// the room being taken counts as a location conflict, just like when the session was made
+pub async fn session_reschedule(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionRescheduleProps,
+) -> Result<response::Scheduled<response::SessionChangeReport>, response::InnexgoHoursError> {
+  // prevent negative duration
+  if props.start_time > props.end_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let (session, session_data) =
+    get_running_session(&mut sp, user.user_id, props.session_id).await?;
+
+  let course = course_service::get_by_course_id(&mut sp, session.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+  check_school_open(&mut sp, course.school_id, props.start_time, props.end_time).await?;
+
+  let commitments = commitment_service::get_active_by_session_id(&mut sp, session.session_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let attendee_user_ids: Vec<i64> = commitments.iter().map(|x| x.attendee_user_id).collect();
+
+  let conflicts = get_session_conflicts(
+    &mut sp,
+    session.course_id,
+    Some(session.session_id),
+    &attendee_user_ids,
+    props.start_time,
+    props.end_time,
+  )
+  .await?;
+  check_conflicts(&props.conflict_mode, &conflicts)?;
+
+  let session_change = session_change_service::add(
+    &mut sp,
+    user.user_id,
+    session.session_id,
+    request::SessionChangeKind::Reschedule,
+    props.reason,
+    session_data.start_time,
+    session_data.end_time,
+    Some(props.start_time),
+    Some(props.end_time),
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let session_data = session_data_service::add(
+    &mut sp,
+    user.user_id,
+    session.session_id,
+    session_data.name,
+    props.start_time,
+    props.end_time,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  publish(
+    &config,
+    events::Event::SessionChange(session_change.clone()),
+  );
+
+  let mut resp_commitments = vec![];
+  for x in commitments.into_iter() {
+    resp_commitments.push(fill_commitment(con, x).await?);
+  }
+
+  // return json
+  Ok(response::Scheduled {
+    data: response::SessionChangeReport {
+      session_change: fill_session_change(con, session_change).await?,
+      session_data: fill_session_data(con, session_data).await?,
+      commitments: resp_commitments,
+    },
+    conflicts: conflicts.into_iter().map(fill_conflict).collect(),
+  })
+}
+
+pub async fn session_note_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionNoteNewProps,
+) -> Result<response::SessionNoteData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let session = session_service::get_by_session_id(&mut sp, props.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  // ensure note writer may run sessions
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    session.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, session.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // create session note
+  let session_note = session_note_service::add(&mut sp, user.user_id, session.session_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // create session note data
+  let session_note_data = session_note_data_service::add(
+    &mut sp,
+    user.user_id,
+    session_note.session_note_id,
+    props.session_note_kind,
+    props.session_note_visibility,
+    props.content,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_session_note_data(con, session_note_data).await
+}
+
+pub async fn session_note_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionNoteDataNewProps,
+) -> Result<response::SessionNoteData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let session_note = session_note_service::get_by_session_note_id(&mut sp, props.session_note_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNoteNonexistent)?;
+
+  let session = session_service::get_by_session_id(&mut sp, session_note.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  // ensure note writer may run sessions
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    session.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, session.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // now we can update data
+  let session_note_data = session_note_data_service::add(
+    &mut sp,
+    user.user_id,
+    session_note.session_note_id,
+    props.session_note_kind,
+    props.session_note_visibility,
+    props.content,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_session_note_data(con, session_note_data).await
+}
+
+// ratings go from 1 to this
+static FEEDBACK_MAX_RATING: i64 = 5;
+
+// how long after leaving feedback it can still be changed
+static FEEDBACK_EDIT_WINDOW: i64 = 24 * 60 * 60 * 1000;
+
+// leaves feedback on a session the user attended, once it's over
+pub async fn feedback_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::FeedbackNewProps,
+) -> Result<response::FeedbackData, response::InnexgoHoursError> {
+  if !(1..=FEEDBACK_MAX_RATING).contains(&props.rating) {
+    return Err(response::InnexgoHoursError::FeedbackRatingInvalid);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let commitment = commitment_service::get_by_commitment_id(&mut sp, props.commitment_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
+
+  // only the attendee themselves may leave feedback
+  if commitment.attendee_user_id != user.user_id {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // and only if they were still going when the session happened
+  let current = commitment_service::get_by_attendee_user_id_session_id(
+    &mut sp,
+    commitment.attendee_user_id,
+    commitment.session_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
+
+  if current.commitment_id != commitment.commitment_id || !current.active {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let session_data = session_data_service::get_by_session_id(&mut sp, commitment.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  if !session_data.active {
+    return Err(response::InnexgoHoursError::SessionCancelled);
+  }
+
+  if session_data.end_time > utils::current_time_millis() {
+    return Err(response::InnexgoHoursError::FeedbackSessionNotEnded);
+  }
+
+  // once per attendee, even if they dropped out and came back
+  if feedback_service::get_by_attendee_user_id_session_id(
+    &mut sp,
+    commitment.attendee_user_id,
+    commitment.session_id,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  .is_some()
+  {
+    return Err(response::InnexgoHoursError::FeedbackExists);
+  }
+
+  let feedback = feedback_service::add(&mut sp, user.user_id, commitment.commitment_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let feedback_data = feedback_data_service::add(
+    &mut sp,
+    user.user_id,
+    feedback.feedback_id,
+    props.rating,
+    props.comment,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_feedback_data(con, feedback_data).await
+}
+
+// edits or retracts feedback, only for a little while after it was left
+pub async fn feedback_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::FeedbackDataNewProps,
+) -> Result<response::FeedbackData, response::InnexgoHoursError> {
+  if !(1..=FEEDBACK_MAX_RATING).contains(&props.rating) {
+    return Err(response::InnexgoHoursError::FeedbackRatingInvalid);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let feedback = feedback_service::get_by_feedback_id(&mut sp, props.feedback_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::FeedbackNonexistent)?;
+
+  if feedback.creator_user_id != user.user_id {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  if utils::current_time_millis() > feedback.creation_time + FEEDBACK_EDIT_WINDOW {
+    return Err(response::InnexgoHoursError::FeedbackEditWindowClosed);
+  }
+
+  let feedback_data = feedback_data_service::add(
+    &mut sp,
+    user.user_id,
+    feedback.feedback_id,
+    props.rating,
+    props.comment,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_feedback_data(con, feedback_data).await
+}
+
+// how long a meeting pass can be followed for after it's handed out
+static MEETING_PASS_DURATION: i64 = 5 * 60 * 1000;
+
+// sets the link for a location or a session, the newest one is the one in use
+pub async fn meeting_link_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::MeetingLinkNewProps,
+) -> Result<response::MeetingLink, response::InnexgoHoursError> {
+  // we redirect browsers here, so nothing like javascript: urls
+  if !props.url.starts_with("https://") && !props.url.starts_with("http://") {
+    return Err(response::InnexgoHoursError::MeetingLinkInvalid);
+  }
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  if !can_manage_meeting_link(&mut sp, user.user_id, props.location_id, props.session_id).await? {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let meeting_link = meeting_link_service::add(
+    &mut sp,
+    user.user_id,
+    props.location_id,
+    props.session_id,
+    props.url,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_meeting_link(con, meeting_link).await
+}
+
+// hands out a one time pass to the session's meeting, following it is what records the join
+pub async fn meeting_pass_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::MeetingPassNewProps,
+) -> Result<response::MeetingPass, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let session = session_service::get_by_session_id(&mut sp, props.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  if !can_join_session(&mut sp, user.user_id, &session).await? {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let session_data = session_data_service::get_by_session_id(&mut sp, session.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  if !session_data.active {
+    return Err(response::InnexgoHoursError::SessionCancelled);
+  }
+
+  let meeting_link = meeting_link_service::get_active_for_session_id(&mut sp, session.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::MeetingLinkNonexistent)?;
+
+  let meeting_pass = meeting_pass_service::add(
+    &mut sp,
+    user.user_id,
+    session.session_id,
+    meeting_link.meeting_link_id,
+    utils::gen_random_string(),
+    utils::current_time_millis() + MEETING_PASS_DURATION,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_meeting_pass(con, meeting_pass).await
+}
+
+// follows a meeting pass, returning the url to send the browser to
+// the token is all the proof we need, since it was only ever given to the person it was issued to
+pub async fn meeting_pass_redeem(
+  _config: Config,
+  db: Db,
+  meeting_pass_token: String,
+) -> Result<String, response::InnexgoHoursError> {
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let meeting_pass = meeting_pass_service::get_by_meeting_pass_token(&mut sp, &meeting_pass_token)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::MeetingPassNonexistent)?;
+
+  if utils::current_time_millis() > meeting_pass.expiration_time {
+    return Err(response::InnexgoHoursError::MeetingPassExpired);
+  }
+
+  let meeting_link =
+    meeting_link_service::get_by_meeting_link_id(&mut sp, meeting_pass.meeting_link_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::MeetingLinkNonexistent)?;
+
+  // the link may have been taken down since the pass was handed out
+  let current = meeting_link_service::get_active_for_session_id(&mut sp, meeting_pass.session_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  if current.map(|x| x.meeting_link_id) != Some(meeting_link.meeting_link_id) {
+    return Err(response::InnexgoHoursError::MeetingLinkNonexistent);
+  }
+
+  // record the join, which fails if the pass was already used
+  meeting_join_service::add(&mut sp, meeting_pass.meeting_pass_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::MeetingPassExpired)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  Ok(meeting_link.url)
+}
+
+// uploads bigger than this are turned away
+static ATTACHMENT_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+// largest body attachment_new will read
+// base64 makes the file a third bigger, the rest leaves room for the other props
+pub static ATTACHMENT_NEW_BODY_LIMIT: u64 = 14 * 1024 * 1024;
+
+// the kinds of file we take, and the bytes a file of that kind has to start with
+static ATTACHMENT_MEDIA_TYPES: &[(&str, &[u8])] = &[
+  ("image/png", b"\x89PNG\r\n\x1a\n"),
+  ("image/jpeg", b"\xff\xd8\xff"),
+  ("image/gif", b"GIF8"),
+  ("application/pdf", b"%PDF-"),
+  ("text/plain", b""),
+];
+
+fn validate_attachment(media_type: &str, data: &[u8]) -> Result<(), response::InnexgoHoursError> {
+  if data.len() > ATTACHMENT_MAX_SIZE {
+    return Err(response::InnexgoHoursError::AttachmentTooLarge);
+  }
+
+  // don't take the uploader's word for what the file is
+  match ATTACHMENT_MEDIA_TYPES
+    .iter()
+    .find(|(t, _)| *t == media_type)
+  {
+    // text has no magic bytes, but it does have to be text
+    Some((t, _)) if *t == "text/plain" => std::str::from_utf8(data)
+      .map(|_| ())
+      .map_err(|_| response::InnexgoHoursError::AttachmentMediaTypeInvalid),
+    Some((_, magic)) if data.starts_with(magic) => Ok(()),
+    _ => Err(response::InnexgoHoursError::AttachmentMediaTypeInvalid),
+  }
+}
+
+// attaches a file to exactly one of a session request or a session
+pub async fn attachment_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AttachmentNewProps,
+) -> Result<response::Attachment, response::InnexgoHoursError> {
+  // the contents are sent base64url encoded
+  let data =
+    base64_url::decode(&props.data).map_err(|_| response::InnexgoHoursError::DecodeError)?;
+
+  validate_attachment(&props.media_type, &data)?;
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let may_attach = match (props.session_request_id, props.session_id) {
+    // whoever filed the request, or anyone who could answer it
+    (Some(session_request_id), None) => {
+      let session_request =
+        session_request_service::get_by_session_request_id(&mut sp, session_request_id)
+          .await
+          .map_err(report_postgres_err)?
+          .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
+
+      session_request.creator_user_id == user.user_id
+        || course_membership_service::has_capability(
+          &mut sp,
+          user.user_id,
+          session_request.course_id,
+          CourseCapability::RunSessions,
+        )
+        .await
+        .map_err(report_postgres_err)?
+    }
+    // handouts come from those who run the session
+    (None, Some(session_id)) => {
+      let session = session_service::get_by_session_id(&mut sp, session_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+      course_membership_service::has_capability(
+        &mut sp,
+        user.user_id,
+        session.course_id,
+        CourseCapability::RunSessions,
+      )
+      .await
+      .map_err(report_postgres_err)?
+    }
+    _ => return Err(response::InnexgoHoursError::AttachmentTargetInvalid),
+  };
+
+  if !may_attach {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // identical files end up under the same key, so they're only stored once
+  let checksum = utils::checksum(&data);
+
+  let attachment = attachment_service::add(
+    &mut sp,
+    user.user_id,
+    props.session_request_id,
+    props.session_id,
+    props.name,
+    props.media_type,
+    data.len() as i64,
+    checksum.clone(),
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // only store the file once its row is in, a failed store rolls the row back
+  // the blob isn't removed if the commit fails, since other attachments may share it
+  config
+    .blob_store
+    .put(&checksum, &data)
+    .await
+    .map_err(report_blob_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_attachment(con, attachment).await
+}
+
+// slots shorter than this aren't worth booking
+static MIN_SLOT_DURATION: i64 = 5 * 60 * 1000;
+
+// slots are worked out while the database is held, so there can't be too many of them at once
+static MAX_SLOTS: i64 = 5000;
+
+// availabilities must fit at least one slot, and not too many
+fn validate_availability(
+  start_time: i64,
+  end_time: i64,
+  slot_duration: i64,
+) -> Result<(), response::InnexgoHoursError> {
+  if start_time > end_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  if slot_duration < MIN_SLOT_DURATION || slot_duration > end_time - start_time {
+    return Err(response::InnexgoHoursError::AvailabilitySlotDurationInvalid);
+  }
+
+  if (end_time - start_time) / slot_duration > MAX_SLOTS {
+    return Err(response::InnexgoHoursError::AvailabilityTooManySlots);
+  }
+
+  Ok(())
+}
+
+pub async fn availability_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AvailabilityNewProps,
+) -> Result<response::AvailabilityData, response::InnexgoHoursError> {
+  validate_availability(props.start_time, props.end_time, props.slot_duration)?;
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate course exists
+  let _ = course_service::get_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  // the sessions made from bookings are run by whoever published the availability
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    props.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  let availability = availability_service::add(&mut sp, user.user_id, props.course_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let availability_data = availability_data_service::add(
+    &mut sp,
+    user.user_id,
+    availability.availability_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    props.slot_duration,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_availability_data(con, availability_data).await
+}
+
+pub async fn availability_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AvailabilityDataNewProps,
+) -> Result<response::AvailabilityData, response::InnexgoHoursError> {
+  validate_availability(props.start_time, props.end_time, props.slot_duration)?;
+
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  let availability = availability_service::get_by_availability_id(&mut sp, props.availability_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
+
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    availability.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // slots that were already booked keep their sessions
+  let availability_data = availability_data_service::add(
+    &mut sp,
+    user.user_id,
+    availability.availability_id,
+    props.name,
+    props.start_time,
+    props.end_time,
+    props.slot_duration,
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_availability_data(con, availability_data).await
+}
+
+pub async fn availability_booking_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::AvailabilityBookingNewProps,
+) -> Result<response::AvailabilityBooking, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // held until we commit, so nobody else can book from this availability in the meantime
+  let availability = availability_service::lock_by_availability_id(&mut sp, props.availability_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
+
+  let availability_data =
+    availability_data_service::get_by_availability_id(&mut sp, availability.availability_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
+
+  if !availability_data.active {
+    return Err(response::InnexgoHoursError::AvailabilityArchived);
+  }
+
+  // only students of the course can book
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    availability.course_id,
+    CourseCapability::Attend,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, availability.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // must be the start of one of the slots, and not already over
+  if !availability_data_service::is_slot_start(&availability_data, props.start_time)
+    || props.start_time < utils::current_time_millis()
+  {
+    return Err(response::InnexgoHoursError::AvailabilitySlotNonexistent);
+  }
+
+  let taken =
+    availability_booking_service::get_taken_start_times(&mut sp, availability.availability_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+  if taken.contains(&props.start_time) {
+    return Err(response::InnexgoHoursError::AvailabilitySlotTaken);
+  }
+
+  let course = course_service::get_by_course_id(&mut sp, availability.course_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+  check_school_open(
+    &mut sp,
+    course.school_id,
+    props.start_time,
+    props.start_time + availability_data.slot_duration,
+  )
+  .await?;
+
+  // nobody is around to settle a conflict, so the student, the instructors and the room all have to be free
+  let conflicts = get_session_conflicts(
+    &mut sp,
+    availability.course_id,
+    None,
+    &[user.user_id],
+    props.start_time,
+    props.start_time + availability_data.slot_duration,
+  )
+  .await?;
+  check_conflicts(&Some(request::ConflictMode::Strict), &conflicts)?;
+
+  // make the session the slot stands for, run by whoever published the availability,
+  // and commit the student to it
+  let session = session_service::add(
+    &mut sp,
+    availability.creator_user_id,
+    availability.course_id,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  session_data_service::add(
+    &mut sp,
+    availability.creator_user_id,
+    session.session_id,
+    availability_data.name,
+    props.start_time,
+    props.start_time + availability_data.slot_duration,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let commitment = commitment_service::add(
+    &mut sp,
+    user.user_id,
+    user.user_id,
+    session.session_id,
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let availability_booking = availability_booking_service::add(
+    &mut sp,
+    user.user_id,
+    availability.availability_id,
+    props.start_time,
+    commitment.commitment_id,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  publish(&config, events::Event::Commitment(commitment));
+
+  // return json
+  fill_availability_booking(con, availability_booking).await
+}
+
+pub async fn commitment_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CommitmentNewProps,
+) -> Result<response::Scheduled<Vec<response::Commitment>>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate session exists
+  let session = session_service::get_by_session_id(&mut sp, props.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  // ensure commitment creator may run sessions in the session's course
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    session.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // check that course isn't archived
+  if !course_data_service::is_active_by_course_id(&mut sp, session.course_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::CourseArchived);
+  }
+
+  // withdrawing from a session, or committing to a cancelled one, can't double book anyone
+  let session_data = session_data_service::get_by_session_id(&mut sp, session.session_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+
+  let conflicts = if props.active && session_data.active {
+    conflict_service::get_by_attendee_user_ids(
+      &mut sp,
+      &props.attendee_user_ids,
+      Some(session.session_id),
+      session_data.start_time,
+      session_data.end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?
+  } else {
+    vec![]
+  };
+  check_conflicts(&props.conflict_mode, &conflicts)?;
+
+  let mut commitments_ret = vec![];
+  let mut new_events = vec![];
+
+  // create session from provided users automatically
+  for attendee_user_id in props.attendee_user_ids {
+    // ensure attendee is the student of the session's course
+    if !course_membership_service::has_capability(
+      &mut sp,
+      attendee_user_id,
+      session.course_id,
+      CourseCapability::Attend,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+      return Err(response::InnexgoHoursError::UserNonexistent);
+    }
+
+    // create a new committment
+    let commitment = commitment_service::add(
+      &mut sp,
+      user.user_id,
+      attendee_user_id,
+      session.session_id,
+      props.active,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    new_events.push(events::Event::Commitment(commitment.clone()));
+
+    // push json ready version
+    commitments_ret.push(fill_commitment(&mut sp, commitment).await?);
+  }
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  for event in new_events {
+    publish(&config, event);
+  }
+
+  Ok(response::Scheduled {
+    data: commitments_ret,
+    conflicts: conflicts.into_iter().map(fill_conflict).collect(),
+  })
+}
+
+pub async fn encounter_new(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::EncounterNewProps,
+) -> Result<response::Encounter, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // attendance is taken in the student's homeroom unless we're told otherwise
+  let location_id = match props.location_id {
+    Some(location_id) => location_id,
+    None => get_homeroom_location_id(&mut sp, props.attendee_user_id).await?,
+  };
+
+  // validate location exists
+  let _ = location_service::get_by_location_id(&mut sp, location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // check that location is not archived
+  if !location_data_service::is_active_by_location_id(&mut sp, location_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::LocationArchived);
+  }
+
+  // can only add encounter if you may take attendance at the claimed location
+  if !course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let encounter = encounter_service::add(
+    &mut sp,
+    user.user_id,
+    location_id,
+    props.attendee_user_id,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  publish(&config, events::Event::Encounter(encounter.clone()));
+
+  // return json
+  fill_encounter(con, encounter).await
+}
+
+// self check ins count against this budget whether or not the code was right
+static CHECKIN_MAX_ATTEMPTS: i64 = 5;
+static CHECKIN_ATTEMPT_WINDOW: i64 = 5 * 60 * 1000;
+
+// lets a student sign themselves in by typing the code displayed at the location
+pub async fn encounter_new_checkin(
+  config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::EncounterNewCheckinProps,
+) -> Result<response::Encounter, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // students check in to their homeroom unless they say otherwise
+  let location_id = match props.location_id {
+    Some(location_id) => location_id,
+    None => get_homeroom_location_id(&mut sp, user.user_id).await?,
+  };
+
+  // validate location exists
+  let _ = location_service::get_by_location_id(&mut sp, location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // check that location is not archived
+  if !location_data_service::is_active_by_location_id(&mut sp, location_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::LocationArchived);
+  }
+
+  // only students enrolled in a course at this location may check in here
+  if !course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    location_id,
+    CourseCapability::Attend,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  let location_checkin = location_checkin_service::get_active_by_location_id(&mut sp, location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationCheckinNonexistent)?;
+
+  let time = utils::current_time_millis();
+
+  if checkin_attempt_service::count_by_creator_user_id_since(
+    &mut sp,
+    user.user_id,
+    time - CHECKIN_ATTEMPT_WINDOW,
+  )
+  .await
+  .map_err(report_postgres_err)?
+    >= CHECKIN_MAX_ATTEMPTS
+  {
+    return Err(response::InnexgoHoursError::CheckinRateLimited);
+  }
+
+  let code_period = utils::verify_checkin_code(&location_checkin.secret, &props.code, time);
+
+  // a code may only be used once, or a student could keep signing in for the whole time it's valid
+  let code_used = match code_period {
+    Some(code_period) => {
+      checkin_attempt_service::is_code_period_used(&mut sp, user.user_id, location_id, code_period)
+        .await
+        .map_err(report_postgres_err)?
+    }
+    None => false,
+  };
+
+  let _ = checkin_attempt_service::add(
+    &mut sp,
+    user.user_id,
+    location_id,
+    code_period.filter(|_| !code_used),
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // failed attempts must still be recorded, or the rate limit would be meaningless
+  if code_used {
+    sp.commit().await.map_err(report_postgres_err)?;
+    return Err(response::InnexgoHoursError::CheckinCodeUsed);
+  }
+
+  if code_period.is_none() {
+    sp.commit().await.map_err(report_postgres_err)?;
+    return Err(response::InnexgoHoursError::CheckinCodeInvalid);
+  }
+
+  let encounter = encounter_service::add(
+    &mut sp,
+    user.user_id,
+    location_id,
+    user.user_id,
+    request::EncounterKind::SelfCheckin,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  publish(&config, events::Event::Encounter(encounter.clone()));
+
+  // return json
+  fill_encounter(con, encounter).await
+}
+
+// kiosks may only backfill scans from within this window
+static ENCOUNTER_MAX_BACKFILL: i64 = 7 * 24 * 60 * 60 * 1000;
+// tolerated drift of kiosk clocks ahead of ours
+static ENCOUNTER_MAX_CLOCK_SKEW: i64 = 5 * 60 * 1000;
+static ENCOUNTER_MAX_BATCH_SIZE: usize = 1000;
+
+// why a single scan from a kiosk batch can't be ingested, if it can't
+async fn check_encounter_batch_item(
+  con: &mut impl GenericClient,
+  user_id: i64,
+  item: &request::EncounterNewBatchItem,
+  now: i64,
+) -> Result<(), response::InnexgoHoursError> {
+  if item.scan_time < now - ENCOUNTER_MAX_BACKFILL
+    || item.scan_time > now + ENCOUNTER_MAX_CLOCK_SKEW
+  {
+    return Err(response::InnexgoHoursError::EncounterScanTimeInvalid);
+  }
+
+  // validate location exists
+  let _ = location_service::get_by_location_id(con, item.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // check that location is not archived
+  if !location_data_service::is_active_by_location_id(con, item.location_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::LocationArchived);
+  }
+
+  // kiosk must be signed in as someone who may take attendance at the location
+  if !course_membership_service::has_capability_at(
+    con,
+    user_id,
+    item.location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  Ok(())
+}
+
+// ingests scans buffered by an offline kiosk
+// every scan gets its own result, so one bad scan doesn't hold back the rest of the batch
+// replays of an already ingested idempotency key return the original encounter instead of a duplicate
+pub async fn encounter_new_batch(
+  config: Config,
+  db: Db,
   auth_service: AuthService,
-  props: request::SubscriptionNewProps,
-) -> Result<response::Subscription, response::InnexgoHoursError> {
+  props: request::EncounterNewBatchProps,
+) -> Result<Vec<response::EncounterNewBatchResult>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  if props.encounters.len() > ENCOUNTER_MAX_BATCH_SIZE {
+    return Err(response::InnexgoHoursError::EncounterBatchTooLarge);
+  }
+
+  let now = utils::current_time_millis();
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // (idempotency key, outcome, encounter if there is one)
+  let mut results = vec![];
+  let mut new_events = vec![];
+
+  for item in props.encounters {
+    // if we've seen this one before, just hand it back
+    if let Some(encounter) = encounter_service::get_by_creator_user_id_idempotency_key(
+      &mut sp,
+      user.user_id,
+      &item.idempotency_key,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+      results.push((
+        item.idempotency_key,
+        Ok(response::EncounterNewBatchResultKind::Duplicate),
+        Some(encounter),
+      ));
+      continue;
+    }
+
+    match check_encounter_batch_item(&mut sp, user.user_id, &item, now).await {
+      Ok(()) => (),
+      // the database failing isn't the scan's fault
+      Err(response::InnexgoHoursError::InternalServerError) => {
+        return Err(response::InnexgoHoursError::InternalServerError)
+      }
+      Err(e) => {
+        results.push((item.idempotency_key, Err(e), None));
+        continue;
+      }
+    }
+
+    let encounter = encounter_service::add(
+      &mut sp,
+      user.user_id,
+      item.location_id,
+      item.attendee_user_id,
+      request::EncounterKind::Hardware,
+      Some(item.scan_time),
+      Some(item.idempotency_key.clone()),
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    new_events.push(events::Event::Encounter(encounter.clone()));
+    results.push((
+      item.idempotency_key,
+      Ok(response::EncounterNewBatchResultKind::Accepted),
+      Some(encounter),
+    ));
+  }
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  for event in new_events {
+    publish(&config, event);
+  }
+
+  // return json
+  let mut resp_results = vec![];
+  for (idempotency_key, outcome, encounter) in results.into_iter() {
+    let encounter = match encounter {
+      Some(x) => Some(fill_encounter(con, x).await?),
+      None => None,
+    };
+
+    resp_results.push(match outcome {
+      Ok(kind) => response::EncounterNewBatchResult {
+        idempotency_key,
+        encounter_new_batch_result_kind: kind,
+        encounter,
+        error: None,
+      },
+      Err(e) => response::EncounterNewBatchResult {
+        idempotency_key,
+        encounter_new_batch_result_kind: response::EncounterNewBatchResultKind::Rejected,
+        encounter,
+        error: Some(e),
+      },
+    });
+  }
+
+  Ok(resp_results)
+}
+
+pub async fn stay_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::StayNewProps,
+) -> Result<response::StayData, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate attendee exists
+  let attendee = auth_service
+    .get_user_by_id(props.attendee_user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  // validate location exists
+  let location = location_service::get_by_location_id(&mut sp, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // validate the encounters if they exist, and get the min and max times
+  let fst = match (props.fst_encounter_id, props.fst_time) {
+    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
+    (None, Some(time)) => Right(time),
+    (Some(encounter_id), None) => {
+      // validate location exists
+      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      // validate encounter  has the required specs
+      if encounter.location_id != props.location_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
+      }
+      if encounter.attendee_user_id != props.attendee_user_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
+      }
+      Left(encounter)
+    }
+    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
+  };
+  let snd = match (props.snd_encounter_id, props.snd_time) {
+    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
+    (None, Some(time)) => Right(time),
+    (Some(encounter_id), None) => {
+      // validate location exists
+      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      // validate encounter  has the required specs
+      if encounter.location_id != props.location_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
+      }
+      if encounter.attendee_user_id != props.attendee_user_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
+      }
+      Left(encounter)
+    }
+    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
+  };
+
+  // validate fst time is before snd time
+  let fst_time = fst.clone().map_left(|x| x.creation_time).into_inner();
+  let snd_time = snd.clone().map_left(|x| x.creation_time).into_inner();
+  if fst_time > snd_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  // ensure stay creator may take attendance at the location
+  if !course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    props.location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // create stay
+  let stay = stay_service::add(
+    &mut sp,
+    user.user_id,
+    props.attendee_user_id,
+    props.location_id,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // create stay data
+  let stay_data = stay_data_service::add(
+    &mut sp,
+    user.user_id,
+    stay.stay_id,
+    fst.map_left(|x| x.encounter_id),
+    snd.map_left(|x| x.encounter_id),
+    true,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  sp.commit().await.map_err(report_postgres_err)?;
+
+  // return json
+  fill_stay_data(con, stay_data).await
+}
+
+pub async fn stay_data_new(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::StayDataNewProps,
+) -> Result<response::StayData, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
+  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+  // validate stay exists
+  let stay = stay_service::get_by_stay_id(&mut sp, props.stay_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+
+  // ensure stay creator may take attendance at the location of the original stay
+  if !course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    stay.location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // validate the encounters if they exist, and get the min and max times
+  let fst = match (props.fst_encounter_id, props.fst_time) {
+    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
+    (None, Some(time)) => Right(time),
+    (Some(encounter_id), None) => {
+      // validate location exists
+      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      // validate encounter  has the required specs
+      if encounter.location_id != stay.location_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
+      }
+      if encounter.attendee_user_id != stay.attendee_user_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
+      }
+      Left(encounter)
+    }
+    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
+  };
+  let snd = match (props.snd_encounter_id, props.snd_time) {
+    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
+    (None, Some(time)) => Right(time),
+    (Some(encounter_id), None) => {
+      // validate location exists
+      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
+      // validate encounter  has the required specs
+      if encounter.location_id != stay.location_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
+      }
+      if encounter.attendee_user_id != stay.attendee_user_id {
+        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
+      }
+      Left(encounter)
+    }
+    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
+  };
+
+  // validate fst time is before snd time
+  let fst_time = fst.clone().map_left(|x| x.creation_time).into_inner();
+  let snd_time = snd.clone().map_left(|x| x.creation_time).into_inner();
+  if fst_time > snd_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  // create stay data
+  let stay_data = stay_data_service::add(
+    &mut sp,
+    user.user_id,
+    stay.stay_id,
+    fst.map_left(|x| x.encounter_id),
+    snd.map_left(|x| x.encounter_id),
+    props.active,
+  )
+  .await
+  .map_err(report_postgres_err)?;
 
-  // create event
-  let subscription = subscription_service::add(con, user.user_id, props.subscription_kind, 1, 0)
-    .await
-    .map_err(report_postgres_err)?;
+  sp.commit().await.map_err(report_postgres_err)?;
 
   // return json
-  fill_subscription(con, subscription).await
+  fill_stay_data(con, stay_data).await
 }
 
-pub async fn location_new(
-  _config: Config,
+// marks every listed attendee of a session present, tardy or absent at once
+// submitting the sheet again updates what it recorded before instead of adding to it
+pub async fn attendance_sheet_new(
+  config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::LocationNewProps,
-) -> Result<response::LocationData, response::InnexgoHoursError> {
+  props: request::AttendanceSheetNewProps,
+) -> Result<Vec<response::AttendanceSheetEntry>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
   let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // validate school exists and that key creator is admin
-  let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+  let session = session_service::get_by_session_id(&mut sp, props.session_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
 
-  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
-    .await
-    .map_err(report_postgres_err)?
+  // only staff who may take attendance in the course may fill out the sheet
+  if !course_membership_service::has_capability(
+    &mut sp,
+    user.user_id,
+    session.course_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?
   {
     return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+  let session_data = session_data_service::get_by_session_id(&mut sp, session.session_id)
     .await
     .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
+    .filter(|x| x.active)
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
 
-  // create location
-  let location = location_service::add(&mut sp, user.user_id, props.school_id)
+  // attendance is taken at the course's location
+  let course_data = course_data_service::get_by_course_id(&mut sp, session.course_id)
     .await
-    .map_err(report_postgres_err)?;
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
 
-  // create location data
-  let location_data = location_data_service::add(
-    &mut sp,
-    user.user_id,
-    location.location_id,
-    props.name,
-    props.address,
-    props.phone,
-    true,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  let mut new_events = vec![];
+
+  for entry in props.entries {
+    // the attendee must have actually committed to this session
+    let commitment = commitment_service::get_by_attendee_user_id_session_id(
+      &mut sp,
+      entry.attendee_user_id,
+      session.session_id,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    .filter(|x| x.active)
+    .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
+
+    let arrival_time = match entry.attendance_kind {
+      request::AttendanceKind::Present => Some(entry.time.unwrap_or(session_data.start_time)),
+      request::AttendanceKind::Tardy => Some(
+        entry
+          .time
+          .ok_or(response::InnexgoHoursError::AttendanceSheetTimeInvalid)?,
+      ),
+      request::AttendanceKind::Absent => None,
+    };
+
+    if let Some(arrival_time) = arrival_time {
+      if arrival_time < session_data.start_time || arrival_time > session_data.end_time {
+        return Err(response::InnexgoHoursError::AttendanceSheetTimeInvalid);
+      }
+    }
+
+    // what an earlier submission of the sheet recorded for the attendee, if anything
+    let sheet_stay_data = match stay_service::get_by_attendee_user_id_session_id(
+      &mut sp,
+      entry.attendee_user_id,
+      session.session_id,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+      Some(stay) => stay_data_service::get_by_stay_id(&mut sp, stay.stay_id)
+        .await
+        .map_err(report_postgres_err)?,
+      None => None,
+    };
+
+    match (arrival_time, sheet_stay_data) {
+      (Some(arrival_time), None) => {
+        let encounter = encounter_service::add(
+          &mut sp,
+          user.user_id,
+          course_data.location_id,
+          entry.attendee_user_id,
+          request::EncounterKind::Manual,
+          Some(arrival_time),
+          None,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+
+        // they're assumed to stay until the session ends
+        let stay = stay_service::add(
+          &mut sp,
+          user.user_id,
+          entry.attendee_user_id,
+          course_data.location_id,
+          Some(session.session_id),
+        )
+        .await
+        .map_err(report_postgres_err)?;
+
+        let _ = stay_data_service::add(
+          &mut sp,
+          user.user_id,
+          stay.stay_id,
+          Left(encounter.encounter_id),
+          Right(session_data.end_time),
+          true,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+
+        new_events.push(events::Event::Encounter(encounter));
+      }
+      (Some(arrival_time), Some(stay_data)) => {
+        let recorded_arrival_time = match stay_data.fst {
+          Left(encounter_id) => {
+            encounter_service::get_by_encounter_id(&mut sp, encounter_id)
+              .await
+              .map_err(report_postgres_err)?
+              .ok_or(response::InnexgoHoursError::EncounterNonexistent)?
+              .creation_time
+          }
+          Right(time) => time,
+        };
+
+        // a corrected arrival is an edit of the stay, rather than another sign in
+        let fst = if recorded_arrival_time == arrival_time {
+          stay_data.fst
+        } else {
+          Right(arrival_time)
+        };
+        let snd = Right(session_data.end_time);
+
+        // submitting the same thing again changes nothing
+        if !stay_data.active || stay_data.fst != fst || stay_data.snd != snd {
+          let _ = stay_data_service::add(&mut sp, user.user_id, stay_data.stay_id, fst, snd, true)
+            .await
+            .map_err(report_postgres_err)?;
+        }
+      }
+      // they weren't there after all
+      (None, Some(stay_data)) if stay_data.active => {
+        let _ = stay_data_service::add(
+          &mut sp,
+          user.user_id,
+          stay_data.stay_id,
+          stay_data.fst,
+          stay_data.snd,
+          false,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+      }
+      (None, _) => {}
+    }
+
+    let current_irregularity =
+      irregularity_service::get_by_commitment_id(&mut sp, commitment.commitment_id)
+        .await
+        .map_err(report_postgres_err)?
+        .filter(|x| x.active);
+
+    match (entry.attendance_kind, current_irregularity) {
+      // showing up on time clears anything recorded earlier
+      (request::AttendanceKind::Present, Some(irregularity)) => {
+        let _ = irregularity_service::add(
+          &mut sp,
+          user.user_id,
+          commitment.commitment_id,
+          irregularity.irregularity_kind,
+          None,
+          None,
+          false,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+      }
+      (request::AttendanceKind::Present, None) => {}
+      // already recorded by an earlier submission
+      (request::AttendanceKind::Tardy, Some(irregularity))
+        if matches!(
+          irregularity.irregularity_kind,
+          request::IrregularityKind::Tardy
+        ) && irregularity.start_time == arrival_time => {}
+      (request::AttendanceKind::Absent, Some(irregularity))
+        if matches!(
+          irregularity.irregularity_kind,
+          request::IrregularityKind::Absent
+        ) => {}
+      (request::AttendanceKind::Tardy, _) => {
+        let _ = irregularity_service::add(
+          &mut sp,
+          user.user_id,
+          commitment.commitment_id,
+          request::IrregularityKind::Tardy,
+          arrival_time,
+          None,
+          true,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+      }
+      (request::AttendanceKind::Absent, _) => {
+        let _ = irregularity_service::add(
+          &mut sp,
+          user.user_id,
+          commitment.commitment_id,
+          request::IrregularityKind::Absent,
+          None,
+          None,
+          true,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+      }
+    }
+  }
 
   sp.commit().await.map_err(report_postgres_err)?;
 
-  // return json
-  fill_location_data(con, location_data).await
+  for event in new_events {
+    publish(&config, event);
+  }
+
+  // return the whole sheet as it now stands
+  fill_attendance_sheet(con, session.session_id).await
 }
 
-pub async fn location_data_new(
+pub async fn attendance_sheet_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::LocationDataNewProps,
-) -> Result<response::LocationData, response::InnexgoHoursError> {
+  props: request::AttendanceSheetViewProps,
+) -> Result<Vec<response::AttendanceSheetEntry>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // validate location
-  let location = location_service::get_by_location_id(&mut sp, props.location_id)
+  let session = session_service::get_by_session_id(con, props.session_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
 
-  // only let us modify if we are an an instructor at this location or an admin
+  // the whole sheet is only for staff who can view records
+  if !course_membership_service::has_capability(
+    con,
+    user.user_id,
+    session.course_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
 
-  let instructor_at =
-    course_membership_service::is_instructor_at(&mut sp, user.user_id, props.location_id)
-      .await
-      .map_err(report_postgres_err)?;
+  fill_attendance_sheet(con, session.session_id).await
+}
 
-  let admin_at = adminship_service::is_admin(&mut sp, user.user_id, location.school_id)
+// a daily report covers this long, starting whenever the caller's day starts
+static HOMEROOM_ATTENDANCE_DAY: i64 = 24 * 60 * 60 * 1000;
+
+// who turned up to their homeroom on a given day
+// administrators see the whole school, staff see the homerooms whose records they can view
+pub async fn homeroom_attendance_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::HomeroomAttendanceViewProps,
+) -> Result<Vec<response::HomeroomAttendanceEntry>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let _ = school_service::get_by_school_id(con, props.school_id)
     .await
-    .map_err(report_postgres_err)?;
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
 
-  if !(instructor_at || admin_at) {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
+  let is_admin = adminship_service::is_admin(con, user.user_id, props.school_id)
+    .await
+    .map_err(report_postgres_err)?;
 
-  // now we can update data
-  let location_data = location_data_service::add(
-    &mut sp,
-    user.user_id,
-    props.location_id,
-    props.name,
-    props.address,
-    props.phone,
-    props.active,
+  let attendance = homeroom_service::get_attendance(
+    con,
+    props.school_id,
+    &props.course_id,
+    props.day_start_time,
+    props.day_start_time + HOMEROOM_ATTENDANCE_DAY,
   )
   .await
   .map_err(report_postgres_err)?;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  // entries come sorted by course, so only ask once per course
+  let mut visible_course: Option<(i64, bool)> = None;
+
+  let mut entries = vec![];
+  for x in attendance.into_iter() {
+    let course_id = x.homeroom.course_id;
+    let can_view = match visible_course {
+      Some((visible_course_id, can_view)) if visible_course_id == course_id => can_view,
+      _ => {
+        let can_view = is_admin
+          || course_membership_service::has_capability(
+            con,
+            user.user_id,
+            course_id,
+            CourseCapability::ViewRecords,
+          )
+          .await
+          .map_err(report_postgres_err)?;
+        visible_course = Some((course_id, can_view));
+        can_view
+      }
+    };
+
+    if can_view {
+      entries.push(response::HomeroomAttendanceEntry {
+        course_id,
+        location_id: x.homeroom.location_id,
+        student_user_id: x.homeroom.student_user_id,
+        arrival_time: x.arrival_time,
+      });
+    }
+  }
 
-  // return json
-  fill_location_data(con, location_data).await
+  Ok(entries)
 }
 
-pub async fn course_new(
-  _config: Config,
+// moves a student from one location to another in one step
+pub async fn hall_pass_new(
+  config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseNewProps,
-) -> Result<response::CourseData, response::InnexgoHoursError> {
+  props: request::HallPassNewProps,
+) -> Result<response::HallPass, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
+  // validate attendee exists
+  let _ = auth_service
+    .get_user_by_id(props.attendee_user_id)
+    .await
+    .map_err(report_auth_err)?;
+
+  if props.origin_location_id == props.destination_location_id {
+    return Err(response::InnexgoHoursError::HallPassSameLocation);
+  }
+
+  let time = utils::current_time_millis();
+
+  if props.expected_return_time <= time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
   let con = &mut *db.lock().await;
   let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // validate school exists and that key creator is admin
-  let _ = school_service::get_by_school_id(&mut sp, props.school_id)
+  let origin = location_service::get_by_location_id(&mut sp, props.origin_location_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
 
-  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+  let destination = location_service::get_by_location_id(&mut sp, props.destination_location_id)
     .await
     .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  if origin.school_id != destination.school_id {
+    return Err(response::InnexgoHoursError::HallPassWrongSchool);
   }
 
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+  // check that the destination is not archived
+  if !location_data_service::is_active_by_location_id(&mut sp, destination.location_id)
     .await
     .map_err(report_postgres_err)?
   {
-    return Err(response::InnexgoHoursError::SchoolArchived);
+    return Err(response::InnexgoHoursError::LocationArchived);
   }
 
-  // check that location is valid
-  let _ = location_service::get_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+  // passes are written by staff who take attendance where the student currently is
+  if !course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    origin.location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
 
-  // check that location is not archived
-  if !location_data_service::is_active_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
+  let mut new_events = vec![];
+
+  // close out the origin
+  if let Some(sign_out) = close_presence_at(
+    &mut sp,
+    user.user_id,
+    props.attendee_user_id,
+    origin.location_id,
+    time,
+  )
+  .await?
   {
-    return Err(response::InnexgoHoursError::LocationArchived);
+    new_events.push(events::Event::Encounter(sign_out));
   }
 
-  // create course
-  let course = course_service::add(&mut sp, user.user_id, props.school_id)
-    .await
-    .map_err(report_postgres_err)?;
+  // open a stay at the destination that lasts until they're expected back
+  let sign_in = encounter_service::add(
+    &mut sp,
+    user.user_id,
+    destination.location_id,
+    props.attendee_user_id,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
 
-  // create course data
-  let course_data = course_data_service::add(
+  let destination_stay = stay_service::add(
     &mut sp,
     user.user_id,
-    course.course_id,
-    props.location_id,
-    props.name,
-    props.description,
-    props.homeroom, // TODO do we have to restrict how many classes can be a homeroom ?
+    props.attendee_user_id,
+    destination.location_id,
+    None,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let _ = stay_data_service::add(
+    &mut sp,
+    user.user_id,
+    destination_stay.stay_id,
+    Left(sign_in.encounter_id),
+    Right(props.expected_return_time),
     true,
   )
   .await
   .map_err(report_postgres_err)?;
 
-  // give the creator a course membership
-  let _ = course_membership_service::add(
+  new_events.push(events::Event::Encounter(sign_in));
+
+  let hall_pass = hall_pass_service::add(
     &mut sp,
     user.user_id,
-    user.user_id,
-    course.course_id,
-    request::CourseMembershipKind::Instructor,
-    None,
+    props.attendee_user_id,
+    origin.location_id,
+    destination.location_id,
+    destination_stay.stay_id,
+    props.hall_pass_reason_kind,
+    props.expected_return_time,
   )
   .await
   .map_err(report_postgres_err)?;
 
   sp.commit().await.map_err(report_postgres_err)?;
 
+  for event in new_events {
+    publish(&config, event);
+  }
+
   // return json
-  fill_course_data(con, course_data).await
+  fill_hall_pass(con, hall_pass).await
 }
 
-pub async fn course_data_new(
-  _config: Config,
+// brings a student on a hall pass back to where they came from
+pub async fn hall_pass_new_return(
+  config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseDataNewProps,
-) -> Result<response::CourseData, response::InnexgoHoursError> {
+  props: request::HallPassNewReturnProps,
+) -> Result<response::HallPass, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
   let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  let course = course_service::get_by_course_id(&mut sp, props.course_id)
+  let hall_pass = hall_pass_service::get_by_hall_pass_id(&mut sp, props.hall_pass_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+    .ok_or(response::InnexgoHoursError::HallPassNonexistent)?;
 
-  if !course_membership_service::is_instructor(&mut sp, user.user_id, props.course_id)
+  if hall_pass_service::get_return_time(&mut sp, &hall_pass)
     .await
     .map_err(report_postgres_err)?
+    .is_some()
   {
+    return Err(response::InnexgoHoursError::HallPassReturned);
+  }
+
+  // staff at either end may check the student back in
+  let can_take_attendance_at_origin = course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    hall_pass.origin_location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let can_take_attendance_at_destination = course_membership_service::has_capability_at(
+    &mut sp,
+    user.user_id,
+    hall_pass.destination_location_id,
+    CourseCapability::TakeAttendance,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  if !can_take_attendance_at_origin && !can_take_attendance_at_destination {
     return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  // check that location is valid
-  let _ = location_service::get_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+  let time = utils::current_time_millis();
 
-  // check that location is not archived
-  if !location_data_service::is_active_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
+  let mut new_events = vec![];
+
+  // close out the destination, which may already have ended on its own if they're late
+  if let Some(sign_out) = close_presence_at(
+    &mut sp,
+    user.user_id,
+    hall_pass.attendee_user_id,
+    hall_pass.destination_location_id,
+    time,
+  )
+  .await?
   {
-    return Err(response::InnexgoHoursError::LocationArchived);
+    new_events.push(events::Event::Encounter(sign_out));
   }
 
-  // now we can update data
-  let course_data = course_data_service::add(
+  // being seen at the origin again is what marks the pass returned
+  let sign_in = encounter_service::add(
     &mut sp,
     user.user_id,
-    course.course_id,
-    props.location_id,
-    props.name,
-    props.description,
-    props.homeroom, // TODO do we have to restrict how many classes can be a homeroom
-    props.active,
+    hall_pass.origin_location_id,
+    hall_pass.attendee_user_id,
+    request::EncounterKind::Manual,
+    None,
+    None,
   )
   .await
   .map_err(report_postgres_err)?;
 
+  new_events.push(events::Event::Encounter(sign_in));
+
   sp.commit().await.map_err(report_postgres_err)?;
 
+  for event in new_events {
+    publish(&config, event);
+  }
+
   // return json
-  fill_course_data(con, course_data).await
+  fill_hall_pass(con, hall_pass).await
 }
 
-pub async fn course_key_new(
+pub async fn subscription_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseKeyNewProps,
-) -> Result<response::CourseKeyData, response::InnexgoHoursError> {
-  if props.start_time > props.end_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
-  }
-
+  props: request::SubscriptionViewProps,
+) -> Result<Vec<response::Subscription>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  let _ = course_service::get_by_course_id(&mut sp, props.course_id)
+  // get users
+  let subscriptions = subscription_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
-    .await
-    .map_err(report_postgres_err)?
+  // return subscriptions
+  let mut resp_subscriptions = vec![];
+  for u in subscriptions
+    // you can view your own subscriptions
+    .into_iter()
+    .filter(|u| u.creator_user_id == user.user_id)
   {
-    return Err(response::InnexgoHoursError::CourseArchived);
+    resp_subscriptions.push(fill_subscription(con, u).await?);
   }
 
-  // get instructor or admin
-  if !course_membership_service::is_instructor(&mut sp, user.user_id, props.course_id)
+  Ok(resp_subscriptions)
+}
+
+pub async fn school_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolViewProps,
+) -> Result<Vec<response::School>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get users
+  let schools = school_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+    .map_err(report_postgres_err)?;
+
+  // return schools
+  let mut resp_schools = vec![];
+  for u in schools.into_iter() {
+    // you can view all schools
+    resp_schools.push(fill_school(con, u).await?);
   }
 
-  // now create key
-  let course_key = course_key_service::add(
-    &mut sp,
-    &utils::gen_random_string(),
-    user.user_id,
-    props.course_id,
-    props.max_uses,
-    props.course_membership_kind,
-    props.start_time,
-    props.end_time,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  Ok(resp_schools)
+}
 
-  // create key data
-  let course_key_data =
-    course_key_data_service::add(&mut sp, user.user_id, course_key.course_key_key, true)
-      .await
-      .map_err(report_postgres_err)?;
+pub async fn school_data_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDataViewProps,
+) -> Result<Vec<response::SchoolData>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
+  let con = &mut *db.lock().await;
+  // get users
+  let school_data = school_data_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
+  // return users
+  // return school_datas
+  let mut resp_school_datas = vec![];
+  for u in school_data.into_iter() {
+    // you can view all schools
+    resp_school_datas.push(fill_school_data(con, u).await?);
+  }
 
-  // return json
-  fill_course_key_data(con, course_key_data).await
+  Ok(resp_school_datas)
 }
 
-pub async fn course_key_data_new(
+pub async fn school_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseKeyDataNewProps,
-) -> Result<response::CourseKeyData, response::InnexgoHoursError> {
+  props: request::SchoolDataHistoryProps,
+) -> Result<Vec<response::Version<response::SchoolData>>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // get course key
-  let course_key = course_key_service::get_by_course_key_key(&mut sp, &props.course_key_key)
+  // you can view all schools
+  let school_data = school_data_service::get_history_by_school_id(con, props.school_id)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // get corresponding course
-  let course = course_service::get_by_course_id(&mut sp, course_key.course_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+  let mut resp_school_datas = vec![];
+  for x in school_data.into_iter() {
+    resp_school_datas.push(fill_school_data(con, x).await?);
+  }
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, course_key.course_id)
+  fill_history(resp_school_datas)
+}
+
+pub async fn school_duration_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDurationViewProps,
+) -> Result<Vec<response::SchoolDuration>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get users
+  let school_durations = school_duration_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
+    .map_err(report_postgres_err)?;
+
+  // return school_durations
+  let mut resp_school_durations = vec![];
+  for u in school_durations.into_iter() {
+    // you can view all school_durations
+    resp_school_durations.push(fill_school_duration(con, u).await?);
   }
 
-  // is valid instructor
-  if !course_membership_service::is_instructor(&mut sp, user.user_id, course.course_id)
+  Ok(resp_school_durations)
+}
+
+pub async fn school_duration_data_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDurationDataViewProps,
+) -> Result<Vec<response::SchoolDurationData>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get users
+  let school_duration_data = school_duration_data_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+    .map_err(report_postgres_err)?;
+  // return users
+  // return school_duration_datas
+  let mut resp_school_duration_datas = vec![];
+  for u in school_duration_data.into_iter() {
+    // you can view all school_durations
+    resp_school_duration_datas.push(fill_school_duration_data(con, u).await?);
   }
 
-  // create key data
-  let course_key_data = course_key_data_service::add(
-    &mut sp,
-    user.user_id,
-    course_key.course_key_key,
-    props.active,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  Ok(resp_school_duration_datas)
+}
 
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
+pub async fn school_duration_data_history(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolDurationDataHistoryProps,
+) -> Result<Vec<response::Version<response::SchoolDurationData>>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
-  // return json
-  fill_course_key_data(con, course_key_data).await
+  let con = &mut *db.lock().await;
+
+  // you can view all school_durations
+  let school_duration_data =
+    school_duration_data_service::get_history_by_school_duration_id(con, props.school_duration_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+  let mut resp_school_duration_datas = vec![];
+  for x in school_duration_data.into_iter() {
+    resp_school_duration_datas.push(fill_school_duration_data(con, x).await?);
+  }
+
+  fill_history(resp_school_duration_datas)
 }
 
-pub async fn course_membership_new_key(
+pub async fn term_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseMembershipNewKeyProps,
-) -> Result<response::CourseMembership, response::InnexgoHoursError> {
-  // validate api membership
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  props: request::TermViewProps,
+) -> Result<Vec<response::Term>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // get course key
-  let course_key = course_key_service::get_by_course_key_key(&mut sp, &props.course_key_key)
+  // get terms
+  let terms = term_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // time is within range
-  let now = utils::current_time_millis();
-  if now < course_key.start_time || now > course_key.end_time {
-    return Err(response::InnexgoHoursError::CourseKeyExpired);
+  // return terms
+  let mut resp_terms = vec![];
+  for u in terms.into_iter() {
+    // you can view all terms
+    resp_terms.push(fill_term(con, u).await?);
   }
 
-  // it fits under max uses
-  let uses = course_membership_service::count_course_key_uses(&mut sp, &course_key.course_key_key)
-    .await
-    .map_err(report_postgres_err)?;
+  Ok(resp_terms)
+}
 
-  if uses >= course_key.max_uses {
-    return Err(response::InnexgoHoursError::CourseKeyUsed);
-  }
+pub async fn term_data_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::TermDataViewProps,
+) -> Result<Vec<response::TermData>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, course_key.course_id)
+  let con = &mut *db.lock().await;
+  // get term data
+  let term_data = term_data_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
-  }
-
-  // now create membership
-  let course_membership = course_membership_service::add(
-    &mut sp,
-    user.user_id,
-    user.user_id,
-    course_key.course_id,
-    course_key.course_membership_kind,
-    Some(course_key.course_key_key),
-  )
-  .await
-  .map_err(report_postgres_err)?;
+    .map_err(report_postgres_err)?;
 
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
+  // return term_datas
+  let mut resp_term_datas = vec![];
+  for u in term_data.into_iter() {
+    // you can view all term data
+    resp_term_datas.push(fill_term_data(con, u).await?);
+  }
 
-  // return json
-  fill_course_membership(con, course_membership).await
+  Ok(resp_term_datas)
 }
 
-pub async fn course_membership_new_cancel(
+pub async fn term_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseMembershipNewCancelProps,
-) -> Result<response::CourseMembership, response::InnexgoHoursError> {
-  // validate api membership
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
-
-  // validate target exists
-  let target = auth_service
-    .get_user_by_id(props.user_id)
-    .await
-    .map_err(report_auth_err)?;
+  props: request::TermDataHistoryProps,
+) -> Result<Vec<response::Version<response::TermData>>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // get corresponding course
-  let course = course_service::get_by_course_id(&mut sp, props.course_id)
+  // you can view all terms
+  let term_data = term_data_service::get_history_by_term_id(con, props.term_id)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
+  let mut resp_term_datas = vec![];
+  for x in term_data.into_iter() {
+    resp_term_datas.push(fill_term_data(con, x).await?);
   }
 
-  let is_instructor =
-    course_membership_service::is_instructor(&mut sp, user.user_id, course.course_id)
-      .await
-      .map_err(report_postgres_err)?;
+  fill_history(resp_term_datas)
+}
 
-  let is_self_cancel = user.user_id == props.user_id;
+pub async fn closure_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::ClosureViewProps,
+) -> Result<Vec<response::Closure>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // if user_id == creator_user_id then authorize
-  // otherwise creator_user_id must be an admin or instructor
-  if !(is_self_cancel || is_instructor) {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  let con = &mut *db.lock().await;
+  // get closures
+  let closures = closure_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // return closures
+  let mut resp_closures = vec![];
+  for u in closures.into_iter() {
+    // you can view all closures
+    resp_closures.push(fill_closure(con, u).await?);
   }
 
-  // make sure we cannot leave the course without an instructor
-  if is_instructor {
-    let num_instructors = course_membership_service::count_instructors(&mut sp, course.course_id)
-      .await
-      .map_err(report_postgres_err)?;
+  Ok(resp_closures)
+}
 
-    if num_instructors <= 1 {
-      return Err(response::InnexgoHoursError::CourseMembershipCannotLeaveEmpty);
-    }
-  }
+pub async fn closure_data_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::ClosureDataViewProps,
+) -> Result<Vec<response::ClosureData>, response::InnexgoHoursError> {
+  // validate api key
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // now create membership
-  let course_membership = course_membership_service::add(
-    &mut sp,
-    user.user_id,
-    props.user_id,
-    course.course_id,
-    request::CourseMembershipKind::Cancel,
-    None,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  let con = &mut *db.lock().await;
+  // get closure data
+  let closure_data = closure_data_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
+  // return closure_datas
+  let mut resp_closure_datas = vec![];
+  for u in closure_data.into_iter() {
+    // you can view all closure data
+    resp_closure_datas.push(fill_closure_data(con, u).await?);
+  }
 
-  // return json
-  fill_course_membership(con, course_membership).await
+  Ok(resp_closure_datas)
 }
 
-pub async fn school_new(
+pub async fn closure_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolNewProps,
-) -> Result<response::SchoolData, response::InnexgoHoursError> {
+  props: request::ClosureDataHistoryProps,
+) -> Result<Vec<response::Version<response::ClosureData>>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
 
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // get subscription
-  let subscription = subscription_service::get_by_user_id(&mut sp, user.user_id)
+  // you can view all closures
+  let closure_data = closure_data_service::get_history_by_closure_id(con, props.closure_id)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SubscriptionNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // check if subscription is authorized
-  if adminship_service::count_valid_adminships_by_user_id(&mut sp, user.user_id)
-    .await
-    .map_err(report_postgres_err)?
-    >= subscription.max_uses
-  {
-    return Err(response::InnexgoHoursError::SubscriptionLimited);
+  let mut resp_closure_datas = vec![];
+  for x in closure_data.into_iter() {
+    resp_closure_datas.push(fill_closure_data(con, x).await?);
   }
 
-  // create school
-  let school = school_service::add(&mut sp, user.user_id, props.whole)
+  fill_history(resp_closure_datas)
+}
+
+pub async fn course_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseViewProps,
+) -> Result<Vec<response::Course>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get users
+  let courses = course_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // create data
-  let school_data = school_data_service::add(
-    &mut sp,
-    user.user_id,
-    school.school_id,
-    props.name,
-    props.description,
-    true,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  // return courses
+  let mut resp_courses = vec![];
 
-  // create adminship
-  let adminship = adminship_service::add(
-    &mut sp,
-    user.user_id,
-    user.user_id,
-    school.school_id,
-    request::AdminshipKind::Admin,
-    None,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  for x in courses.into_iter() {
+    // students and instructors can see the courses they are (or were) a member of
+    // administrators can see those plus the courses that they own
 
-  sp.commit().await.map_err(report_postgres_err)?;
+    let is_member =
+      course_membership_service::get_by_user_id_course_id(con, user.user_id, x.course_id)
+        .await
+        .map_err(report_postgres_err)?
+        .is_some();
 
-  // return json
-  fill_school_data(con, school_data).await
+    let is_admin = adminship_service::get_by_user_id_school_id(con, user.user_id, x.school_id)
+      .await
+      .map_err(report_postgres_err)?
+      .is_some();
+
+    if is_member || is_admin {
+      resp_courses.push(fill_course(con, x).await?);
+    }
+  }
+
+  Ok(resp_courses)
 }
 
-pub async fn school_data_new(
+pub async fn course_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolDataNewProps,
-) -> Result<response::SchoolData, response::InnexgoHoursError> {
+  props: request::CourseDataViewProps,
+) -> Result<Vec<response::CourseData>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
+  // get users
+  let course_data = course_data_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+  // return course_datas
+  let mut resp_course_datas = vec![];
+  for x in course_data.into_iter() {
+    // students and instructors can see the courses they are (or were) a member of
+    // administrators can see those plus the courses that they own
 
-  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
+    let is_member =
+      course_membership_service::get_by_user_id_course_id(con, user.user_id, x.course_id)
+        .await
+        .map_err(report_postgres_err)?
+        .is_some();
 
-  // create data
-  let school_data = school_data_service::add(
-    &mut sp,
-    user.user_id,
-    props.school_id,
-    props.name,
-    props.description,
-    props.active,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+    let course = course_service::get_by_course_id(con, x.course_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+    let is_admin = adminship_service::get_by_user_id_school_id(con, user.user_id, course.school_id)
+      .await
+      .map_err(report_postgres_err)?
+      .is_some();
 
-  sp.commit().await.map_err(report_postgres_err)?;
+    if is_member || is_admin {
+      resp_course_datas.push(fill_course_data(con, x).await?);
+    }
+  }
 
-  // return json
-  fill_school_data(con, school_data).await
+  Ok(resp_course_datas)
 }
 
-pub async fn school_duration_new(
+pub async fn course_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolDurationNewProps,
-) -> Result<response::SchoolDuration, response::InnexgoHoursError> {
+  props: request::CourseDataHistoryProps,
+) -> Result<Vec<response::Version<response::CourseData>>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
 
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  let school = school_service::get_by_school_id(&mut sp, props.school_id)
+  let course = course_service::get_by_course_id(con, props.course_id)
     .await
     .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
 
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
+  // students and instructors can see the courses they are (or were) a member of
+  // administrators can see those plus the courses that they own
+  let is_member =
+    course_membership_service::get_by_user_id_course_id(con, user.user_id, course.course_id)
+      .await
+      .map_err(report_postgres_err)?
+      .is_some();
 
-  if !adminship_service::is_admin(&mut sp, user.user_id, props.school_id)
+  let is_admin = adminship_service::get_by_user_id_school_id(con, user.user_id, course.school_id)
     .await
     .map_err(report_postgres_err)?
-  {
+    .is_some();
+
+  if !(is_member || is_admin) {
     return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  // create duration
-  let school_duration = school_duration_service::add(&mut sp, user.user_id, props.school_id)
+  // every version of the course
+  let course_data = course_data_service::get_history_by_course_id(con, props.course_id)
     .await
     .map_err(report_postgres_err)?;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  let mut resp_course_datas = vec![];
+  for x in course_data.into_iter() {
+    resp_course_datas.push(fill_course_data(con, x).await?);
+  }
 
-  // return json
-  fill_school_duration(con, school_duration).await
+  fill_history(resp_course_datas)
 }
 
-pub async fn school_duration_data_new(
+pub async fn location_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolDurationDataNewProps,
-) -> Result<response::SchoolDurationData, response::InnexgoHoursError> {
+  props: request::LocationViewProps,
+) -> Result<Vec<response::Location>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // fetch school duration
-  let school_duration =
-    school_duration_service::get_by_school_duration_id(&mut sp, props.school_duration_id)
-      .await
-      .map_err(report_postgres_err)?
-      .ok_or(response::InnexgoHoursError::SchoolDurationNonexistent)?;
-
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, school_duration.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
-
-  if !adminship_service::is_admin(&mut sp, user.user_id, school_duration.school_id)
+  // get users
+  let locations = location_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
+    .map_err(report_postgres_err)?;
 
-  // create data
-  let school_duration_data = school_duration_data_service::add(
-    &mut sp,
-    user.user_id,
-    props.school_duration_id,
-    props.day,
-    props.minute_start,
-    props.minute_end,
-    props.active,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  // return locations
+  let mut resp_locations = vec![];
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  for x in locations.into_iter() {
+    // all locations are visible
+    resp_locations.push(fill_location(con, x).await?);
+  }
 
-  // return json
-  fill_school_duration_data(con, school_duration_data).await
+  Ok(resp_locations)
 }
 
-pub async fn school_key_new(
+pub async fn location_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolKeyNewProps,
-) -> Result<response::SchoolKeyData, response::InnexgoHoursError> {
-  if props.start_time > props.end_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
-  }
+  props: request::LocationDataViewProps,
+) -> Result<Vec<response::LocationData>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  let school = school_service::get_by_school_id(&mut sp, props.school_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
-
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
+  // get users
+  let location_data = location_data_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
+    .map_err(report_postgres_err)?;
 
-  // get is admin
-  if !adminship_service::is_admin(&mut sp, user.user_id, school.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  // return location_datas
+  let mut resp_location_datas = vec![];
+  for x in location_data.into_iter() {
+    // all location datas are visible, meeting links are kept apart in meeting_link_t
+    resp_location_datas.push(fill_location_data(con, x).await?);
   }
 
-  // now create key
-  let school_key = school_key_service::add(
-    &mut sp,
-    &utils::gen_random_string(),
-    user.user_id,
-    props.school_id,
-    props.start_time,
-    props.end_time,
-  )
-  .await
-  .map_err(report_postgres_err)?;
-
-  // create key data
-  let school_key_data =
-    school_key_data_service::add(&mut sp, user.user_id, school_key.school_key_key, true)
-      .await
-      .map_err(report_postgres_err)?;
-
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
-
-  // return json
-  fill_school_key_data(con, school_key_data).await
+  Ok(resp_location_datas)
 }
 
-pub async fn school_key_data_new(
+pub async fn location_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolKeyDataNewProps,
-) -> Result<response::SchoolKeyData, response::InnexgoHoursError> {
+  props: request::LocationDataHistoryProps,
+) -> Result<Vec<response::Version<response::LocationData>>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let _ = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // get school key
-  let school_key = school_key_service::get_by_school_key_key(&mut sp, &props.school_key_key)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolKeyNonexistent)?;
-
-  // get corresponding school
-  let school = school_service::get_by_school_id(&mut sp, school_key.school_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
 
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, school_key.school_id)
+  // all location datas are visible
+  let location_data = location_data_service::get_history_by_location_id(con, props.location_id)
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
+    .map_err(report_postgres_err)?;
 
-  // get admin
-  if !adminship_service::is_admin(&mut sp, user.user_id, school.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  let mut resp_location_datas = vec![];
+  for x in location_data.into_iter() {
+    resp_location_datas.push(fill_location_data(con, x).await?);
   }
 
-  // create key data
-  let school_key_data = school_key_data_service::add(
-    &mut sp,
-    user.user_id,
-    school_key.school_key_key,
-    props.active,
-  )
-  .await
-  .map_err(report_postgres_err)?;
-
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
-
-  // return json
-  fill_school_key_data(con, school_key_data).await
+  fill_history(resp_location_datas)
 }
 
-pub async fn adminship_new_key(
+pub async fn course_membership_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::AdminshipNewKeyProps,
-) -> Result<response::Adminship, response::InnexgoHoursError> {
-  // validate api membership
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  props: request::CourseMembershipViewProps,
+) -> Result<Vec<response::CourseMembership>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // get school key
-  let school_key = school_key_service::get_by_school_key_key(&mut sp, &props.school_key_key)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolKeyNonexistent)?;
-
-  // time is within range
-  let now = utils::current_time_millis();
-  if now < school_key.start_time || now > school_key.end_time {
-    return Err(response::InnexgoHoursError::SchoolKeyExpired);
-  }
-
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, school_key.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
-
-  // it fits under max uses
-  if adminship_service::count_school_key_uses(&mut sp, &school_key.school_key_key)
+  // get users
+  let course_memberships = course_membership_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    >= 1
-  {
-    return Err(response::InnexgoHoursError::SchoolKeyUsed);
-  }
+    .map_err(report_postgres_err)?;
 
-  // now create adminship
-  let adminship = adminship_service::add(
-    &mut sp,
-    user.user_id,
-    user.user_id,
-    school_key.school_id,
-    request::AdminshipKind::Admin,
-    Some(school_key.school_key_key),
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  // return course_memberships
+  let mut resp_course_memberships = vec![];
+  for x in course_memberships.into_iter() {
+    // members of a course can see all their fellow course memberships
 
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
+    let can_view = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      x.course_id,
+      CourseCapability::View,
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-  // return json
-  fill_adminship(con, adminship).await
+    if can_view {
+      resp_course_memberships.push(fill_course_membership(con, x).await?);
+    }
+  }
+
+  Ok(resp_course_memberships)
 }
 
-pub async fn adminship_new_cancel(
+pub async fn course_key_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::AdminshipNewCancelProps,
-) -> Result<response::Adminship, response::InnexgoHoursError> {
-  // validate api membership
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
-
-  // validate target exists
-  let target = auth_service
-    .get_user_by_id(props.user_id)
-    .await
-    .map_err(report_auth_err)?;
+  props: request::CourseKeyViewProps,
+) -> Result<Vec<response::CourseKey>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // check that school isn't archived
-  if !school_data_service::is_active_by_school_id(&mut sp, props.school_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::SchoolArchived);
-  }
-
-  // get corresponding school
-  let school = school_service::get_by_school_id(&mut sp, props.school_id)
+  // get users
+  let course_keys = course_key_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  if !adminship_service::is_admin(&mut sp, user.user_id, school.school_id)
+  // return course_keys
+  let mut resp_course_keys = vec![];
+  for x in course_keys.into_iter() {
+    // only course managers may view course keys
+    let can_manage = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      x.course_id,
+      CourseCapability::Manage,
+    )
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
+    .map_err(report_postgres_err)?;
 
-  // make sure we cannot leave the school without an admin
-  if adminship_service::count_admins(&mut sp, school.school_id)
-    .await
-    .map_err(report_postgres_err)?
-    <= 1
-  {
-    return Err(response::InnexgoHoursError::AdminshipCannotLeaveEmpty);
+    if can_manage {
+      resp_course_keys.push(fill_course_key(con, x).await?);
+    }
   }
 
-  // now create cancel
-  let adminship = adminship_service::add(
-    &mut sp,
-    user.user_id,
-    props.user_id,
-    school.school_id,
-    request::AdminshipKind::Cancel,
-    None,
-  )
-  .await
-  .map_err(report_postgres_err)?;
-
-  // commit changes
-  sp.commit().await.map_err(report_postgres_err)?;
-
-  // return json
-  fill_adminship(con, adminship).await
+  Ok(resp_course_keys)
 }
 
-pub async fn session_request_new(
+pub async fn course_key_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SessionRequestNewProps,
-) -> Result<response::SessionRequest, response::InnexgoHoursError> {
-  if props.start_time > props.end_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
-  }
-
+  props: request::CourseKeyDataViewProps,
+) -> Result<Vec<response::CourseKeyData>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
+  // get users
+  let course_key_data = course_key_data_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+  // return course_key_datas
+  let mut resp_course_key_datas = vec![];
+  for x in course_key_data.into_iter() {
+    let course_key = course_key_service::get_by_course_key_key(con, &x.course_key_key)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
 
-  // check course exists
-  let _ = course_service::get_by_course_id(&mut sp, props.course_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
+    // only course managers may view course key data
+    let can_manage = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      course_key.course_id,
+      CourseCapability::Manage,
+    )
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
-  }
+    .map_err(report_postgres_err)?;
 
-  // check is student of course
-  if !course_membership_service::is_student(&mut sp, user.user_id, props.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+    if can_manage {
+      resp_course_key_datas.push(fill_course_key_data(con, x).await?);
+    }
   }
 
-  // create request
-  let session_request = session_request_service::add(
-    &mut sp,
-    user.user_id,
-    props.course_id,
-    props.message,
-    props.start_time,
-    props.end_time,
-  )
-  .await
-  .map_err(report_postgres_err)?;
-
-  sp.commit().await.map_err(report_postgres_err)?;
-
-  // return json
-  fill_session_request(con, session_request).await
+  Ok(resp_course_key_datas)
 }
 
-pub async fn session_request_response_new(
+pub async fn course_key_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SessionRequestResponseNewProps,
-) -> Result<response::SessionRequestResponse, response::InnexgoHoursError> {
+  props: request::CourseKeyDataHistoryProps,
+) -> Result<Vec<response::Version<response::CourseKeyData>>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
 
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // check session request exists
-  let session_request =
-    session_request_service::get_by_session_request_id(&mut sp, props.session_request_id)
-      .await
-      .map_err(report_postgres_err)?
-      .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
-
-  // check that session request response doesn't exist
-  if session_request_response_service::get_by_session_request_id(&mut sp, props.session_request_id)
+  let course_key = course_key_service::get_by_course_key_key(con, &props.course_key_key)
     .await
     .map_err(report_postgres_err)?
-    .is_some()
-  {
-    return Err(response::InnexgoHoursError::SessionRequestResponseExistent);
-  }
+    .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, session_request.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
+  // only course managers may view course key data
+  let can_manage = course_membership_service::has_capability(
+    con,
+    user.user_id,
+    course_key.course_id,
+    CourseCapability::Manage,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  if !can_manage {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  // check this user is instructor
-  let is_instructor =
-    course_membership_service::is_instructor(&mut sp, user.user_id, session_request.course_id)
+  // every version of the course key
+  let course_key_data =
+    course_key_data_service::get_history_by_course_key_key(con, &props.course_key_key)
       .await
       .map_err(report_postgres_err)?;
 
-  let is_creator = user.user_id == session_request.creator_user_id;
-
-  let commitment_id = match props.session_id {
-    None => {
-      // either the creator of the request or the instructor may cancel
-      if !is_instructor && !is_creator {
-        return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-      }
-      None
-    }
-    // if accepted
-    Some(session_id) => {
-      // only the instructor can create a commitment
-      if !is_instructor {
-        return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-      }
+  let mut resp_course_key_datas = vec![];
+  for x in course_key_data.into_iter() {
+    resp_course_key_datas.push(fill_course_key_data(con, x).await?);
+  }
 
-      // check if session id is valid
-      let session = session_service::get_by_session_id(&mut sp, session_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+  fill_history(resp_course_key_datas)
+}
 
-      // check that session is compatible with session_request's course
-      if session_request.course_id != session.course_id {
-        return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-      }
+pub async fn course_invitation_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CourseInvitationViewProps,
+) -> Result<Vec<response::CourseInvitation>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-      // check if we already have a commitment
-      let maybe_commitment = commitment_service::get_by_attendee_user_id_session_id(
-        &mut sp,
-        session_request.creator_user_id,
-        session_id,
-      )
-      .await
-      .map_err(report_postgres_err)?;
+  let con = &mut *db.lock().await;
+  // get invitations
+  let course_invitations = course_invitation_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-      let commitment = match maybe_commitment {
-        // use commitment if exists
-        Some(commitment) => commitment,
+  // return course_invitations
+  let mut resp_course_invitations = vec![];
+  for x in course_invitations.into_iter() {
+    // only course managers may view course invitations
+    let can_manage = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      x.course_id,
+      CourseCapability::Manage,
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-        // otherwise add one
-        None => commitment_service::add(
-          &mut sp,
-          user.user_id,
-          session_request.creator_user_id,
-          session_id,
-          true,
-        )
-        .await
-        .map_err(report_postgres_err)?,
-      };
-      // return
-      Some(commitment.commitment_id)
+    if can_manage {
+      resp_course_invitations.push(fill_course_invitation(con, x).await?);
     }
-  };
+  }
 
-  // create request response
-  let session_request_response = session_request_response_service::add(
-    &mut sp,
-    props.session_request_id,
-    user.user_id,
-    props.message,
-    commitment_id,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  Ok(resp_course_invitations)
+}
+
+pub async fn commitment_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::CommitmentViewProps,
+) -> Result<Vec<response::Commitment>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  let con = &mut *db.lock().await;
+  // get users
+  let commitments = commitment_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  // return json
-  fill_session_request_response(con, session_request_response).await
+  // return commitments
+  let mut resp_commitments = vec![];
+  for x in commitments.into_iter() {
+    if can_view_commitment(con, user.user_id, &x).await? {
+      resp_commitments.push(fill_commitment(con, x).await?);
+    }
+  }
+  Ok(resp_commitments)
 }
 
-pub async fn session_new(
+pub async fn encounter_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SessionNewProps,
-) -> Result<response::SessionData, response::InnexgoHoursError> {
-  if props.start_time > props.end_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
+  props: request::EncounterViewProps,
+) -> Result<Vec<response::Encounter>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get users
+  let encounters = encounter_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // return encounters
+  let mut resp_encounters = vec![];
+  for x in encounters.into_iter() {
+    if can_view_encounter(con, user.user_id, &x).await? {
+      resp_encounters.push(fill_encounter(con, x).await?);
+    }
   }
+  Ok(resp_encounters)
+}
 
+pub async fn session_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionViewProps,
+) -> Result<Vec<response::Session>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // validate course exists
-  let _ = course_service::get_by_course_id(&mut sp, props.course_id)
+  // get users
+  let sessions = session_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // ensure session creator is instructor
-  if !course_membership_service::is_instructor(&mut sp, user.user_id, props.course_id)
+  // return sessions
+  let mut resp_sessions = vec![];
+  for x in sessions.into_iter() {
+    // members of the course can see sessions
+    let can_view = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      x.course_id,
+      CourseCapability::View,
+    )
     .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
+    .map_err(report_postgres_err)?;
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, props.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
+    if can_view {
+      resp_sessions.push(fill_session(con, x).await?);
+    }
   }
 
-  // create session
-  let session = session_service::add(&mut sp, user.user_id, props.course_id)
+  Ok(resp_sessions)
+}
+
+pub async fn session_data_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionDataViewProps,
+) -> Result<Vec<response::SessionData>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get users
+  let session_data = session_data_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // create session data
-  let session_data = session_data_service::add(
-    &mut sp,
-    user.user_id,
-    session.session_id,
-    props.name,
-    props.start_time,
-    props.end_time,
-    true,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  // return session_datas
+  let mut resp_session_datas = vec![];
+  for x in session_data.into_iter() {
+    // members of the course can see sessions
 
-  // create session from provided users automatically
-  for attendee_user_id in props.attendee_user_ids {
-    // ensure attendee is the student of the session's course
-    if !course_membership_service::is_student(&mut sp, attendee_user_id, session.course_id)
+    let session = session_service::get_by_session_id(con, x.session_id)
       .await
       .map_err(report_postgres_err)?
-    {
-      return Err(response::InnexgoHoursError::UserNonexistent);
-    }
+      .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
 
-    // create a new committment
-    let _ = commitment_service::add(
-      &mut sp,
+    let can_view = course_membership_service::has_capability(
+      con,
       user.user_id,
-      attendee_user_id,
-      session.session_id,
-      true,
+      session.course_id,
+      CourseCapability::View,
     )
     .await
     .map_err(report_postgres_err)?;
-  }
 
-  sp.commit().await.map_err(report_postgres_err)?;
+    if can_view {
+      resp_session_datas.push(fill_session_data(con, x).await?);
+    }
+  }
 
-  // return json
-  fill_session_data(con, session_data).await
+  Ok(resp_session_datas)
 }
 
-pub async fn session_data_new(
+pub async fn session_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SessionDataNewProps,
-) -> Result<response::SessionData, response::InnexgoHoursError> {
-  // prevent negative duration
-  if props.start_time > props.end_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
-  }
-
+  props: request::SessionDataHistoryProps,
+) -> Result<Vec<response::Version<response::SessionData>>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  let session = session_service::get_by_session_id(&mut sp, props.session_id)
+  let session = session_service::get_by_session_id(con, props.session_id)
     .await
     .map_err(report_postgres_err)?
     .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
 
-  // ensure session creator is instructor
-  if !course_membership_service::is_instructor(&mut sp, user.user_id, session.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
-
-  // now we can update data
-  let session_data = session_data_service::add(
-    &mut sp,
+  // members of the course can see sessions
+  let can_view = course_membership_service::has_capability(
+    con,
     user.user_id,
-    session.session_id,
-    props.name,
-    props.start_time,
-    props.end_time,
-    props.active,
+    session.course_id,
+    CourseCapability::View,
   )
   .await
   .map_err(report_postgres_err)?;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  if !can_view {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
 
-  // return json
-  fill_session_data(con, session_data).await
+  // every version of the session
+  let session_data = session_data_service::get_history_by_session_id(con, props.session_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let mut resp_session_datas = vec![];
+  for x in session_data.into_iter() {
+    resp_session_datas.push(fill_session_data(con, x).await?);
+  }
+
+  fill_history(resp_session_datas)
 }
 
-pub async fn commitment_new(
+pub async fn session_change_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CommitmentNewProps,
-) -> Result<Vec<response::Commitment>, response::InnexgoHoursError> {
+  props: request::SessionChangeViewProps,
+) -> Result<Vec<response::SessionChange>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // validate session exists
-  let session = session_service::get_by_session_id(&mut sp, props.session_id)
+  let session_changes = session_change_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // ensure commitment creator is instructor of the session's course
-  if !course_membership_service::is_instructor(&mut sp, user.user_id, session.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  // return session changes
+  let mut resp_session_changes = vec![];
+  for x in session_changes.into_iter() {
+    if can_view_session_change(con, user.user_id, &x).await? {
+      resp_session_changes.push(fill_session_change(con, x).await?);
+    }
   }
 
-  // check that course isn't archived
-  if !course_data_service::is_active_by_course_id(&mut sp, session.course_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::CourseArchived);
-  }
+  Ok(resp_session_changes)
+}
 
-  let mut commitments_ret = vec![];
+pub async fn session_note_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SessionNoteViewProps,
+) -> Result<Vec<response::SessionNote>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // create session from provided users automatically
-  for attendee_user_id in props.attendee_user_ids {
-    // ensure attendee is the student of the session's course
-    if !course_membership_service::is_student(&mut sp, attendee_user_id, session.course_id)
-      .await
-      .map_err(report_postgres_err)?
-    {
-      return Err(response::InnexgoHoursError::UserNonexistent);
-    }
+  let con = &mut *db.lock().await;
 
-    // create a new committment
-    let commitment = commitment_service::add(
-      &mut sp,
-      user.user_id,
-      attendee_user_id,
-      session.session_id,
-      props.active,
-    )
+  let session_notes = session_note_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-    // push json ready version
-    commitments_ret.push(fill_commitment(&mut sp, commitment).await?);
+  // return session notes
+  let mut resp_session_notes = vec![];
+  for x in session_notes.into_iter() {
+    if can_view_session_note(con, user.user_id, &x).await? {
+      resp_session_notes.push(fill_session_note(con, x).await?);
+    }
   }
 
-  sp.commit().await.map_err(report_postgres_err)?;
-
-  Ok(commitments_ret)
+  Ok(resp_session_notes)
 }
 
-pub async fn encounter_new(
+pub async fn session_note_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::EncounterNewProps,
-) -> Result<response::Encounter, response::InnexgoHoursError> {
+  props: request::SessionNoteDataViewProps,
+) -> Result<Vec<response::SessionNoteData>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
-  // validate location exists
-  let _ = location_service::get_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
-
-  // check that location is not archived
-  if !location_data_service::is_active_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::LocationArchived);
-  }
-
-  // can only add encounter if you are an instructor of the claimed location
-  if !course_membership_service::is_instructor_at(&mut sp, user.user_id, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
 
-  let encounter = encounter_service::add(
-    &mut sp,
-    user.user_id,
-    props.location_id,
-    props.attendee_user_id,
-    request::EncounterKind::Manual,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  let session_note_data = session_note_data_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  // return session note data
+  let mut resp_session_note_data = vec![];
+  for x in session_note_data.into_iter() {
+    if can_view_session_note_data(con, user.user_id, &x).await? {
+      resp_session_note_data.push(fill_session_note_data(con, x).await?);
+    }
+  }
 
-  // return json
-  fill_encounter(con, encounter).await
+  Ok(resp_session_note_data)
 }
 
-pub async fn stay_new(
+pub async fn session_note_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::StayNewProps,
-) -> Result<response::StayData, response::InnexgoHoursError> {
+  props: request::SessionNoteDataHistoryProps,
+) -> Result<Vec<response::Version<response::SessionNoteData>>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // validate attendee exists
-  let attendee = auth_service
-    .get_user_by_id(props.attendee_user_id)
-    .await
-    .map_err(report_auth_err)?;
+  // every version of the note, all of them must be visible
+  let session_note_data =
+    session_note_data_service::get_history_by_session_note_id(con, props.session_note_id)
+      .await
+      .map_err(report_postgres_err)?;
 
-  // validate location exists
-  let location = location_service::get_by_location_id(&mut sp, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+  if session_note_data.is_empty() {
+    return Err(response::InnexgoHoursError::SessionNoteNonexistent);
+  }
 
-  // validate the encounters if they exist, and get the min and max times
-  let fst = match (props.fst_encounter_id, props.fst_time) {
-    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
-    (None, Some(time)) => Right(time),
-    (Some(encounter_id), None) => {
-      // validate location exists
-      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
-      // validate encounter  has the required specs
-      if encounter.location_id != props.location_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
-      }
-      if encounter.attendee_user_id != props.attendee_user_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
-      }
-      Left(encounter)
-    }
-    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
-  };
-  let snd = match (props.snd_encounter_id, props.snd_time) {
-    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
-    (None, Some(time)) => Right(time),
-    (Some(encounter_id), None) => {
-      // validate location exists
-      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
-      // validate encounter  has the required specs
-      if encounter.location_id != props.location_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
-      }
-      if encounter.attendee_user_id != props.attendee_user_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
-      }
-      Left(encounter)
+  let mut resp_session_note_data = vec![];
+  for x in session_note_data.into_iter() {
+    if !can_view_session_note_data(con, user.user_id, &x).await? {
+      return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
     }
-    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
-  };
-
-  // validate fst time is before snd time
-  let fst_time = fst.clone().map_left(|x| x.creation_time).into_inner();
-  let snd_time = snd.clone().map_left(|x| x.creation_time).into_inner();
-  if fst_time > snd_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
+    resp_session_note_data.push(fill_session_note_data(con, x).await?);
   }
 
-  // ensure stay creator is an instructor at the location
-  if !course_membership_service::is_instructor_at(&mut sp, user.user_id, props.location_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
-  }
+  fill_history(resp_session_note_data)
+}
 
-  // create stay
-  let stay = stay_service::add(
-    &mut sp,
-    user.user_id,
-    props.attendee_user_id,
-    props.location_id,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+pub async fn meeting_link_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::MeetingLinkViewProps,
+) -> Result<Vec<response::MeetingLink>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // create stay data
-  let stay_data = stay_data_service::add(
-    &mut sp,
-    user.user_id,
-    stay.stay_id,
-    fst.map_left(|x| x.encounter_id),
-    snd.map_left(|x| x.encounter_id),
-    true,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  let con = &mut *db.lock().await;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  let meeting_links = meeting_link_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  // return json
-  fill_stay_data(con, stay_data).await
+  // everyone else only ever gets a pass
+  let mut resp_meeting_links = vec![];
+  for x in meeting_links.into_iter() {
+    if can_manage_meeting_link(con, user.user_id, x.location_id, x.session_id).await? {
+      resp_meeting_links.push(fill_meeting_link(con, x).await?);
+    }
+  }
+
+  Ok(resp_meeting_links)
 }
 
-pub async fn stay_data_new(
+pub async fn meeting_join_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::StayDataNewProps,
-) -> Result<response::StayData, response::InnexgoHoursError> {
+  props: request::MeetingJoinViewProps,
+) -> Result<Vec<response::MeetingJoin>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
-  // validate stay exists
-  let stay = stay_service::get_by_stay_id(&mut sp, props.stay_id)
+  let meeting_joins = meeting_join_service::query(con, props)
     .await
-    .map_err(report_postgres_err)?
-    .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+    .map_err(report_postgres_err)?;
 
-  // ensure stay creator is an instructor at the location of the original stay
-  if !course_membership_service::is_instructor_at(&mut sp, user.user_id, stay.location_id)
-    .await
-    .map_err(report_postgres_err)?
-  {
-    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  // return meeting joins
+  let mut resp_meeting_joins = vec![];
+  for x in meeting_joins.into_iter() {
+    if can_view_meeting_join(con, user.user_id, &x).await? {
+      resp_meeting_joins.push(fill_meeting_join(con, x).await?);
+    }
   }
 
-  // validate the encounters if they exist, and get the min and max times
-  let fst = match (props.fst_encounter_id, props.fst_time) {
-    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
-    (None, Some(time)) => Right(time),
-    (Some(encounter_id), None) => {
-      // validate location exists
-      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
-      // validate encounter  has the required specs
-      if encounter.location_id != stay.location_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
-      }
-      if encounter.attendee_user_id != stay.attendee_user_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
-      }
-      Left(encounter)
-    }
-    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
-  };
-  let snd = match (props.snd_encounter_id, props.snd_time) {
-    (None, None) => return Err(response::InnexgoHoursError::StayProvidedNoTime),
-    (None, Some(time)) => Right(time),
-    (Some(encounter_id), None) => {
-      // validate location exists
-      let encounter = encounter_service::get_by_encounter_id(&mut sp, encounter_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::EncounterNonexistent)?;
-      // validate encounter  has the required specs
-      if encounter.location_id != stay.location_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongLocation);
-      }
-      if encounter.attendee_user_id != stay.attendee_user_id {
-        return Err(response::InnexgoHoursError::StayEncounterWrongUser);
-      }
-      Left(encounter)
-    }
-    (Some(_), Some(_)) => return Err(response::InnexgoHoursError::StayProvidedDoubleTime),
-  };
+  Ok(resp_meeting_joins)
+}
 
-  // validate fst time is before snd time
-  let fst_time = fst.clone().map_left(|x| x.creation_time).into_inner();
-  let snd_time = snd.clone().map_left(|x| x.creation_time).into_inner();
-  if fst_time > snd_time {
-    return Err(response::InnexgoHoursError::NegativeDuration);
-  }
+// students, their guardians, and staff who can view records in the course may see irregularities
+pub async fn irregularity_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::IrregularityViewProps,
+) -> Result<Vec<response::Irregularity>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
-  // create stay data
-  let stay_data = stay_data_service::add(
-    &mut sp,
-    user.user_id,
-    stay.stay_id,
-    fst.map_left(|x| x.encounter_id),
-    snd.map_left(|x| x.encounter_id),
-    props.active,
-  )
-  .await
-  .map_err(report_postgres_err)?;
+  let con = &mut *db.lock().await;
 
-  sp.commit().await.map_err(report_postgres_err)?;
+  let irregularities = irregularity_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
 
-  // return json
-  fill_stay_data(con, stay_data).await
+  // irregularities are as visible as the commitment they're about
+  let mut resp_irregularities = vec![];
+  for x in irregularities.into_iter() {
+    let commitment = commitment_service::get_by_commitment_id(con, x.commitment_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
+
+    if can_view_commitment(con, user.user_id, &commitment).await? {
+      resp_irregularities.push(fill_irregularity(con, x).await?);
+    }
+  }
+
+  Ok(resp_irregularities)
 }
 
-pub async fn subscription_view(
+pub async fn feedback_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SubscriptionViewProps,
-) -> Result<Vec<response::Subscription>, response::InnexgoHoursError> {
+  props: request::FeedbackDataViewProps,
+) -> Result<Vec<response::FeedbackData>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let subscriptions = subscription_service::query(con, props)
+
+  let feedback_data = feedback_data_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return subscriptions
-  let mut resp_subscriptions = vec![];
-  for u in subscriptions
-    // you can view your own subscriptions
-    .into_iter()
-    .filter(|u| u.creator_user_id == user.user_id)
-  {
-    resp_subscriptions.push(fill_subscription(con, u).await?);
+  // return feedback data
+  let mut resp_feedback_data = vec![];
+  for x in feedback_data.into_iter() {
+    if can_view_feedback_data(con, user.user_id, &x).await? {
+      resp_feedback_data.push(fill_feedback_data(con, x).await?);
+    }
   }
 
-  Ok(resp_subscriptions)
+  Ok(resp_feedback_data)
 }
 
-pub async fn school_view(
+pub async fn feedback_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolViewProps,
-) -> Result<Vec<response::School>, response::InnexgoHoursError> {
+  props: request::FeedbackDataHistoryProps,
+) -> Result<Vec<response::Version<response::FeedbackData>>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let schools = school_service::query(con, props)
+
+  let feedback = feedback_service::get_by_feedback_id(con, props.feedback_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::FeedbackNonexistent)?;
+
+  // like the feedback itself, its history is only for the author
+  if feedback.creator_user_id != user.user_id {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // every version of the feedback
+  let feedback_data = feedback_data_service::get_history_by_feedback_id(con, props.feedback_id)
     .await
     .map_err(report_postgres_err)?;
 
-  // return schools
-  let mut resp_schools = vec![];
-  for u in schools.into_iter() {
-    // you can view all schools
-    resp_schools.push(fill_school(con, u).await?);
+  let mut resp_feedback_datas = vec![];
+  for x in feedback_data.into_iter() {
+    resp_feedback_datas.push(fill_feedback_data(con, x).await?);
   }
 
-  Ok(resp_schools)
+  fill_history(resp_feedback_datas)
 }
 
-pub async fn school_data_view(
+// one summary per instructor of each course with feedback, for the courses the user may see the records of
+pub async fn feedback_summary_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolDataViewProps,
-) -> Result<Vec<response::SchoolData>, response::InnexgoHoursError> {
+  props: request::FeedbackSummaryViewProps,
+) -> Result<Vec<response::FeedbackSummary>, response::InnexgoHoursError> {
   // validate api key
-  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let school_data = school_data_service::query(con, props)
+
+  let entries = feedback_data_service::get_active_entries(
+    con,
+    &props.course_id,
+    &props.instructor_user_id,
+    props.min_start_time,
+    props.max_start_time,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  // entries come sorted by course, then instructor
+  let mut by_instructor: Vec<((i64, i64), Vec<FeedbackEntry>)> = vec![];
+  for x in entries.into_iter() {
+    let key = (x.course_id, x.instructor_user_id);
+    match by_instructor.last_mut() {
+      Some((k, instructor_entries)) if *k == key => instructor_entries.push(x),
+      _ => by_instructor.push((key, vec![x])),
+    }
+  }
+
+  let mut resp_feedback_summaries = vec![];
+  for ((course_id, instructor_user_id), instructor_entries) in by_instructor.into_iter() {
+    let course = course_service::get_by_course_id(con, course_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+    // staff who can view the course's records, and administrators of its school
+    let can_view_records = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      course.course_id,
+      CourseCapability::ViewRecords,
+    )
     .await
     .map_err(report_postgres_err)?;
-  // return users
-  // return school_datas
-  let mut resp_school_datas = vec![];
-  for u in school_data.into_iter() {
-    // you can view all schools
-    resp_school_datas.push(fill_school_data(con, u).await?);
+
+    let is_admin = adminship_service::is_admin(con, user.user_id, course.school_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+    if can_view_records || is_admin {
+      resp_feedback_summaries.push(
+        fill_feedback_summary(
+          con,
+          course,
+          instructor_user_id,
+          instructor_entries,
+          props.anonymize,
+        )
+        .await?,
+      );
+    }
   }
 
-  Ok(resp_school_datas)
+  Ok(resp_feedback_summaries)
 }
 
-pub async fn school_duration_view(
+pub async fn attachment_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolDurationViewProps,
-) -> Result<Vec<response::SchoolDuration>, response::InnexgoHoursError> {
+  props: request::AttachmentViewProps,
+) -> Result<Vec<response::Attachment>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let school_durations = school_duration_service::query(con, props)
+
+  let attachments = attachment_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return school_durations
-  let mut resp_school_durations = vec![];
-  for u in school_durations.into_iter() {
-    // you can view all school_durations
-    resp_school_durations.push(fill_school_duration(con, u).await?);
+  // return attachments
+  let mut resp_attachments = vec![];
+  for x in attachments.into_iter() {
+    if can_view_attachment(con, user.user_id, &x).await? {
+      resp_attachments.push(fill_attachment(con, x).await?);
+    }
   }
 
-  Ok(resp_school_durations)
+  Ok(resp_attachments)
 }
 
-pub async fn school_duration_data_view(
-  _config: Config,
+pub async fn attachment_download(
+  config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SchoolDurationDataViewProps,
-) -> Result<Vec<response::SchoolDurationData>, response::InnexgoHoursError> {
+  props: request::AttachmentDownloadProps,
+) -> Result<response::AttachmentDownload, response::InnexgoHoursError> {
   // validate api key
-  let _ = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let school_duration_data = school_duration_data_service::query(con, props)
+
+  let attachment = attachment_service::get_by_attachment_id(con, props.attachment_id)
     .await
-    .map_err(report_postgres_err)?;
-  // return users
-  // return school_duration_datas
-  let mut resp_school_duration_datas = vec![];
-  for u in school_duration_data.into_iter() {
-    // you can view all school_durations
-    resp_school_duration_datas.push(fill_school_duration_data(con, u).await?);
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::AttachmentNonexistent)?;
+
+  if !can_view_attachment(con, user.user_id, &attachment).await? {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  Ok(resp_school_duration_datas)
+  let data = config
+    .blob_store
+    .get(&attachment.checksum)
+    .await
+    .map_err(report_blob_err)?
+    .ok_or(response::InnexgoHoursError::AttachmentNonexistent)?;
+
+  Ok(response::AttachmentDownload {
+    attachment: fill_attachment(con, attachment).await?,
+    data: base64_url::encode(&data),
+  })
 }
 
-pub async fn course_view(
+pub async fn availability_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseViewProps,
-) -> Result<Vec<response::Course>, response::InnexgoHoursError> {
+  props: request::AvailabilityViewProps,
+) -> Result<Vec<response::Availability>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let courses = course_service::query(con, props)
+
+  let availabilities = availability_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return courses
-  let mut resp_courses = vec![];
-
-  for x in courses.into_iter() {
-    // students and instructors can see the courses they are (or were) a member of
-    // administrators can see those plus the courses that they own
-
-    let is_member =
-      course_membership_service::get_by_user_id_course_id(con, user.user_id, x.course_id)
-        .await
-        .map_err(report_postgres_err)?
-        .is_some();
-
-    let is_admin = adminship_service::get_by_user_id_school_id(con, user.user_id, x.school_id)
-      .await
-      .map_err(report_postgres_err)?
-      .is_some();
+  let mut resp_availabilities = vec![];
+  for x in availabilities.into_iter() {
+    // members of the course can see availabilities
+    let can_view = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      x.course_id,
+      CourseCapability::View,
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-    if is_member || is_admin {
-      resp_courses.push(fill_course(con, x).await?);
+    if can_view {
+      resp_availabilities.push(fill_availability(con, x).await?);
     }
   }
 
-  Ok(resp_courses)
+  Ok(resp_availabilities)
 }
 
-pub async fn course_data_view(
+pub async fn availability_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseDataViewProps,
-) -> Result<Vec<response::CourseData>, response::InnexgoHoursError> {
+  props: request::AvailabilityDataViewProps,
+) -> Result<Vec<response::AvailabilityData>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let course_data = course_data_service::query(con, props)
+
+  let availability_data = availability_data_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return course_datas
-  let mut resp_course_datas = vec![];
-  for x in course_data.into_iter() {
-    // students and instructors can see the courses they are (or were) a member of
-    // administrators can see those plus the courses that they own
-
-    let is_member =
-      course_membership_service::get_by_user_id_course_id(con, user.user_id, x.course_id)
-        .await
-        .map_err(report_postgres_err)?
-        .is_some();
-
-    let course = course_service::get_by_course_id(con, x.course_id)
+  let mut resp_availability_datas = vec![];
+  for x in availability_data.into_iter() {
+    let availability = availability_service::get_by_availability_id(con, x.availability_id)
       .await
       .map_err(report_postgres_err)?
-      .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+      .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
 
-    let is_admin = adminship_service::get_by_user_id_school_id(con, user.user_id, course.school_id)
-      .await
-      .map_err(report_postgres_err)?
-      .is_some();
+    // members of the course can see availabilities
+    let can_view = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      availability.course_id,
+      CourseCapability::View,
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-    if is_member || is_admin {
-      resp_course_datas.push(fill_course_data(con, x).await?);
+    if can_view {
+      resp_availability_datas.push(fill_availability_data(con, x).await?);
     }
   }
 
-  Ok(resp_course_datas)
+  Ok(resp_availability_datas)
 }
 
-pub async fn location_view(
+pub async fn availability_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::LocationViewProps,
-) -> Result<Vec<response::Location>, response::InnexgoHoursError> {
+  props: request::AvailabilityDataHistoryProps,
+) -> Result<Vec<response::Version<response::AvailabilityData>>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let locations = location_service::query(con, props)
+
+  let availability = availability_service::get_by_availability_id(con, props.availability_id)
     .await
-    .map_err(report_postgres_err)?;
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
 
-  // return locations
-  let mut resp_locations = vec![];
+  // members of the course can see availabilities
+  let can_view = course_membership_service::has_capability(
+    con,
+    user.user_id,
+    availability.course_id,
+    CourseCapability::View,
+  )
+  .await
+  .map_err(report_postgres_err)?;
 
-  for x in locations.into_iter() {
-    // all locations are visible
-    resp_locations.push(fill_location(con, x).await?);
+  if !can_view {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  Ok(resp_locations)
+  // every version of the availability
+  let availability_data =
+    availability_data_service::get_history_by_availability_id(con, props.availability_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+  let mut resp_availability_datas = vec![];
+  for x in availability_data.into_iter() {
+    resp_availability_datas.push(fill_availability_data(con, x).await?);
+  }
+
+  fill_history(resp_availability_datas)
 }
 
-pub async fn location_data_view(
+// the slots that are still free to book, earliest first
+pub async fn availability_slot_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::LocationDataViewProps,
-) -> Result<Vec<response::LocationData>, response::InnexgoHoursError> {
+  props: request::AvailabilitySlotViewProps,
+) -> Result<Vec<response::AvailabilitySlot>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let location_data = location_data_service::query(con, props)
-    .await
-    .map_err(report_postgres_err)?;
 
-  // return location_datas
-  let mut resp_location_datas = vec![];
-  for x in location_data.into_iter() {
-    // all location datas are visible
-    // TODO: we should figure out how to protect zoom links
-    resp_location_datas.push(fill_location_data(con, x).await?);
-  }
+  // slots that have already started can't be booked
+  let min_start_time = std::cmp::max(
+    props.min_start_time.unwrap_or(i64::MIN),
+    utils::current_time_millis(),
+  );
 
-  Ok(resp_location_datas)
-}
+  let availability_data = availability_data_service::get_bookable(
+    con,
+    &props.availability_id,
+    &props.course_id,
+    min_start_time,
+    props.max_start_time,
+  )
+  .await
+  .map_err(report_postgres_err)?;
 
-pub async fn course_membership_view(
-  _config: Config,
-  db: Db,
-  auth_service: AuthService,
-  props: request::CourseMembershipViewProps,
-) -> Result<Vec<response::CourseMembership>, response::InnexgoHoursError> {
-  // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let mut resp_slots = vec![];
+  for x in availability_data.into_iter() {
+    let availability = availability_service::get_by_availability_id(con, x.availability_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::AvailabilityNonexistent)?;
 
-  let con = &mut *db.lock().await;
-  // get users
-  let course_memberships = course_membership_service::query(con, props)
+    // members of the course can see availabilities
+    let can_view = course_membership_service::has_capability(
+      con,
+      user.user_id,
+      availability.course_id,
+      CourseCapability::View,
+    )
     .await
     .map_err(report_postgres_err)?;
 
-  // return course_memberships
-  let mut resp_course_memberships = vec![];
-  for x in course_memberships.into_iter() {
-    // members of a course can see all their fellow course memberships
+    if !can_view {
+      continue;
+    }
 
-    let is_member = course_membership_service::is_member(con, user.user_id, x.course_id)
+    let taken = availability_booking_service::get_taken_start_times(con, x.availability_id)
       .await
       .map_err(report_postgres_err)?;
 
-    if is_member {
-      resp_course_memberships.push(fill_course_membership(con, x).await?);
+    let course = course_service::get_by_course_id(con, availability.course_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
+
+    // slots during a closure can't be booked either
+    let closures = closure_data_service::get_active_by_school_id_in_range(
+      con,
+      course.school_id,
+      x.start_time,
+      x.end_time,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    let resp_availability = fill_availability(con, availability).await?;
+
+    for start_time in availability_data_service::slot_start_times(&x) {
+      if start_time < min_start_time
+        || props.max_start_time.map_or(false, |max| start_time > max)
+        || taken.contains(&start_time)
+        || closures
+          .iter()
+          .any(|c| c.start_time < start_time + x.slot_duration && c.end_time > start_time)
+      {
+        continue;
+      }
+
+      resp_slots.push(response::AvailabilitySlot {
+        availability: resp_availability.clone(),
+        start_time,
+        end_time: start_time + x.slot_duration,
+      });
     }
   }
 
-  Ok(resp_course_memberships)
+  resp_slots.sort_by_key(|x| x.start_time);
+
+  Ok(resp_slots)
 }
 
-pub async fn course_key_view(
+// keeps the number of candidate slots we work out in one go reasonable
+static FREE_TIME_MAX_RANGE: i64 = 31 * 24 * 60 * 60 * 1000;
+
+// finds times in the school's schedule that the given attendees can make, best first
+// school durations are read as minutes from midnight utc, see school_duration_data_new
+pub async fn free_time_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseKeyViewProps,
-) -> Result<Vec<response::CourseKey>, response::InnexgoHoursError> {
+  props: request::FreeTimeViewProps,
+) -> Result<Vec<response::FreeTimeSlot>, response::InnexgoHoursError> {
+  if props.min_time > props.max_time {
+    return Err(response::InnexgoHoursError::NegativeDuration);
+  }
+
+  if props.slot_duration < MIN_SLOT_DURATION
+    || props.max_time - props.min_time > FREE_TIME_MAX_RANGE
+    || (props.max_time - props.min_time) / props.slot_duration > MAX_SLOTS
+  {
+    return Err(response::InnexgoHoursError::FreeTimeRangeInvalid);
+  }
+
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let course_keys = course_key_service::query(con, props)
+
+  let course = course_service::get_by_course_id(con, props.course_id)
     .await
-    .map_err(report_postgres_err)?;
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::CourseNonexistent)?;
 
-  // return course_keys
-  let mut resp_course_keys = vec![];
-  for x in course_keys.into_iter() {
-    // only instructors may view course keys
-    let is_instructor = course_membership_service::is_instructor(con, user.user_id, x.course_id)
-      .await
-      .map_err(report_postgres_err)?;
+  // only those who could schedule the session may look at everyone's calendars
+  if !course_membership_service::has_capability(
+    con,
+    user.user_id,
+    props.course_id,
+    CourseCapability::RunSessions,
+  )
+  .await
+  .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
 
-    if is_instructor {
-      resp_course_keys.push(fill_course_key(con, x).await?);
+  // ensure attendees are students of the course
+  for attendee_user_id in props.attendee_user_ids.iter() {
+    if !course_membership_service::has_capability(
+      con,
+      *attendee_user_id,
+      props.course_id,
+      CourseCapability::Attend,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+      return Err(response::InnexgoHoursError::UserNonexistent);
     }
   }
 
-  Ok(resp_course_keys)
+  let free_slots = free_time_service::get_free_slots(
+    con,
+    course.school_id,
+    &props.attendee_user_ids,
+    props.min_time,
+    props.max_time,
+    props.slot_duration,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  Ok(
+    free_slots
+      .into_iter()
+      .map(|x| response::FreeTimeSlot {
+        start_time: x.start_time,
+        end_time: x.end_time,
+        free_attendee_user_ids: x.free_attendee_user_ids,
+        busy_attendee_user_ids: x.busy_attendee_user_ids,
+      })
+      .collect(),
+  )
 }
 
-pub async fn course_key_data_view(
+pub async fn availability_booking_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CourseKeyDataViewProps,
-) -> Result<Vec<response::CourseKeyData>, response::InnexgoHoursError> {
+  props: request::AvailabilityBookingViewProps,
+) -> Result<Vec<response::AvailabilityBooking>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let course_key_data = course_key_data_service::query(con, props)
+
+  let availability_bookings = availability_booking_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return course_key_datas
-  let mut resp_course_key_datas = vec![];
-  for x in course_key_data.into_iter() {
-    let course_key = course_key_service::get_by_course_key_key(con, &x.course_key_key)
+  let mut resp_availability_bookings = vec![];
+  for x in availability_bookings.into_iter() {
+    let commitment = commitment_service::get_by_commitment_id(con, x.commitment_id)
       .await
       .map_err(report_postgres_err)?
-      .ok_or(response::InnexgoHoursError::CourseKeyNonexistent)?;
-
-    // only instructors may view course key data
-    let is_instructor =
-      course_membership_service::is_instructor(con, user.user_id, course_key.course_id)
-        .await
-        .map_err(report_postgres_err)?;
+      .ok_or(response::InnexgoHoursError::CommitmentNonexistent)?;
 
-    if is_instructor {
-      resp_course_key_datas.push(fill_course_key_data(con, x).await?);
+    // bookings are as visible as the commitments they hold
+    if can_view_commitment(con, user.user_id, &commitment).await? {
+      resp_availability_bookings.push(fill_availability_booking(con, x).await?);
     }
   }
 
-  Ok(resp_course_key_datas)
+  Ok(resp_availability_bookings)
 }
 
-pub async fn commitment_view(
+pub async fn stay_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::CommitmentViewProps,
-) -> Result<Vec<response::Commitment>, response::InnexgoHoursError> {
+  props: request::StayViewProps,
+) -> Result<Vec<response::Stay>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
   // get users
-  let commitments = commitment_service::query(con, props)
+  let stays = stay_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return commitments
-  let mut resp_commitments = vec![];
-  for x in commitments.into_iter() {
-    // only instructors and attendees of the commitment can see their data
-
+  // return stays
+  let mut resp_stays = vec![];
+  for x in stays.into_iter() {
+    // attendees, their guardians, and staff who can view records at the location may view
     let is_attendee = x.attendee_user_id == user.user_id;
 
-    let session = session_service::get_by_session_id(con, x.session_id)
+    let is_guardian = guardianship_service::is_guardian(con, user.user_id, x.attendee_user_id)
       .await
-      .map_err(report_postgres_err)?
-      .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
+      .map_err(report_postgres_err)?;
 
-    let is_instructor =
-      course_membership_service::is_instructor(con, user.user_id, session.course_id)
-        .await
-        .map_err(report_postgres_err)?;
+    let can_view_records = course_membership_service::has_capability_at(
+      con,
+      user.user_id,
+      x.location_id,
+      CourseCapability::ViewRecords,
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-    if is_attendee || is_instructor {
-      resp_commitments.push(fill_commitment(con, x).await?);
+    if is_attendee || is_guardian || can_view_records {
+      resp_stays.push(fill_stay(con, x).await?);
     }
   }
-  Ok(resp_commitments)
+
+  Ok(resp_stays)
 }
 
-pub async fn encounter_view(
+pub async fn stay_data_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::EncounterViewProps,
-) -> Result<Vec<response::Encounter>, response::InnexgoHoursError> {
+  props: request::StayDataViewProps,
+) -> Result<Vec<response::StayData>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
   // get users
-  let encounters = encounter_service::query(con, props)
+  let stay_data = stay_data_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return encounters
-  let mut resp_encounters = vec![];
-  for x in encounters.into_iter() {
-    // TODO: please check if allowed to view
+  // return stay_datas
+  let mut resp_stay_datas = vec![];
+  for x in stay_data.into_iter() {
+    // attendees, their guardians, and staff who can view records at the location may view
+    let stay = stay_service::get_by_stay_id(con, x.stay_id)
+      .await
+      .map_err(report_postgres_err)?
+      .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+
+    let is_attendee = stay.attendee_user_id == user.user_id;
+
+    let is_guardian = guardianship_service::is_guardian(con, user.user_id, stay.attendee_user_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+    let can_view_records = course_membership_service::has_capability_at(
+      con,
+      user.user_id,
+      stay.location_id,
+      CourseCapability::ViewRecords,
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-    resp_encounters.push(fill_encounter(con, x).await?);
+    if is_attendee || is_guardian || can_view_records {
+      resp_stay_datas.push(fill_stay_data(con, x).await?);
+    }
   }
-  Ok(resp_encounters)
+
+  Ok(resp_stay_datas)
 }
 
-pub async fn session_view(
+pub async fn stay_data_history(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SessionViewProps,
-) -> Result<Vec<response::Session>, response::InnexgoHoursError> {
+  props: request::StayDataHistoryProps,
+) -> Result<Vec<response::Version<response::StayData>>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let sessions = session_service::query(con, props)
+
+  let stay = stay_service::get_by_stay_id(con, props.stay_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::StayNonexistent)?;
+
+  // attendees, their guardians, and staff who can view records at the location may view
+  let is_attendee = stay.attendee_user_id == user.user_id;
+
+  let is_guardian = guardianship_service::is_guardian(con, user.user_id, stay.attendee_user_id)
     .await
     .map_err(report_postgres_err)?;
 
-  // return sessions
-  let mut resp_sessions = vec![];
-  for x in sessions.into_iter() {
-    // members of the course can see sessions
-    let is_member = course_membership_service::is_member(con, user.user_id, x.course_id)
-      .await
-      .map_err(report_postgres_err)?;
+  let can_view_records = course_membership_service::has_capability_at(
+    con,
+    user.user_id,
+    stay.location_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
 
-    if is_member {
-      resp_sessions.push(fill_session(con, x).await?);
-    }
+  if !(is_attendee || is_guardian || can_view_records) {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  Ok(resp_sessions)
+  // every version of the stay
+  let stay_data = stay_data_service::get_history_by_stay_id(con, props.stay_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let mut resp_stay_datas = vec![];
+  for x in stay_data.into_iter() {
+    resp_stay_datas.push(fill_stay_data(con, x).await?);
+  }
+
+  fill_history(resp_stay_datas)
 }
 
-pub async fn session_data_view(
+pub async fn hall_pass_view(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::SessionDataViewProps,
-) -> Result<Vec<response::SessionData>, response::InnexgoHoursError> {
+  props: request::HallPassViewProps,
+) -> Result<Vec<response::HallPass>, response::InnexgoHoursError> {
   // validate api key
   let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let session_data = session_data_service::query(con, props)
+  // get hall passes
+  let hall_passes = hall_pass_service::query(con, props)
     .await
     .map_err(report_postgres_err)?;
 
-  // return session_datas
-  let mut resp_session_datas = vec![];
-  for x in session_data.into_iter() {
-    // members of the course can see sessions
-
-    let session = session_service::get_by_session_id(con, x.session_id)
-      .await
-      .map_err(report_postgres_err)?
-      .ok_or(response::InnexgoHoursError::SessionNonexistent)?;
-
-    let is_member = course_membership_service::is_member(con, user.user_id, session.course_id)
-      .await
-      .map_err(report_postgres_err)?;
-
-    resp_session_datas.push(fill_session_data(con, x).await?);
+  // return hall passes
+  let mut resp_hall_passes = vec![];
+  for x in hall_passes.into_iter() {
+    if can_view_hall_pass(con, user.user_id, &x).await? {
+      resp_hall_passes.push(fill_hall_pass(con, x).await?);
+    }
   }
 
-  Ok(resp_session_datas)
+  Ok(resp_hall_passes)
 }
 
-pub async fn stay_view(
+pub async fn occupancy_view_location(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::StayViewProps,
-) -> Result<Vec<response::Stay>, response::InnexgoHoursError> {
+  props: request::OccupancyViewLocationProps,
+) -> Result<Vec<response::Occupant>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let stays = stay_service::query(con, props)
+
+  let location = location_service::get_by_location_id(con, props.location_id)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::LocationNonexistent)?;
+
+  // staff who can view records at the location and school admins may view
+  let can_view_records = course_membership_service::has_capability_at(
+    con,
+    user.user_id,
+    location.location_id,
+    CourseCapability::ViewRecords,
+  )
+  .await
+  .map_err(report_postgres_err)?;
+
+  let is_admin = adminship_service::is_admin(con, user.user_id, location.school_id)
     .await
     .map_err(report_postgres_err)?;
 
-  // return stays
-  let mut resp_stays = vec![];
-  for x in stays.into_iter() {
-    // TODO: restrict
-    resp_stays.push(fill_stay(con, x).await?);
+  if !can_view_records && !is_admin {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
   }
 
-  Ok(resp_stays)
+  let time = utils::current_time_millis();
+
+  let occupants = occupancy_service::get_by_location_id(con, location.location_id, time)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let mut resp_occupants = vec![];
+  for x in occupants.into_iter() {
+    resp_occupants.push(fill_occupant(con, x, time).await?);
+  }
+
+  Ok(resp_occupants)
 }
 
-pub async fn stay_data_view(
+pub async fn occupancy_view_school(
   _config: Config,
   db: Db,
   auth_service: AuthService,
-  props: request::StayDataViewProps,
-) -> Result<Vec<response::StayData>, response::InnexgoHoursError> {
+  props: request::OccupancyViewSchoolProps,
+) -> Result<Vec<response::Occupant>, response::InnexgoHoursError> {
   // validate api key
-  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
   let con = &mut *db.lock().await;
-  // get users
-  let stay_data = stay_data_service::query(con, props)
+
+  let school = school_service::get_by_school_id(con, props.school_id)
     .await
-    .map_err(report_postgres_err)?;
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolNonexistent)?;
 
-  // return stay_datas
-  let mut resp_stay_datas = vec![];
-  for x in stay_data.into_iter() {
-    // TODO: restrict
+  // only admins may view the whole school
+  if !adminship_service::is_admin(con, user.user_id, school.school_id)
+    .await
+    .map_err(report_postgres_err)?
+  {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
 
-    resp_stay_datas.push(fill_stay_data(con, x).await?);
+  let time = utils::current_time_millis();
+
+  let occupants = occupancy_service::get_by_school_id(con, school.school_id, time)
+    .await
+    .map_err(report_postgres_err)?;
+
+  let mut resp_occupants = vec![];
+  for x in occupants.into_iter() {
+    resp_occupants.push(fill_occupant(con, x, time).await?);
   }
 
-  Ok(resp_stay_datas)
+  Ok(resp_occupants)
 }
 
 pub async fn session_request_view(
@@ -2734,13 +8998,7 @@ pub async fn session_request_view(
   // return session_requests
   let mut resp_session_requests = vec![];
   for x in session_request.into_iter() {
-    // attendees and instructors may view
-    let is_attendee = user.user_id == x.creator_user_id;
-    let is_instructor = course_membership_service::is_instructor(con, user.user_id, x.course_id)
-      .await
-      .map_err(report_postgres_err)?;
-
-    if is_attendee || is_instructor {
+    if can_view_session_request(con, user.user_id, &x).await? {
       resp_session_requests.push(fill_session_request(con, x).await?);
     }
   }
@@ -2766,20 +9024,7 @@ pub async fn session_request_response_view(
   // return session_request_responses
   let mut resp_session_request_responses = vec![];
   for x in session_request_response.into_iter() {
-    // attendees and instructors may view
-    let session_request =
-      session_request_service::get_by_session_request_id(con, x.session_request_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::InnexgoHoursError::SessionRequestNonexistent)?;
-
-    let is_attendee = user.user_id == session_request.creator_user_id;
-    let is_instructor =
-      course_membership_service::is_instructor(con, user.user_id, session_request.course_id)
-        .await
-        .map_err(report_postgres_err)?;
-
-    if is_attendee || is_instructor {
+    if can_view_session_request_response(con, user.user_id, &x).await? {
       resp_session_request_responses.push(fill_session_request_response(con, x).await?);
     }
   }
@@ -2855,6 +9100,76 @@ pub async fn school_key_data_view(
   Ok(resp_school_key_datas)
 }
 
+pub async fn school_key_data_history(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolKeyDataHistoryProps,
+) -> Result<Vec<response::Version<response::SchoolKeyData>>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+  let con = &mut *db.lock().await;
+
+  let school_key = school_key_service::get_by_school_key_key(con, &props.school_key_key)
+    .await
+    .map_err(report_postgres_err)?
+    .ok_or(response::InnexgoHoursError::SchoolKeyNonexistent)?;
+
+  // can view if admin
+  let is_admin = adminship_service::is_admin(con, user.user_id, school_key.school_id)
+    .await
+    .map_err(report_postgres_err)?;
+
+  if !is_admin {
+    return Err(response::InnexgoHoursError::ApiKeyUnauthorized);
+  }
+
+  // every version of the school key
+  let school_key_data =
+    school_key_data_service::get_history_by_school_key_key(con, &props.school_key_key)
+      .await
+      .map_err(report_postgres_err)?;
+
+  let mut resp_school_key_datas = vec![];
+  for x in school_key_data.into_iter() {
+    resp_school_key_datas.push(fill_school_key_data(con, x).await?);
+  }
+
+  fill_history(resp_school_key_datas)
+}
+
+pub async fn school_invitation_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::SchoolInvitationViewProps,
+) -> Result<Vec<response::SchoolInvitation>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get invitations
+  let school_invitations = school_invitation_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // return school_invitations
+  let mut resp_school_invitations = vec![];
+  for x in school_invitations.into_iter() {
+    // only admins may view school invitations
+    let is_admin = adminship_service::is_admin(con, user.user_id, x.school_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+    if is_admin {
+      resp_school_invitations.push(fill_school_invitation(con, x).await?);
+    }
+  }
+
+  Ok(resp_school_invitations)
+}
+
 pub async fn adminship_view(
   _config: Config,
   db: Db,
@@ -2886,3 +9201,120 @@ pub async fn adminship_view(
 
   Ok(resp_adminships)
 }
+
+pub async fn guardianship_view(
+  _config: Config,
+  db: Db,
+  auth_service: AuthService,
+  props: request::GuardianshipViewProps,
+) -> Result<Vec<response::Guardianship>, response::InnexgoHoursError> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let con = &mut *db.lock().await;
+  // get guardianships
+  let guardianships = guardianship_service::query(con, props)
+    .await
+    .map_err(report_postgres_err)?;
+
+  // return guardianships
+  let mut resp_guardianships = vec![];
+  for x in guardianships.into_iter() {
+    // the guardian, the student, and the student's admins may view
+    let is_party = user.user_id == x.guardian_user_id || user.user_id == x.student_user_id;
+
+    let is_admin = guardianship_service::is_admin_of_student(con, user.user_id, x.student_user_id)
+      .await
+      .map_err(report_postgres_err)?;
+
+    if is_party || is_admin {
+      resp_guardianships.push(fill_guardianship(con, x).await?);
+    }
+  }
+
+  Ok(resp_guardianships)
+}
+
+// how often a subscription checks that the api key it was opened with is still valid
+static EVENT_SUBSCRIBE_API_KEY_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// streams newly created encounters, session requests, responses and commitments as server sent events
+// a "resync" event means some events were missed, and the client should refetch what it shows
+pub async fn event_subscribe(
+  config: Config,
+  _db: Db,
+  auth_service: AuthService,
+  props: request::EventSubscribeProps,
+) -> Result<
+  impl Stream<Item = Result<warp::sse::Event, Infallible>> + Send,
+  response::InnexgoHoursError,
+> {
+  // validate api key
+  let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
+
+  let receiver = config.dispatched_events.subscribe();
+  let next_check = tokio::time::Instant::now() + EVENT_SUBSCRIBE_API_KEY_CHECK_INTERVAL;
+
+  let stream = futures_util::stream::unfold(
+    (receiver, next_check),
+    move |(mut receiver, mut next_check)| {
+      let auth_service = auth_service.clone();
+      let props = props.clone();
+      async move {
+        loop {
+          let dispatched = match tokio::time::timeout_at(next_check, receiver.recv()).await {
+            Ok(Ok(dispatched)) => dispatched,
+            // we fell behind and skipped events
+            Ok(Err(RecvError::Lagged(_))) => Arc::new(events::Dispatched::Resync),
+            Ok(Err(RecvError::Closed)) => return None,
+            // end the stream once the key has expired or been revoked
+            Err(_) => {
+              get_user_if_api_key_valid(&auth_service, props.api_key.clone())
+                .await
+                .ok()?;
+              next_check = tokio::time::Instant::now() + EVENT_SUBSCRIBE_API_KEY_CHECK_INTERVAL;
+              continue;
+            }
+          };
+
+          let sse_event = match &*dispatched {
+            events::Dispatched::Resync => warp::sse::Event::default().event("resync").data(""),
+            events::Dispatched::Visible {
+              school_id,
+              course_id,
+              location_id,
+              audience,
+              name,
+              data,
+            } => {
+              let wanted = audience.contains(&user.user_id)
+                && filter_admits(&props.school_id, Some(*school_id))
+                && filter_admits(&props.course_id, *course_id)
+                && filter_admits(&props.location_id, *location_id);
+
+              if !wanted {
+                continue;
+              }
+
+              match warp::sse::Event::default().event(*name).json_data(data) {
+                Ok(sse_event) => sse_event,
+                Err(e) => {
+                  utils::log(utils::Event {
+                    msg: "failed to push event".to_owned(),
+                    source: Some(e.to_string()),
+                    severity: utils::SeverityKind::Warning,
+                  });
+                  continue;
+                }
+              }
+            }
+          };
+
+          return Some((Ok(sse_event), (receiver, next_check)));
+        }
+      }
+    },
+  );
+
+  Ok(stream)
+}
@@ -0,0 +1,132 @@
+use super::db_types::*;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+// a homeroom is an active course marked as one, and its students are the ones it takes daily attendance for
+// a student is in at most one homeroom per school
+
+impl From<tokio_postgres::row::Row> for Homeroom {
+  fn from(row: tokio_postgres::row::Row) -> Homeroom {
+    Homeroom {
+      course_id: row.get("course_id"),
+      school_id: row.get("school_id"),
+      location_id: row.get("location_id"),
+      student_user_id: row.get("student_user_id"),
+    }
+  }
+}
+
+// the homerooms the user is a student in, one per school
+pub async fn get_by_student_user_id(
+  con: &mut impl GenericClient,
+  student_user_id: i64,
+) -> Result<Vec<Homeroom>, tokio_postgres::Error> {
+  let student = request::CourseMembershipKind::Student as i64;
+
+  let result = con
+    .query(
+      "
+      SELECT c.course_id, c.school_id, cd.location_id, cm.user_id student_user_id
+      FROM recent_course_membership_v cm
+      JOIN course_t c ON c.course_id = cm.course_id
+      JOIN recent_course_data_v cd ON cd.course_id = c.course_id
+      WHERE 1 = 1
+      AND cm.user_id = $1
+      AND cm.course_membership_kind = $2
+      AND cd.homeroom
+      AND cd.active
+      ORDER BY c.school_id
+      ",
+      &[&student_user_id, &student],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+// how many students would end up in two homerooms if the course were one
+// with student_user_id only that student is counted, otherwise every student of the course is
+pub async fn count_students_in_other_homerooms(
+  con: &mut impl GenericClient,
+  course_id: i64,
+  student_user_id: Option<i64>,
+) -> Result<i64, tokio_postgres::Error> {
+  let student = request::CourseMembershipKind::Student as i64;
+
+  let result = con
+    .query_one(
+      "
+      SELECT count(DISTINCT other_cm.user_id)
+      FROM course_t mine
+      JOIN course_t other ON other.school_id = mine.school_id
+      JOIN recent_course_data_v other_cd ON other_cd.course_id = other.course_id
+      JOIN recent_course_membership_v other_cm ON other_cm.course_id = other.course_id
+      WHERE 1 = 1
+      AND mine.course_id = $1
+      AND other.course_id <> mine.course_id
+      AND other_cd.homeroom
+      AND other_cd.active
+      AND other_cm.course_membership_kind = $3
+      AND ($2::bigint IS NULL OR other_cm.user_id = $2)
+      AND ($2::bigint IS NOT NULL OR other_cm.user_id IN (
+        SELECT cm.user_id FROM recent_course_membership_v cm
+        WHERE cm.course_id = $1
+        AND cm.course_membership_kind = $3
+      ))
+      ",
+      &[&course_id, &student_user_id, &student],
+    )
+    .await?
+    .get(0);
+
+  Ok(result)
+}
+
+// every homeroom student in the school, with the first time they were seen at their homeroom in [min_time, max_time)
+pub async fn get_attendance(
+  con: &mut impl GenericClient,
+  school_id: i64,
+  course_id: &Option<Vec<i64>>,
+  min_time: i64,
+  max_time: i64,
+) -> Result<Vec<HomeroomAttendance>, tokio_postgres::Error> {
+  let student = request::CourseMembershipKind::Student as i64;
+
+  let result = con
+    .query(
+      "
+      SELECT c.course_id, c.school_id, cd.location_id, cm.user_id student_user_id,
+        (
+          SELECT min(e.creation_time) FROM encounter_t e
+          WHERE 1 = 1
+          AND e.attendee_user_id = cm.user_id
+          AND e.location_id = cd.location_id
+          AND e.creation_time >= $3
+          AND e.creation_time < $4
+        ) arrival_time
+      FROM course_t c
+      JOIN recent_course_data_v cd ON cd.course_id = c.course_id
+      JOIN recent_course_membership_v cm ON cm.course_id = c.course_id
+      WHERE 1 = 1
+      AND c.school_id = $1
+      AND ($2::bigint[] IS NULL OR c.course_id = ANY($2))
+      AND cd.homeroom
+      AND cd.active
+      AND cm.course_membership_kind = $5
+      ORDER BY c.course_id, cm.user_id
+      ",
+      &[&school_id, course_id, &min_time, &max_time, &student],
+    )
+    .await?
+    .into_iter()
+    .map(|row| HomeroomAttendance {
+      arrival_time: row.get("arrival_time"),
+      homeroom: row.into(),
+    })
+    .collect();
+
+  Ok(result)
+}
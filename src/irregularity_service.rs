@@ -0,0 +1,146 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use std::convert::TryInto;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Irregularity {
+  // select * from irregularity_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Irregularity {
+    Irregularity {
+      irregularity_id: row.get("irregularity_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      commitment_id: row.get("commitment_id"),
+      irregularity_kind: (row.get::<_, i64>("irregularity_kind") as u8)
+        .try_into()
+        .unwrap(),
+      start_time: row.get("start_time"),
+      end_time: row.get("end_time"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  commitment_id: i64,
+  irregularity_kind: request::IrregularityKind,
+  start_time: Option<i64>,
+  end_time: Option<i64>,
+  active: bool,
+) -> Result<Irregularity, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let irregularity_id = con
+    .query_one(
+      "INSERT INTO
+       irregularity_t(
+           creation_time,
+           creator_user_id,
+           commitment_id,
+           irregularity_kind,
+           start_time,
+           end_time,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7)
+       RETURNING irregularity_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &commitment_id,
+        &(irregularity_kind.clone() as i64),
+        &start_time,
+        &end_time,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(Irregularity {
+    irregularity_id,
+    creation_time,
+    creator_user_id,
+    commitment_id,
+    irregularity_kind,
+    start_time,
+    end_time,
+    active,
+  })
+}
+
+pub async fn get_by_commitment_id(
+  con: &mut impl GenericClient,
+  commitment_id: i64,
+) -> Result<Option<Irregularity>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT i.* FROM recent_irregularity_v i
+      WHERE i.commitment_id = $1
+      ",
+      &[&commitment_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::IrregularityViewProps,
+) -> Result<Vec<Irregularity>, tokio_postgres::Error> {
+  let sql = [
+    if props.only_recent {
+      "SELECT i.* FROM recent_irregularity_v i"
+    } else {
+      "SELECT i.* FROM irregularity_t i"
+    },
+    " INNER JOIN commitment_t c ON i.commitment_id = c.commitment_id",
+    " INNER JOIN session_t ses ON c.session_id = ses.session_id",
+    " WHERE 1 = 1",
+    " AND ($1::bigint[]  IS NULL OR i.irregularity_id = ANY($1))",
+    " AND ($2::bigint    IS NULL OR i.creation_time >= $2)",
+    " AND ($3::bigint    IS NULL OR i.creation_time <= $3)",
+    " AND ($4::bigint[]  IS NULL OR i.creator_user_id = ANY($4))",
+    " AND ($5::bigint[]  IS NULL OR i.commitment_id = ANY($5))",
+    " AND ($6::bigint[]  IS NULL OR i.irregularity_kind = ANY($6))",
+    " AND ($7::bool      IS NULL OR i.active = $7)",
+    " AND ($8::bigint[]  IS NULL OR c.attendee_user_id = ANY($8))",
+    " AND ($9::bigint[]  IS NULL OR c.session_id = ANY($9))",
+    " AND ($10::bigint[] IS NULL OR ses.course_id = ANY($10))",
+    " ORDER BY i.irregularity_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.irregularity_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.commitment_id,
+        &props
+          .irregularity_kind
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.active,
+        &props.attendee_user_id,
+        &props.session_id,
+        &props.course_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
@@ -0,0 +1,89 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for LocationCheckin {
+  // select * from location_checkin_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> LocationCheckin {
+    LocationCheckin {
+      location_checkin_id: row.get("location_checkin_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      location_id: row.get("location_id"),
+      secret: row.get("secret"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  location_id: i64,
+  secret: String,
+  active: bool,
+) -> Result<LocationCheckin, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let location_checkin_id = con
+    .query_one(
+      "INSERT INTO
+       location_checkin_t(
+           creation_time,
+           creator_user_id,
+           location_id,
+           secret,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5)
+       RETURNING location_checkin_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &location_id,
+        &secret,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(LocationCheckin {
+    location_checkin_id,
+    creation_time,
+    creator_user_id,
+    location_id,
+    secret,
+    active,
+  })
+}
+
+pub async fn get_by_location_id(
+  con: &mut impl GenericClient,
+  location_id: i64,
+) -> Result<Option<LocationCheckin>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT lc.* FROM recent_location_checkin_v lc
+      WHERE lc.location_id = $1
+      ",
+      &[&location_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// the currently enabled self check in for a location, if any
+pub async fn get_active_by_location_id(
+  con: &mut impl GenericClient,
+  location_id: i64,
+) -> Result<Option<LocationCheckin>, tokio_postgres::Error> {
+  let result = get_by_location_id(con, location_id)
+    .await?
+    .filter(|x| x.active);
+
+  Ok(result)
+}
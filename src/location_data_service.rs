@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use std::convert::From;
 use tokio_postgres::GenericClient;
@@ -103,6 +104,27 @@ pub async fn get_by_location_id(
   Ok(result)
 }
 
+// every version of the location, oldest first
+pub async fn get_history_by_location_id(
+  con: &mut impl GenericClient,
+  location_id: i64,
+) -> Result<Vec<LocationData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT ld.* FROM location_data_t ld
+      WHERE ld.location_id = $1
+      ORDER BY ld.location_data_id
+      ",
+      &[&location_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 pub async fn is_active_by_location_id(
   con: &mut impl GenericClient,
   location_id: i64,
@@ -120,11 +142,15 @@ pub async fn query(
   props: innexgo_hours_api::request::LocationDataViewProps,
 ) -> Result<Vec<LocationData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT lod.* FROM recent_location_data_v lod"
-    } else {
-      "SELECT lod.* FROM location_data_t lod"
-    },
+    utils::select_versions(
+      "location_data_t",
+      "lod",
+      "location_data_id",
+      "location_id",
+      props.only_recent,
+      13,
+    )
+    .as_str(),
     " JOIN location_t lo ON lod.location_id = lo.location_id",
     " WHERE 1 = 1",
     " AND ($1::bigint[]  IS NULL OR lod.location_data_id = ANY($1))",
@@ -139,6 +165,7 @@ pub async fn query(
     " AND ($10::text[]   IS NULL OR lod.phone = ANY($10))",
     " AND ($11::bool     IS NULL OR lod.active = $11)",
     " AND ($12::bigint[] IS NULL OR lo.school_id = ANY($12))",
+    " AND ($13::bigint  IS NULL OR lod.creation_time <= $13)",
     " ORDER BY lod.location_data_id",
   ]
   .join("\n");
@@ -161,6 +188,7 @@ pub async fn query(
         &props.phone,
         &props.active,
         &props.school_id,
+        &props.as_of,
       ],
     )
     .await?
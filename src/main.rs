@@ -7,41 +7,81 @@ use std::sync::Arc;
 use tokio_postgres::{Client, NoTls};
 use warp::Filter;
 
+use tokio::sync::broadcast;
 use tokio::sync::Mutex;
 
 mod utils;
 
-use auth_service_api::client::AuthService;
+use mail_service_api::client::MailService;
 
 // web stuff
 mod api;
+mod auth;
+mod blob_store;
 mod db_types;
+mod event_dispatcher;
+mod events;
 mod handlers;
 
 // db
 mod adminship_service;
+mod attachment_service;
+mod availability_booking_service;
+mod availability_data_service;
+mod availability_service;
+mod checkin_attempt_service;
+mod closure_data_service;
+mod closure_service;
 mod commitment_service;
+mod conflict_service;
 mod course_data_service;
+mod course_invitation_service;
 mod course_key_data_service;
 mod course_key_service;
 mod course_membership_service;
 mod course_service;
 mod encounter_service;
+mod feedback_data_service;
+mod feedback_service;
+mod free_time_service;
+mod guardianship_service;
+mod guardianship_summary;
+mod guardianship_summary_run_service;
+mod hall_pass_check_service;
+mod hall_pass_monitor;
+mod hall_pass_service;
+mod homeroom_service;
+mod irregularity_service;
 mod location_service;
 mod location_data_service;
+mod location_checkin_service;
+mod meeting_join_service;
+mod meeting_link_service;
+mod meeting_pass_service;
+mod occupancy_service;
 mod school_data_service;
 mod school_duration_data_service;
 mod school_duration_service;
+mod school_invitation_service;
 mod school_key_data_service;
 mod school_key_service;
 mod school_service;
+mod session_change_service;
 mod session_data_service;
+mod session_note_data_service;
+mod session_note_service;
 mod session_request_response_service;
 mod session_request_service;
 mod session_service;
 mod stay_service;
 mod stay_data_service;
 mod subscription_service;
+mod term_data_service;
+mod term_service;
+
+// conformance tests against a disposable postgres
+#[cfg(test)]
+mod tests;
 
 static SERVICE_NAME: &str = "innexgo-hours-service";
 
@@ -51,15 +91,27 @@ struct Opts {
   site_external_url: String,
   #[clap(short, long)]
   database_url: String,
+  #[clap(short, long, required_unless_present = "static_auth_file")]
+  auth_service_url: Option<String>,
+  // answer auth requests from this file instead of an auth service, for local development
+  #[clap(long)]
+  static_auth_file: Option<String>,
   #[clap(short, long)]
-  auth_service_url: String,
+  mail_service_url: String,
   #[clap(short, long)]
   port: u16,
+  // where attachments are stored
+  #[clap(long, default_value = "attachments")]
+  attachment_dir: String,
 }
 
 #[derive(Clone)]
 pub struct Config {
   pub site_external_url: String,
+  pub mail_service: MailService,
+  pub events: broadcast::Sender<events::Event>,
+  pub dispatched_events: broadcast::Sender<Arc<events::Dispatched>>,
+  pub blob_store: blob_store::BlobStore,
 }
 
 pub type Db = Arc<Mutex<Client>>;
@@ -70,7 +122,10 @@ async fn main() {
     database_url,
     site_external_url,
     auth_service_url,
+    static_auth_file,
+    mail_service_url,
     port,
+    attachment_dir,
   } = Opts::parse();
 
   let (client, connection) = loop {
@@ -101,8 +156,17 @@ async fn main() {
 
   let db: Db = Arc::new(Mutex::new(client));
 
-  // open connection to auth service
-  let auth_service = AuthService::new(&auth_service_url).await;
+  // open connection to auth service, unless we were given a file of users to use instead
+  let auth_service = match (static_auth_file, auth_service_url) {
+    (Some(static_auth_file), _) => {
+      auth::AuthService::from_file(&static_auth_file).expect("could not load static auth file")
+    }
+    (None, Some(auth_service_url)) => auth::AuthService::new(&auth_service_url).await,
+    (None, None) => unreachable!("clap requires one of them"),
+  };
+
+  // open connection to mail service
+  let mail_service = MailService::new(&mail_service_url).await;
 
   let log = warp::log::custom(|info| {
     // Use a log macro, or slog, or println, or whatever!
@@ -113,7 +177,28 @@ async fn main() {
     });
   });
 
-  let api = api::api(Config { site_external_url }, db, auth_service);
+  let config = Config {
+    site_external_url,
+    mail_service,
+    events: events::channel(),
+    dispatched_events: events::dispatched_channel(),
+    blob_store: blob_store::BlobStore::new_local(&attachment_dir),
+  };
+
+  // periodically email guardians who asked for a summary
+  tokio::spawn(guardianship_summary::run(
+    config.clone(),
+    db.clone(),
+    auth_service.clone(),
+  ));
+
+  // resolve events for live subscribers
+  event_dispatcher::spawn(config.clone(), db.clone());
+
+  // alert dashboards about students who haven't come back
+  tokio::spawn(hall_pass_monitor::run(config.clone(), db.clone()));
+
+  let api = api::api(config, db, auth_service);
 
   warp::serve(api.with(log)).run(([0, 0, 0, 0], port)).await;
 }
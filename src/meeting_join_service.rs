@@ -0,0 +1,79 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for MeetingJoin {
+  // select * from meeting_join_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> MeetingJoin {
+    MeetingJoin {
+      meeting_pass_id: row.get("meeting_pass_id"),
+      creation_time: row.get("creation_time"),
+    }
+  }
+}
+
+// none if the pass was already used, each pass can only be followed once
+pub async fn add(
+  con: &mut impl GenericClient,
+  meeting_pass_id: i64,
+) -> Result<Option<MeetingJoin>, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let result = con
+    .query_opt(
+      "INSERT INTO
+       meeting_join_t(
+           meeting_pass_id,
+           creation_time
+       )
+       VALUES($1, $2)
+       ON CONFLICT DO NOTHING
+       RETURNING meeting_pass_id
+      ",
+      &[&meeting_pass_id, &creation_time],
+    )
+    .await?
+    .map(|_| MeetingJoin {
+      meeting_pass_id,
+      creation_time,
+    });
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::MeetingJoinViewProps,
+) -> Result<Vec<MeetingJoin>, tokio_postgres::Error> {
+  let results = con
+    .query(
+      "
+      SELECT mj.* FROM meeting_join_t mj
+      INNER JOIN meeting_pass_t mp ON mj.meeting_pass_id = mp.meeting_pass_id
+      INNER JOIN session_t ses ON mp.session_id = ses.session_id
+      WHERE 1 = 1
+      AND ($1::bigint[] IS NULL OR mj.meeting_pass_id = ANY($1))
+      AND ($2::bigint   IS NULL OR mj.creation_time >= $2)
+      AND ($3::bigint   IS NULL OR mj.creation_time <= $3)
+      AND ($4::bigint[] IS NULL OR mp.creator_user_id = ANY($4))
+      AND ($5::bigint[] IS NULL OR mp.session_id = ANY($5))
+      AND ($6::bigint[] IS NULL OR ses.course_id = ANY($6))
+      ORDER BY mj.meeting_pass_id
+      ",
+      &[
+        &props.meeting_pass_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.attendee_user_id,
+        &props.session_id,
+        &props.course_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
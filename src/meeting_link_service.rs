@@ -0,0 +1,155 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for MeetingLink {
+  // select * from meeting_link_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> MeetingLink {
+    MeetingLink {
+      meeting_link_id: row.get("meeting_link_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      location_id: row.get("location_id"),
+      session_id: row.get("session_id"),
+      url: row.get("url"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  location_id: Option<i64>,
+  session_id: Option<i64>,
+  url: String,
+  active: bool,
+) -> Result<MeetingLink, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let meeting_link_id = con
+    .query_one(
+      "INSERT INTO
+       meeting_link_t(
+           creation_time,
+           creator_user_id,
+           location_id,
+           session_id,
+           url,
+           active
+       )
+       VALUES($1, $2, $3, $4, $5, $6)
+       RETURNING meeting_link_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &location_id,
+        &session_id,
+        &url,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(MeetingLink {
+    meeting_link_id,
+    creation_time,
+    creator_user_id,
+    location_id,
+    session_id,
+    url,
+    active,
+  })
+}
+
+pub async fn get_by_meeting_link_id(
+  con: &mut impl GenericClient,
+  meeting_link_id: i64,
+) -> Result<Option<MeetingLink>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM meeting_link_t WHERE meeting_link_id=$1",
+      &[&meeting_link_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// the link to use for a session: its own if it has one, otherwise the one for its course's location
+pub async fn get_active_for_session_id(
+  con: &mut impl GenericClient,
+  session_id: i64,
+) -> Result<Option<MeetingLink>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT ml.* FROM recent_meeting_link_v ml
+      WHERE ml.active
+      AND (
+        ml.session_id = $1
+        OR ml.location_id = (
+          SELECT cd.location_id FROM session_t ses
+          JOIN recent_course_data_v cd ON cd.course_id = ses.course_id
+          WHERE ses.session_id = $1
+        )
+      )
+      ORDER BY ml.session_id IS NULL
+      LIMIT 1
+      ",
+      &[&session_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::MeetingLinkViewProps,
+) -> Result<Vec<MeetingLink>, tokio_postgres::Error> {
+  let sql = [
+    if props.only_recent {
+      "SELECT ml.* FROM recent_meeting_link_v ml"
+    } else {
+      "SELECT ml.* FROM meeting_link_t ml"
+    },
+    " WHERE 1 = 1",
+    " AND ($1::bigint[] IS NULL OR ml.meeting_link_id = ANY($1))",
+    " AND ($2::bigint   IS NULL OR ml.creation_time >= $2)",
+    " AND ($3::bigint   IS NULL OR ml.creation_time <= $3)",
+    " AND ($4::bigint[] IS NULL OR ml.creator_user_id = ANY($4))",
+    " AND ($5::bigint[] IS NULL OR ml.location_id = ANY($5))",
+    " AND ($6::bigint[] IS NULL OR ml.session_id = ANY($6))",
+    " AND ($7::bool     IS NULL OR ml.active = $7)",
+    " ORDER BY ml.meeting_link_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.meeting_link_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.location_id,
+        &props.session_id,
+        &props.active,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
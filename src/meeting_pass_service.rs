@@ -0,0 +1,95 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for MeetingPass {
+  // select * from meeting_pass_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> MeetingPass {
+    MeetingPass {
+      meeting_pass_id: row.get("meeting_pass_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      session_id: row.get("session_id"),
+      meeting_link_id: row.get("meeting_link_id"),
+      meeting_pass_token: row.get("meeting_pass_token"),
+      expiration_time: row.get("expiration_time"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  session_id: i64,
+  meeting_link_id: i64,
+  meeting_pass_token: String,
+  expiration_time: i64,
+) -> Result<MeetingPass, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let meeting_pass_id = con
+    .query_one(
+      "INSERT INTO
+       meeting_pass_t(
+           creation_time,
+           creator_user_id,
+           session_id,
+           meeting_link_id,
+           meeting_pass_token,
+           expiration_time
+       )
+       VALUES($1, $2, $3, $4, $5, $6)
+       RETURNING meeting_pass_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &session_id,
+        &meeting_link_id,
+        &meeting_pass_token,
+        &expiration_time,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(MeetingPass {
+    meeting_pass_id,
+    creation_time,
+    creator_user_id,
+    session_id,
+    meeting_link_id,
+    meeting_pass_token,
+    expiration_time,
+  })
+}
+
+pub async fn get_by_meeting_pass_id(
+  con: &mut impl GenericClient,
+  meeting_pass_id: i64,
+) -> Result<Option<MeetingPass>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM meeting_pass_t WHERE meeting_pass_id=$1",
+      &[&meeting_pass_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn get_by_meeting_pass_token(
+  con: &mut impl GenericClient,
+  meeting_pass_token: &str,
+) -> Result<Option<MeetingPass>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM meeting_pass_t WHERE meeting_pass_token=$1",
+      &[&meeting_pass_token],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
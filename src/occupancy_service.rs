@@ -0,0 +1,158 @@
+use super::db_types::*;
+use either::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use tokio_postgres::GenericClient;
+
+// sign ins older than this that were never matched into a stay are assumed to be stale
+static UNMATCHED_ENCOUNTER_WINDOW: i64 = 24 * 60 * 60 * 1000;
+
+// stays at the given locations that have started but not yet ended at time
+async fn get_open_stays(
+  con: &mut impl GenericClient,
+  location_ids: &[i64],
+  time: i64,
+) -> Result<Vec<Occupant>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT
+        s.stay_id,
+        s.attendee_user_id,
+        s.location_id,
+        COALESCE(fe.creation_time, syd.fst_time) arrival_time
+      FROM stay_t s
+      JOIN recent_stay_data_v syd ON syd.stay_id = s.stay_id
+      LEFT JOIN encounter_t fe ON fe.encounter_id = syd.fst_encounter_id
+      LEFT JOIN encounter_t se ON se.encounter_id = syd.snd_encounter_id
+      WHERE 1 = 1
+      AND s.location_id = ANY($1)
+      AND syd.active
+      AND COALESCE(fe.creation_time, syd.fst_time) <= $2
+      AND COALESCE(se.creation_time, syd.snd_time) > $2
+      ORDER BY s.stay_id
+      ",
+      &[&location_ids, &time],
+    )
+    .await?
+    .into_iter()
+    .map(|row| Occupant {
+      attendee_user_id: row.get("attendee_user_id"),
+      location_id: row.get("location_id"),
+      arrival_time: row.get("arrival_time"),
+      source: Left(row.get("stay_id")),
+    })
+    .collect();
+
+  Ok(result)
+}
+
+// encounters at the given locations that no active stay uses as an endpoint
+// sign ins recorded by an attendance sheet belong to the sheet, even once it's been changed
+async fn get_unmatched_encounters(
+  con: &mut impl GenericClient,
+  location_ids: &[i64],
+  min_time: i64,
+  max_time: i64,
+) -> Result<Vec<Encounter>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT e.* FROM encounter_t e
+      WHERE 1 = 1
+      AND e.location_id = ANY($1)
+      AND e.creation_time >= $2
+      AND e.creation_time <= $3
+      AND NOT EXISTS (
+        SELECT 1 FROM recent_stay_data_v syd
+        WHERE syd.active
+        AND (syd.fst_encounter_id = e.encounter_id OR syd.snd_encounter_id = e.encounter_id)
+      )
+      AND NOT EXISTS (
+        SELECT 1 FROM stay_data_t syd
+        JOIN stay_t s ON s.stay_id = syd.stay_id
+        WHERE s.session_id IS NOT NULL
+        AND syd.fst_encounter_id = e.encounter_id
+      )
+      ORDER BY e.creation_time
+      ",
+      &[&location_ids, &min_time, &max_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
+pub async fn get_by_location_ids(
+  con: &mut impl GenericClient,
+  location_ids: &[i64],
+  time: i64,
+) -> Result<Vec<Occupant>, tokio_postgres::Error> {
+  let mut occupants = get_open_stays(con, location_ids, time).await?;
+
+  // people already covered by an open stay don't need to be inferred from encounters
+  let present: HashSet<(i64, i64)> = occupants
+    .iter()
+    .map(|x| (x.attendee_user_id, x.location_id))
+    .collect();
+
+  // unmatched encounters alternate between sign in and sign out,
+  // so an odd number of them means the attendee is still here
+  let mut unmatched: HashMap<(i64, i64), Vec<Encounter>> = HashMap::new();
+  for encounter in
+    get_unmatched_encounters(con, location_ids, time - UNMATCHED_ENCOUNTER_WINDOW, time).await?
+  {
+    unmatched
+      .entry((encounter.attendee_user_id, encounter.location_id))
+      .or_default()
+      .push(encounter);
+  }
+
+  for (key, encounters) in unmatched {
+    if present.contains(&key) || encounters.len() % 2 == 0 {
+      continue;
+    }
+    // encounters are sorted by time, so the last one is the most recent sign in
+    if let Some(sign_in) = encounters.last() {
+      occupants.push(Occupant {
+        attendee_user_id: sign_in.attendee_user_id,
+        location_id: sign_in.location_id,
+        arrival_time: sign_in.creation_time,
+        source: Right(sign_in.encounter_id),
+      });
+    }
+  }
+
+  occupants.sort_by_key(|x| x.arrival_time);
+
+  Ok(occupants)
+}
+
+pub async fn get_by_location_id(
+  con: &mut impl GenericClient,
+  location_id: i64,
+  time: i64,
+) -> Result<Vec<Occupant>, tokio_postgres::Error> {
+  get_by_location_ids(con, &[location_id], time).await
+}
+
+pub async fn get_by_school_id(
+  con: &mut impl GenericClient,
+  school_id: i64,
+  time: i64,
+) -> Result<Vec<Occupant>, tokio_postgres::Error> {
+  let location_ids: Vec<i64> = con
+    .query(
+      "SELECT l.location_id FROM location_t l WHERE l.school_id = $1",
+      &[&school_id],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.get(0))
+    .collect();
+
+  get_by_location_ids(con, &location_ids, time).await
+}
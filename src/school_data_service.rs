@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use std::convert::From;
 use tokio_postgres::GenericClient;
@@ -98,6 +99,27 @@ pub async fn get_by_school_id(
   Ok(result)
 }
 
+// every version of the school, oldest first
+pub async fn get_history_by_school_id(
+  con: &mut impl GenericClient,
+  school_id: i64,
+) -> Result<Vec<SchoolData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT sd.* FROM school_data_t sd
+      WHERE sd.school_id = $1
+      ORDER BY sd.school_data_id
+      ",
+      &[&school_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 pub async fn is_active_by_school_id(
   con: &mut impl GenericClient,
   school_id: i64,
@@ -115,11 +137,15 @@ pub async fn query(
   props: innexgo_hours_api::request::SchoolDataViewProps,
 ) -> Result<Vec<SchoolData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT sd.* FROM recent_school_data_v sd"
-    } else {
-      "SELECT sd.* FROM school_data_t sd"
-    },
+    utils::select_versions(
+      "school_data_t",
+      "sd",
+      "school_data_id",
+      "school_id",
+      props.only_recent,
+      11,
+    )
+    .as_str(),
     " WHERE 1 = 1",
     " AND ($1::bigint[] IS NULL OR sd.school_data_id = ANY($1))",
     " AND ($2::bigint   IS NULL OR sd.creation_time >= $2)",
@@ -131,6 +157,7 @@ pub async fn query(
     " AND ($8::text[]   IS NULL OR sd.description = ANY($8))",
     " AND ($9::text     IS NULL OR sd.description LIKE CONCAT('%',$9,'%'))",
     " AND ($10::bool    IS NULL OR sd.active = $10)",
+    " AND ($11::bigint  IS NULL OR sd.creation_time <= $11)",
     " ORDER BY sd.school_data_id",
   ]
   .join("\n");
@@ -151,6 +178,7 @@ pub async fn query(
         &props.description,
         &props.partial_description,
         &props.active,
+        &props.as_of,
       ],
     )
     .await?
@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use std::convert::From;
 use tokio_postgres::GenericClient;
@@ -103,17 +104,66 @@ pub async fn get_by_school_duration_id(
   Ok(result)
 }
 
+// every version of the school duration, oldest first
+pub async fn get_history_by_school_duration_id(
+  con: &mut impl GenericClient,
+  school_duration_id: i64,
+) -> Result<Vec<SchoolDurationData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT sdd.* FROM school_duration_data_t sdd
+      WHERE sdd.school_duration_id = $1
+      ORDER BY sdd.school_duration_data_id
+      ",
+      &[&school_duration_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// the active recurring windows of the school
+pub async fn get_active_by_school_id(
+  con: &mut impl GenericClient,
+  school_id: i64,
+) -> Result<Vec<SchoolDurationData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT sdd.* FROM recent_school_duration_data_v sdd
+      JOIN school_duration_t sd ON sdd.school_duration_id = sd.school_duration_id
+      WHERE 1 = 1
+      AND sd.school_id = $1
+      AND sdd.active
+      ORDER BY sdd.day, sdd.minute_start
+      ",
+      &[&school_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 
 pub async fn query(
   con: &mut impl GenericClient,
   props: innexgo_hours_api::request::SchoolDurationDataViewProps,
 ) -> Result<Vec<SchoolDurationData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT sdd.* FROM recent_school_duration_data_v sdd"
-    } else {
-      "SELECT sdd.* FROM school_duration_data sdd"
-    },
+    utils::select_versions(
+      "school_duration_data_t",
+      "sdd",
+      "school_duration_data_id",
+      "school_duration_id",
+      props.only_recent,
+      13,
+    )
+    .as_str(),
     " JOIN school_duration_t sd ON sdd.school_duration_id = sd.school_duration_id",
     " WHERE 1 = 1",
     " AND ($1::bigint[]  IS NULL OR sdd.school_duration_data_id = ANY($1))",
@@ -128,6 +178,7 @@ pub async fn query(
     " AND ($10::bigint   IS NULL OR sdd.minute_end <= $10)",
     " AND ($11::bool     IS NULL OR sdd.active = $11)",
     " AND ($12::bigint[] IS NULL OR sd.school_id = ANY($12))",
+    " AND ($13::bigint  IS NULL OR sdd.creation_time <= $13)",
     " ORDER BY sdd.school_duration_data_id",
   ]
   .join("\n");
@@ -150,6 +201,7 @@ pub async fn query(
         &props.max_minute_end,
         &props.active,
         &props.school_id,
+        &props.as_of,
       ],
     )
     .await?
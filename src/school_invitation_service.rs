@@ -0,0 +1,113 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for SchoolInvitation {
+  // select * from school_invitation_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> SchoolInvitation {
+    SchoolInvitation {
+      school_invitation_id: row.get("school_invitation_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      school_id: row.get("school_id"),
+      email: row.get("email"),
+      end_time: row.get("end_time"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  school_id: i64,
+  email: String,
+  end_time: i64,
+) -> Result<SchoolInvitation, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let school_invitation_id = con
+    .query_one(
+      "INSERT INTO
+       school_invitation_t(
+           creation_time,
+           creator_user_id,
+           school_id,
+           email,
+           end_time
+       )
+       VALUES($1, $2, $3, $4, $5)
+       RETURNING school_invitation_id
+      ",
+      &[&creation_time, &creator_user_id, &school_id, &email, &end_time],
+    )
+    .await?
+    .get(0);
+
+  // return school_invitation
+  Ok(SchoolInvitation {
+    school_invitation_id,
+    creation_time,
+    creator_user_id,
+    school_id,
+    email,
+    end_time,
+  })
+}
+
+pub async fn get_by_school_invitation_id(
+  con: &mut impl GenericClient,
+  school_invitation_id: i64,
+) -> Result<Option<SchoolInvitation>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM school_invitation_t WHERE school_invitation_id=$1",
+      &[&school_invitation_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::SchoolInvitationViewProps,
+) -> Result<Vec<SchoolInvitation>, tokio_postgres::Error> {
+  let sql = "
+    SELECT si.* FROM school_invitation_t si
+    WHERE 1 = 1
+    AND ($1::bigint[] IS NULL OR si.school_invitation_id = ANY($1))
+    AND ($2::bigint   IS NULL OR si.creation_time >= $2)
+    AND ($3::bigint   IS NULL OR si.creation_time <= $3)
+    AND ($4::bigint[] IS NULL OR si.creator_user_id = ANY($4))
+    AND ($5::bigint[] IS NULL OR si.school_id = ANY($5))
+    AND ($6::text[]   IS NULL OR si.email = ANY($6))
+    AND ($7::bigint   IS NULL OR si.end_time >= $7)
+    AND ($8::bigint   IS NULL OR si.end_time <= $8)
+    ORDER BY si.school_invitation_id
+  ";
+
+  let stmnt = con.prepare(sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.school_invitation_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.school_id,
+        &props.email,
+        &props.min_end_time,
+        &props.max_end_time,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(results)
+}
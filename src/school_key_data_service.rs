@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use tokio_postgres::GenericClient;
 
@@ -66,16 +67,70 @@ pub async fn get_by_school_key_data_id(
   Ok(result)
 }
 
+pub async fn get_by_school_key_key(
+  con: &mut impl GenericClient,
+  school_key_key: &str,
+) -> Result<Option<SchoolKeyData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT skd.* FROM recent_school_key_data_v skd
+      WHERE skd.school_key_key = $1
+      ",
+      &[&school_key_key],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the school key, oldest first
+pub async fn get_history_by_school_key_key(
+  con: &mut impl GenericClient,
+  school_key_key: &str,
+) -> Result<Vec<SchoolKeyData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT skd.* FROM school_key_data_t skd
+      WHERE skd.school_key_key = $1
+      ORDER BY skd.school_key_data_id
+      ",
+      &[&school_key_key],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+pub async fn is_active_by_school_key_key(
+  con: &mut impl GenericClient,
+  school_key_key: &str,
+) -> Result<bool, tokio_postgres::Error> {
+  let result = matches!(
+    get_by_school_key_key(con, school_key_key).await?,
+    Some(SchoolKeyData { active: true, .. })
+  );
+
+  Ok(result)
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: innexgo_hours_api::request::SchoolKeyDataViewProps,
 ) -> Result<Vec<SchoolKeyData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT skd.* FROM recent_school_key_data_v skd"
-    } else {
-      "SELECT skd.* FROM school_key_data_t skd"
-    },
+    utils::select_versions(
+      "school_key_data_t",
+      "skd",
+      "school_key_data_id",
+      "school_key_key",
+      props.only_recent,
+      12,
+    )
+    .as_str(),
     " JOIN school_key_t sk ON skd.school_key_key = sk.school_key_key",
     " WHERE 1 = 1",
     " AND ($1::bigint[] IS NULL OR skd.school_key_data_id = ANY($1))",
@@ -89,6 +144,7 @@ pub async fn query(
     " AND ($9::bigint   IS NULL OR sk.start_time <= $9)",
     " AND ($10::bigint  IS NULL OR sk.end_time >= $10)",
     " AND ($11::bigint  IS NULL OR sk.end_time <= $11)",
+    " AND ($12::bigint  IS NULL OR skd.creation_time <= $12)",
     " ORDER BY skd.school_key_data_id",
   ]
   .join("\n");
@@ -110,6 +166,7 @@ pub async fn query(
         &props.max_start_time,
         &props.min_end_time,
         &props.max_end_time,
+        &props.as_of,
       ],
     )
     .await?
@@ -1,5 +1,6 @@
 use super::db_types::*;
 use super::utils::current_time_millis;
+use super::utils::normalize_short_code;
 use innexgo_hours_api::request;
 use tokio_postgres::GenericClient;
 
@@ -13,6 +14,7 @@ impl From<tokio_postgres::row::Row> for SchoolKey {
       school_id: row.get("school_id"),
       start_time: row.get("start_time"),
       end_time: row.get("end_time"),
+      short_code: row.get("short_code"),
     }
   }
 }
@@ -24,6 +26,7 @@ pub async fn add(
   school_id: i64,
   start_time: i64,
   end_time: i64,
+  short_code: Option<String>,
 ) -> Result<SchoolKey, tokio_postgres::Error> {
   let creation_time = current_time_millis();
 
@@ -36,9 +39,10 @@ pub async fn add(
            creator_user_id,
            school_id,
            start_time,
-           end_time
+           end_time,
+           short_code
        )
-       VALUES($1, $2, $3, $4, $5, $6)
+       VALUES($1, $2, $3, $4, $5, $6, $7)
        RETURNING school_key_key
       ",
       &[
@@ -48,6 +52,7 @@ pub async fn add(
         &school_id,
         &start_time,
         &end_time,
+        &short_code,
       ],
     )
     .await?
@@ -60,7 +65,8 @@ pub async fn add(
     creator_user_id,
     school_id,
     start_time,
-    end_time
+    end_time,
+    short_code,
   })
 }
 
@@ -79,6 +85,32 @@ pub async fn get_by_school_key_key(
   Ok(result)
 }
 
+pub async fn get_by_short_code(
+  con: &mut impl GenericClient,
+  short_code: &str,
+) -> Result<Option<SchoolKey>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM school_key_t WHERE short_code=$1",
+      &[&short_code],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+// keys may be redeemed either by their full key or by their short code
+pub async fn get_by_key_or_short_code(
+  con: &mut impl GenericClient,
+  key: &str,
+) -> Result<Option<SchoolKey>, tokio_postgres::Error> {
+  match get_by_school_key_key(con, key).await? {
+    Some(x) => Ok(Some(x)),
+    None => get_by_short_code(con, &normalize_short_code(key)).await,
+  }
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: request::SchoolKeyViewProps,
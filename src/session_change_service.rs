@@ -0,0 +1,142 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use std::convert::TryInto;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for SessionChange {
+  // select * from session_change_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> SessionChange {
+    SessionChange {
+      session_change_id: row.get("session_change_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      session_id: row.get("session_id"),
+      session_change_kind: (row.get::<_, i64>("session_change_kind") as u8)
+        .try_into()
+        .unwrap(),
+      reason: row.get("reason"),
+      old_start_time: row.get("old_start_time"),
+      old_end_time: row.get("old_end_time"),
+      new_start_time: row.get("new_start_time"),
+      new_end_time: row.get("new_end_time"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  session_id: i64,
+  session_change_kind: request::SessionChangeKind,
+  reason: String,
+  old_start_time: i64,
+  old_end_time: i64,
+  new_start_time: Option<i64>,
+  new_end_time: Option<i64>,
+) -> Result<SessionChange, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let session_change_id = con
+    .query_one(
+      "INSERT INTO
+       session_change_t(
+           creation_time,
+           creator_user_id,
+           session_id,
+           session_change_kind,
+           reason,
+           old_start_time,
+           old_end_time,
+           new_start_time,
+           new_end_time
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+       RETURNING session_change_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &session_id,
+        &(session_change_kind.clone() as i64),
+        &reason,
+        &old_start_time,
+        &old_end_time,
+        &new_start_time,
+        &new_end_time,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(SessionChange {
+    session_change_id,
+    creation_time,
+    creator_user_id,
+    session_id,
+    session_change_kind,
+    reason,
+    old_start_time,
+    old_end_time,
+    new_start_time,
+    new_end_time,
+  })
+}
+
+pub async fn get_by_session_change_id(
+  con: &mut impl GenericClient,
+  session_change_id: i64,
+) -> Result<Option<SessionChange>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM session_change_t WHERE session_change_id=$1",
+      &[&session_change_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::SessionChangeViewProps,
+) -> Result<Vec<SessionChange>, tokio_postgres::Error> {
+  let results = con
+    .query(
+      "
+      SELECT sc.* FROM session_change_t sc
+      INNER JOIN session_t ses ON sc.session_id = ses.session_id
+      WHERE 1 = 1
+      AND ($1::bigint[] IS NULL OR sc.session_change_id = ANY($1))
+      AND ($2::bigint   IS NULL OR sc.creation_time >= $2)
+      AND ($3::bigint   IS NULL OR sc.creation_time <= $3)
+      AND ($4::bigint[] IS NULL OR sc.creator_user_id = ANY($4))
+      AND ($5::bigint[] IS NULL OR sc.session_id = ANY($5))
+      AND ($6::bigint[] IS NULL OR sc.session_change_kind = ANY($6))
+      AND ($7::bigint[] IS NULL OR ses.course_id = ANY($7))
+      AND ($8::bigint[] IS NULL OR sc.session_id IN (
+        SELECT c.session_id FROM commitment_t c WHERE c.attendee_user_id = ANY($8)
+      ))
+      ORDER BY sc.session_change_id
+      ",
+      &[
+        &props.session_change_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.session_id,
+        &props
+          .session_change_kind
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.course_id,
+        &props.attendee_user_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(results)
+}
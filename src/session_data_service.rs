@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use std::convert::From;
 use tokio_postgres::GenericClient;
@@ -87,17 +88,88 @@ pub async fn get_by_session_data_id(
   Ok(result)
 }
 
+pub async fn get_by_session_id(
+  con: &mut impl GenericClient,
+  session_id: i64,
+) -> Result<Option<SessionData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT sd.* FROM recent_session_data_v sd
+      WHERE sd.session_id = $1
+      ",
+      &[&session_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the session, oldest first
+pub async fn get_history_by_session_id(
+  con: &mut impl GenericClient,
+  session_id: i64,
+) -> Result<Vec<SessionData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT sd.* FROM session_data_t sd
+      WHERE sd.session_id = $1
+      ORDER BY sd.session_data_id
+      ",
+      &[&session_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// active sessions in any of the school's courses that overlap [start_time, end_time)
+pub async fn get_active_by_school_id_in_range(
+  con: &mut impl GenericClient,
+  school_id: i64,
+  start_time: i64,
+  end_time: i64,
+) -> Result<Vec<SessionData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT sd.* FROM recent_session_data_v sd
+      JOIN session_t ses ON sd.session_id = ses.session_id
+      JOIN course_t c ON ses.course_id = c.course_id
+      WHERE 1 = 1
+      AND c.school_id = $1
+      AND sd.active
+      AND sd.start_time < $3
+      AND sd.end_time > $2
+      ORDER BY sd.session_id
+      ",
+      &[&school_id, &start_time, &end_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: innexgo_hours_api::request::SessionDataViewProps,
 ) -> Result<Vec<SessionData>, tokio_postgres::Error> {
 
   let sql = [
-    if props.only_recent {
-      "SELECT sesd.* FROM recent_session_data_v sesd"
-    } else {
-      "SELECT sesd.* FROM session_data_t sesd"
-    },
+    utils::select_versions(
+      "session_data_t",
+      "sesd",
+      "session_data_id",
+      "session_id",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
     " INNER JOIN session_t ses ON sesd.session_id = ses.session_id",
     " WHERE 1 = 1",
     " AND ($1::bigint[]  IS NULL OR sesd.session_data_id = ANY($1))",
@@ -113,6 +185,7 @@ pub async fn query(
     " AND ($11::bigint   IS NULL OR sesd.end_time <= $11)",
     " AND ($12::bool     IS NULL OR sesd.active = $12)",
     " AND ($13::bigint[] IS NULL OR ses.course_id = ANY($13))",
+    " AND ($14::bigint  IS NULL OR sesd.creation_time <= $14)",
     " ORDER BY sesd.session_data_id",
   ]
   .join("\n");
@@ -136,6 +209,7 @@ pub async fn query(
         &props.max_end_time,
         &props.active,
         &props.course_id,
+        &props.as_of,
       ],
     )
     .await?
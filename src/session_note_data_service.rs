@@ -0,0 +1,180 @@
+use super::db_types::*;
+use super::utils;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use std::convert::TryInto;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for SessionNoteData {
+  // select * from session_note_data_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> SessionNoteData {
+    SessionNoteData {
+      session_note_data_id: row.get("session_note_data_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      session_note_id: row.get("session_note_id"),
+      session_note_kind: (row.get::<_, i64>("session_note_kind") as u8)
+        .try_into()
+        .unwrap(),
+      session_note_visibility: (row.get::<_, i64>("session_note_visibility") as u8)
+        .try_into()
+        .unwrap(),
+      content: row.get("content"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  session_note_id: i64,
+  session_note_kind: request::SessionNoteKind,
+  session_note_visibility: request::SessionNoteVisibility,
+  content: String,
+  active: bool,
+) -> Result<SessionNoteData, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let session_note_data_id = con
+    .query_one(
+      "INSERT INTO
+       session_note_data_t(
+           creation_time,
+           creator_user_id,
+           session_note_id,
+           session_note_kind,
+           session_note_visibility,
+           content,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7)
+       RETURNING session_note_data_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &session_note_id,
+        &(session_note_kind.clone() as i64),
+        &(session_note_visibility.clone() as i64),
+        &content,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(SessionNoteData {
+    session_note_data_id,
+    creation_time,
+    creator_user_id,
+    session_note_id,
+    session_note_kind,
+    session_note_visibility,
+    content,
+    active,
+  })
+}
+
+pub async fn get_by_session_note_id(
+  con: &mut impl GenericClient,
+  session_note_id: i64,
+) -> Result<Option<SessionNoteData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT snd.* FROM recent_session_note_data_v snd
+      WHERE snd.session_note_id = $1
+      ",
+      &[&session_note_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the session note, oldest first
+pub async fn get_history_by_session_note_id(
+  con: &mut impl GenericClient,
+  session_note_id: i64,
+) -> Result<Vec<SessionNoteData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT snd.* FROM session_note_data_t snd
+      WHERE snd.session_note_id = $1
+      ORDER BY snd.session_note_data_id
+      ",
+      &[&session_note_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::SessionNoteDataViewProps,
+) -> Result<Vec<SessionNoteData>, tokio_postgres::Error> {
+  let sql = [
+    utils::select_versions(
+      "session_note_data_t",
+      "snd",
+      "session_note_data_id",
+      "session_note_id",
+      props.only_recent,
+      12,
+    )
+    .as_str(),
+    " JOIN session_note_t sn ON snd.session_note_id = sn.session_note_id",
+    " JOIN session_t ses ON sn.session_id = ses.session_id",
+    " WHERE 1 = 1",
+    " AND ($1::bigint[]  IS NULL OR snd.session_note_data_id = ANY($1))",
+    " AND ($2::bigint    IS NULL OR snd.creation_time >= $2)",
+    " AND ($3::bigint    IS NULL OR snd.creation_time <= $3)",
+    " AND ($4::bigint[]  IS NULL OR snd.creator_user_id = ANY($4))",
+    " AND ($5::bigint[]  IS NULL OR snd.session_note_id = ANY($5))",
+    " AND ($6::bigint[]  IS NULL OR snd.session_note_kind = ANY($6))",
+    " AND ($7::bigint[]  IS NULL OR snd.session_note_visibility = ANY($7))",
+    " AND ($8::text      IS NULL OR snd.content LIKE CONCAT('%',$8,'%'))",
+    " AND ($9::bool      IS NULL OR snd.active = $9)",
+    " AND ($10::bigint[] IS NULL OR sn.session_id = ANY($10))",
+    " AND ($11::bigint[] IS NULL OR ses.course_id = ANY($11))",
+    " AND ($12::bigint   IS NULL OR snd.creation_time <= $12)",
+    " ORDER BY snd.session_note_data_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.session_note_data_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.session_note_id,
+        &props
+          .session_note_kind
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props
+          .session_note_visibility
+          .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.partial_content,
+        &props.active,
+        &props.session_id,
+        &props.course_id,
+        &props.as_of,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
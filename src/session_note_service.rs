@@ -0,0 +1,100 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for SessionNote {
+  // select * from session_note_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> SessionNote {
+    SessionNote {
+      session_note_id: row.get("session_note_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      session_id: row.get("session_id"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  session_id: i64,
+) -> Result<SessionNote, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let session_note_id = con
+    .query_one(
+      "INSERT INTO
+       session_note_t(
+           creation_time,
+           creator_user_id,
+           session_id
+       )
+       VALUES($1, $2, $3)
+       RETURNING session_note_id
+      ",
+      &[&creation_time, &creator_user_id, &session_id],
+    )
+    .await?
+    .get(0);
+
+  // return session note
+  Ok(SessionNote {
+    session_note_id,
+    creation_time,
+    creator_user_id,
+    session_id,
+  })
+}
+
+pub async fn get_by_session_note_id(
+  con: &mut impl GenericClient,
+  session_note_id: i64,
+) -> Result<Option<SessionNote>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM session_note_t WHERE session_note_id=$1",
+      &[&session_note_id],
+    )
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::SessionNoteViewProps,
+) -> Result<Vec<SessionNote>, tokio_postgres::Error> {
+  let sql = "SELECT sn.* FROM session_note_t sn
+     JOIN session_t ses ON sn.session_id = ses.session_id
+     WHERE 1 = 1
+     AND ($1::bigint[] IS NULL OR sn.session_note_id = ANY($1))
+     AND ($2::bigint   IS NULL OR sn.creation_time >= $2)
+     AND ($3::bigint   IS NULL OR sn.creation_time <= $3)
+     AND ($4::bigint[] IS NULL OR sn.creator_user_id = ANY($4))
+     AND ($5::bigint[] IS NULL OR sn.session_id = ANY($5))
+     AND ($6::bigint[] IS NULL OR ses.course_id = ANY($6))
+     ORDER BY sn.session_note_id
+     ";
+
+  let stmnt = con.prepare(sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.session_note_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.session_id,
+        &props.course_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(results)
+}
@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use either::*;
 use std::convert::From;
@@ -109,6 +110,27 @@ pub async fn get_by_stay_id(
   Ok(result)
 }
 
+// every version of the stay, oldest first
+pub async fn get_history_by_stay_id(
+  con: &mut impl GenericClient,
+  stay_id: i64,
+) -> Result<Vec<StayData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT syd.* FROM stay_data_t syd
+      WHERE syd.stay_id = $1
+      ORDER BY syd.stay_data_id
+      ",
+      &[&stay_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
 pub async fn is_active_by_stay_id(
   con: &mut impl GenericClient,
   stay_id: i64,
@@ -126,14 +148,18 @@ pub async fn query(
   props: innexgo_hours_api::request::StayDataViewProps,
 ) -> Result<Vec<StayData>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT syd.* FROM recent_stay_data_v syd"
-    } else {
-      "SELECT syd.* FROM stay_data_t syd"
-    },
-    " JOIN stay sy ON syd.stay_id = sy.stay_id",
-    " LEFT JOIN encounter fstenc ON syd.fst_encounter_id = fstenc.encounter_id",
-    " LEFT JOIN encounter sndenc ON syd.snd_encounter_id = sndenc.encounter_id",
+    utils::select_versions(
+      "stay_data_t",
+      "syd",
+      "stay_data_id",
+      "stay_id",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
+    " JOIN stay_t sy ON syd.stay_id = sy.stay_id",
+    " LEFT JOIN encounter_t fstenc ON syd.fst_encounter_id = fstenc.encounter_id",
+    " LEFT JOIN encounter_t sndenc ON syd.snd_encounter_id = sndenc.encounter_id",
     " WHERE 1 = 1",
     " AND ($1::bigint[]  IS NULL OR syd.stay_data_id = ANY($1))",
     " AND ($2::bigint    IS NULL OR syd.creation_time >= $2)",
@@ -148,6 +174,7 @@ pub async fn query(
     " AND ($11::bigint   IS NULL OR COALESCE(snd_time, sndenc.creation_time) <= $11)",
     " AND ($12::bool     IS NULL OR syd.active = $12)",
     " AND ($13::bigint[] IS NULL OR sy.attendee_user_id = ANY($13))",
+    " AND ($14::bigint  IS NULL OR syd.creation_time <= $14)",
     " ORDER BY syd.stay_data_id",
   ]
   .join("\n");
@@ -171,6 +198,7 @@ pub async fn query(
         &props.max_end_time,
         &props.active,
         &props.attendee_user_id,
+        &props.as_of,
       ],
     )
     .await?
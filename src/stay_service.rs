@@ -12,6 +12,7 @@ impl From<tokio_postgres::row::Row> for Stay {
       creator_user_id: row.get("creator_user_id"),
       attendee_user_id: row.get("attendee_user_id"),
       location_id: row.get("location_id"),
+      session_id: row.get("session_id"),
     }
   }
 }
@@ -21,6 +22,7 @@ pub async fn add(
   creator_user_id: i64,
   attendee_user_id: i64,
   location_id: i64,
+  session_id: Option<i64>,
 ) -> Result<Stay, tokio_postgres::Error> {
   let creation_time = current_time_millis();
 
@@ -31,12 +33,13 @@ pub async fn add(
            creation_time,
            creator_user_id,
            attendee_user_id,
-           location_id
+           location_id,
+           session_id
        )
-       VALUES($1, $2, $3, $4)
+       VALUES($1, $2, $3, $4, $5)
        RETURNING stay_id
       ",
-      &[&creation_time, &creator_user_id, &attendee_user_id, &location_id],
+      &[&creation_time, &creator_user_id, &attendee_user_id, &location_id, &session_id],
     ).await?
     .get(0);
 
@@ -46,7 +49,8 @@ pub async fn add(
     creation_time,
     creator_user_id,
     attendee_user_id,
-    location_id
+    location_id,
+    session_id,
   })
 }
 
@@ -64,6 +68,48 @@ pub async fn get_by_stay_id(
   Ok(result)
 }
 
+// the stay an attendance sheet recorded for the attendee, if it recorded one
+pub async fn get_by_attendee_user_id_session_id(
+  con: &mut impl GenericClient,
+  attendee_user_id: i64,
+  session_id: i64,
+) -> Result<Option<Stay>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "SELECT * FROM stay_t WHERE attendee_user_id=$1 AND session_id=$2",
+      &[&attendee_user_id, &session_id],
+    ).await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn get_by_attendee_user_id_in_range(
+  con: &mut impl GenericClient,
+  attendee_user_id: i64,
+  min_creation_time: i64,
+  max_creation_time: i64,
+) -> Result<Vec<Stay>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT s.* FROM stay_t s
+      WHERE 1 = 1
+      AND s.attendee_user_id = $1
+      AND s.creation_time >= $2
+      AND s.creation_time < $3
+      ORDER BY s.stay_id
+      ",
+      &[&attendee_user_id, &min_creation_time, &max_creation_time],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+
+  Ok(result)
+}
+
 pub async fn query(
   con: &mut impl GenericClient,
   props: request::StayViewProps,
@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use super::utils::current_time_millis;
 use innexgo_hours_api::request;
 use std::convert::TryInto;
@@ -8,8 +9,8 @@ impl From<tokio_postgres::row::Row> for Subscription {
   // select * from subscription order only, otherwise it will fail
   fn from(row: tokio_postgres::Row) -> Subscription {
     Subscription {
-      subscription_id: row.get("creator_user_id"),
-      creation_time: row.get("creator_user_id"),
+      subscription_id: row.get("subscription_id"),
+      creation_time: row.get("creation_time"),
       creator_user_id: row.get("creator_user_id"),
       max_uses: row.get("max_uses"),
       subscription_kind: (row.get::<_, i64>("subscription_kind") as u8)
@@ -87,7 +88,7 @@ pub async fn get_by_subscription_id(
   con: &mut impl GenericClient,
   subscription_id: i64,
 ) -> Result<Option<Subscription>, tokio_postgres::Error> {
-  let sql = "SELECT * FROM subscription WHERE subscription_id=$1";
+  let sql = "SELECT * FROM subscription_t WHERE subscription_id=$1";
   let result = con
     .query_opt(sql, &[&subscription_id])
     .await?
@@ -100,17 +101,22 @@ pub async fn query(
   props: request::SubscriptionViewProps,
 ) -> Result<Vec<Subscription>, tokio_postgres::Error> {
   let sql = [
-    if props.only_recent {
-      "SELECT s.* FROM recent_subscription_v s"
-    } else {
-      "SELECT s.* FROM subscription_t s"
-    },
+    utils::select_versions(
+      "subscription_t",
+      "s",
+      "subscription_id",
+      "creator_user_id",
+      props.only_recent,
+      6,
+    )
+    .as_str(),
     " WHERE 1 = 1",
     " AND ($1::bigint[] IS NULL OR s.subscription_id = ANY($1))",
     " AND ($2::bigint   IS NULL OR s.creation_time >= $2)",
     " AND ($3::bigint   IS NULL OR s.creation_time <= $3)",
     " AND ($4::bigint[] IS NULL OR s.creator_user_id = ANY($4))",
     " AND ($5::bigint[] IS NULL OR s.subscription_kind = ANY($5))",
+    " AND ($6::bigint   IS NULL OR s.creation_time <= $6)",
     " ORDER BY s.subscription_id",
   ]  .join("\n");
 
@@ -127,6 +133,7 @@ pub async fn query(
         &props
           .subscription_kind
           .map(|v| v.into_iter().map(|x| x as i64).collect::<Vec<i64>>()),
+        &props.as_of,
       ],
     )
     .await?
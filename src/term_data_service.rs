@@ -0,0 +1,185 @@
+use super::db_types::*;
+use super::utils;
+use super::utils::current_time_millis;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for TermData {
+  // select * from term_data_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> TermData {
+    TermData {
+      term_data_id: row.get("term_data_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      term_id: row.get("term_id"),
+      name: row.get("name"),
+      start_time: row.get("start_time"),
+      end_time: row.get("end_time"),
+      active: row.get("active"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  term_id: i64,
+  name: String,
+  start_time: i64,
+  end_time: i64,
+  active: bool,
+) -> Result<TermData, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let term_data_id = con
+    .query_one(
+      "INSERT INTO
+       term_data_t(
+           creation_time,
+           creator_user_id,
+           term_id,
+           name,
+           start_time,
+           end_time,
+           active
+       )
+       VALUES ($1, $2, $3, $4, $5, $6, $7)
+       RETURNING term_data_id
+      ",
+      &[
+        &creation_time,
+        &creator_user_id,
+        &term_id,
+        &name,
+        &start_time,
+        &end_time,
+        &active,
+      ],
+    )
+    .await?
+    .get(0);
+
+  Ok(TermData {
+    term_data_id,
+    creation_time,
+    creator_user_id,
+    term_id,
+    name,
+    start_time,
+    end_time,
+    active,
+  })
+}
+
+pub async fn get_by_term_id(
+  con: &mut impl GenericClient,
+  term_id: i64,
+) -> Result<Option<TermData>, tokio_postgres::Error> {
+  let result = con
+    .query_opt(
+      "
+      SELECT td.* FROM recent_term_data_v td
+      WHERE td.term_id = $1
+      ",
+      &[&term_id],
+    )
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// every version of the term, oldest first
+pub async fn get_history_by_term_id(
+  con: &mut impl GenericClient,
+  term_id: i64,
+) -> Result<Vec<TermData>, tokio_postgres::Error> {
+  let result = con
+    .query(
+      "
+      SELECT td.* FROM term_data_t td
+      WHERE td.term_id = $1
+      ORDER BY td.term_data_id
+      ",
+      &[&term_id],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+pub async fn is_active_by_term_id(
+  con: &mut impl GenericClient,
+  term_id: i64,
+) -> Result<bool, tokio_postgres::Error> {
+  let result = matches!(
+    get_by_term_id(con, term_id).await?,
+    Some(TermData { active: true, .. })
+  );
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: innexgo_hours_api::request::TermDataViewProps,
+) -> Result<Vec<TermData>, tokio_postgres::Error> {
+  let sql = [
+    utils::select_versions(
+      "term_data_t",
+      "td",
+      "term_data_id",
+      "term_id",
+      props.only_recent,
+      14,
+    )
+    .as_str(),
+    " JOIN term_t t ON td.term_id = t.term_id",
+    " WHERE 1 = 1",
+    " AND ($1::bigint[]  IS NULL OR td.term_data_id = ANY($1))",
+    " AND ($2::bigint    IS NULL OR td.creation_time >= $2)",
+    " AND ($3::bigint    IS NULL OR td.creation_time <= $3)",
+    " AND ($4::bigint[]  IS NULL OR td.creator_user_id = ANY($4))",
+    " AND ($5::bigint[]  IS NULL OR td.term_id = ANY($5))",
+    " AND ($6::text[]    IS NULL OR td.name = ANY($6))",
+    " AND ($7::text      IS NULL OR td.name LIKE CONCAT('%',$7,'%'))",
+    " AND ($8::bigint    IS NULL OR td.start_time >= $8)",
+    " AND ($9::bigint    IS NULL OR td.start_time <= $9)",
+    " AND ($10::bigint   IS NULL OR td.end_time >= $10)",
+    " AND ($11::bigint   IS NULL OR td.end_time <= $11)",
+    " AND ($12::bool     IS NULL OR td.active = $12)",
+    " AND ($13::bigint[] IS NULL OR t.school_id = ANY($13))",
+    " AND ($14::bigint  IS NULL OR td.creation_time <= $14)",
+    " ORDER BY td.term_data_id",
+  ]
+  .join("\n");
+
+  let stmnt = con.prepare(&sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.term_data_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.term_id,
+        &props.name,
+        &props.partial_name,
+        &props.min_start_time,
+        &props.max_start_time,
+        &props.min_end_time,
+        &props.max_end_time,
+        &props.active,
+        &props.school_id,
+        &props.as_of,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|row| row.into())
+    .collect();
+
+  Ok(results)
+}
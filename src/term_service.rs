@@ -0,0 +1,93 @@
+use super::db_types::*;
+use super::utils::current_time_millis;
+use innexgo_hours_api::request;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for Term {
+  // select * from term_t order only, otherwise it will fail
+  fn from(row: tokio_postgres::row::Row) -> Term {
+    Term {
+      term_id: row.get("term_id"),
+      creation_time: row.get("creation_time"),
+      creator_user_id: row.get("creator_user_id"),
+      school_id: row.get("school_id"),
+    }
+  }
+}
+
+pub async fn add(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  school_id: i64,
+) -> Result<Term, tokio_postgres::Error> {
+  let creation_time = current_time_millis();
+
+  let term_id = con
+    .query_one(
+      "INSERT INTO
+       term_t(
+           creation_time,
+           creator_user_id,
+           school_id
+       )
+       VALUES($1, $2, $3)
+       RETURNING term_id
+      ",
+      &[&creation_time, &creator_user_id, &school_id],
+    )
+    .await?
+    .get(0);
+
+  // return term
+  Ok(Term {
+    term_id,
+    creation_time,
+    creator_user_id,
+    school_id,
+  })
+}
+
+pub async fn get_by_term_id(
+  con: &mut impl GenericClient,
+  term_id: i64,
+) -> Result<Option<Term>, tokio_postgres::Error> {
+  let result = con
+    .query_opt("SELECT * FROM term_t WHERE term_id=$1", &[&term_id])
+    .await?
+    .map(|x| x.into());
+
+  Ok(result)
+}
+
+pub async fn query(
+  con: &mut impl GenericClient,
+  props: request::TermViewProps,
+) -> Result<Vec<Term>, tokio_postgres::Error> {
+  let sql = "SELECT t.* FROM term_t t WHERE 1 = 1
+     AND ($1::bigint[] IS NULL OR t.term_id = ANY($1))
+     AND ($2::bigint   IS NULL OR t.creation_time >= $2)
+     AND ($3::bigint   IS NULL OR t.creation_time <= $3)
+     AND ($4::bigint[] IS NULL OR t.creator_user_id = ANY($4))
+     AND ($5::bigint[] IS NULL OR t.school_id = ANY($5))
+     ORDER BY t.term_id
+     ";
+
+  let stmnt = con.prepare(sql).await?;
+
+  let results = con
+    .query(
+      &stmnt,
+      &[
+        &props.term_id,
+        &props.min_creation_time,
+        &props.max_creation_time,
+        &props.creator_user_id,
+        &props.school_id,
+      ],
+    )
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(results)
+}
@@ -0,0 +1,452 @@
+use super::{new_location, new_school, props, tick, TestDb};
+use crate::checkin_attempt_service;
+use crate::encounter_service;
+use crate::hall_pass_check_service;
+use crate::hall_pass_service;
+use crate::location_checkin_service;
+use crate::occupancy_service;
+use crate::stay_data_service;
+use crate::stay_service;
+use crate::utils::current_time_millis;
+use either::{Left, Right};
+use innexgo_hours_api::request;
+use serde_json::json;
+
+#[tokio::test]
+async fn encounter() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let now = current_time_millis();
+
+  let encounter = encounter_service::add(
+    con,
+    1,
+    location_id,
+    2,
+    request::EncounterKind::Hardware,
+    Some(now - 60_000),
+    Some("kiosk-1".to_owned()),
+  )
+  .await
+  .unwrap();
+
+  // the scan time is when it happened, not when we heard about it
+  assert_eq!(encounter.creation_time, now - 60_000);
+
+  assert!(
+    encounter_service::get_by_encounter_id(con, encounter.encounter_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert_eq!(
+    encounter_service::get_by_creator_user_id_idempotency_key(con, 1, "kiosk-1")
+      .await
+      .unwrap()
+      .map(|x| x.encounter_id),
+    Some(encounter.encounter_id)
+  );
+
+  // the same key from the same creator can only be used once
+  assert!(encounter_service::add(
+    con,
+    1,
+    location_id,
+    2,
+    request::EncounterKind::Hardware,
+    None,
+    Some("kiosk-1".to_owned()),
+  )
+  .await
+  .is_err());
+
+  let found = encounter_service::query(con, props(json!({ "attendeeUserId": [2] })))
+    .await
+    .unwrap();
+  assert_eq!(found.len(), 1);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn stay() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let now = current_time_millis();
+
+  let sign_in = encounter_service::add(
+    con,
+    1,
+    location_id,
+    2,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  let stay = stay_service::add(con, 1, 2, location_id, None)
+    .await
+    .unwrap();
+  assert!(stay_service::get_by_stay_id(con, stay.stay_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    stay_service::get_by_attendee_user_id_in_range(con, 2, now - 60_000, now + 60_000)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert_eq!(
+    stay_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let first = stay_data_service::add(
+    con,
+    1,
+    stay.stay_id,
+    Left(sign_in.encounter_id),
+    Right(now + 60_000),
+    true,
+  )
+  .await
+  .unwrap();
+
+  // still there, so they're occupying the location
+  let occupants = occupancy_service::get_by_location_id(con, location_id, current_time_millis())
+    .await
+    .unwrap();
+  assert_eq!(occupants.len(), 1);
+  assert_eq!(occupants[0].attendee_user_id, 2);
+
+  tick().await;
+  let sign_out = encounter_service::add(
+    con,
+    1,
+    location_id,
+    2,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  stay_data_service::add(
+    con,
+    1,
+    stay.stay_id,
+    Left(sign_in.encounter_id),
+    Left(sign_out.encounter_id),
+    true,
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    stay_data_service::get_by_stay_data_id(con, &first.stay_data_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert_eq!(
+    stay_data_service::get_by_stay_id(con, stay.stay_id)
+      .await
+      .unwrap()
+      .unwrap()
+      .snd,
+    Left(sign_out.encounter_id)
+  );
+  assert!(stay_data_service::is_active_by_stay_id(con, stay.stay_id)
+    .await
+    .unwrap());
+  assert_eq!(
+    stay_data_service::get_history_by_stay_id(con, stay.stay_id)
+      .await
+      .unwrap()
+      .len(),
+    2
+  );
+
+  for (only_recent, expected) in [(true, 1), (false, 2)] {
+    let found = stay_data_service::query(
+      con,
+      props(json!({ "onlyRecent": only_recent, "attendeeUserId": [2] })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  // gone now
+  let occupants = occupancy_service::get_by_school_id(con, school_id, current_time_millis())
+    .await
+    .unwrap();
+  assert!(occupants.is_empty());
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn occupancy() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let other_location_id = new_location(con, 1, school_id).await;
+
+  // a lone sign in with no stay still counts
+  encounter_service::add(
+    con,
+    1,
+    location_id,
+    2,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  tick().await;
+  let now = current_time_millis();
+
+  assert_eq!(
+    occupancy_service::get_by_location_id(con, location_id, now)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert!(
+    occupancy_service::get_by_location_id(con, other_location_id, now)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+  assert_eq!(
+    occupancy_service::get_by_location_ids(con, &[location_id, other_location_id], now)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert_eq!(
+    occupancy_service::get_by_school_id(con, school_id, now)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn checkin() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+
+  location_checkin_service::add(con, 1, location_id, "secret".to_owned(), true)
+    .await
+    .unwrap();
+
+  assert!(
+    location_checkin_service::get_by_location_id(con, location_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(
+    location_checkin_service::get_active_by_location_id(con, location_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+
+  tick().await;
+  location_checkin_service::add(con, 1, location_id, "secret".to_owned(), false)
+    .await
+    .unwrap();
+
+  assert!(
+    location_checkin_service::get_active_by_location_id(con, location_id)
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  let since = current_time_millis();
+  checkin_attempt_service::add(con, 2, location_id, None)
+    .await
+    .unwrap();
+  checkin_attempt_service::add(con, 2, location_id, Some(7))
+    .await
+    .unwrap();
+
+  assert_eq!(
+    checkin_attempt_service::count_by_creator_user_id_since(con, 2, since)
+      .await
+      .unwrap(),
+    2
+  );
+
+  // only successful attempts use up a code
+  for (code_period, expected) in [(7, true), (8, false)] {
+    assert_eq!(
+      checkin_attempt_service::is_code_period_used(con, 2, location_id, code_period)
+        .await
+        .unwrap(),
+      expected
+    );
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn hall_pass() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let origin_location_id = new_location(con, 1, school_id).await;
+  let destination_location_id = new_location(con, 1, school_id).await;
+  let now = current_time_millis();
+
+  let sign_in = encounter_service::add(
+    con,
+    1,
+    destination_location_id,
+    2,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  let destination_stay = stay_service::add(con, 1, 2, destination_location_id, None)
+    .await
+    .unwrap();
+
+  stay_data_service::add(
+    con,
+    1,
+    destination_stay.stay_id,
+    Left(sign_in.encounter_id),
+    Right(now + 60_000),
+    true,
+  )
+  .await
+  .unwrap();
+
+  let hall_pass = hall_pass_service::add(
+    con,
+    1,
+    2,
+    origin_location_id,
+    destination_location_id,
+    destination_stay.stay_id,
+    request::HallPassReasonKind::Restroom,
+    now + 60_000,
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    hall_pass_service::get_by_hall_pass_id(con, hall_pass.hall_pass_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert_eq!(
+    hall_pass_service::query(con, props(json!({ "attendeeUserId": [2] })))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  // not back yet, and due inside the window
+  assert!(hall_pass_service::get_return_time(con, &hall_pass)
+    .await
+    .unwrap()
+    .is_none());
+  assert_eq!(
+    hall_pass_service::get_overdue(con, now, now + 120_000)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  tick().await;
+  let back = encounter_service::add(
+    con,
+    1,
+    origin_location_id,
+    2,
+    request::EncounterKind::Manual,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    hall_pass_service::get_return_time(con, &hall_pass)
+      .await
+      .unwrap(),
+    Some(back.creation_time)
+  );
+  assert!(hall_pass_service::get_overdue(con, now, now + 120_000)
+    .await
+    .unwrap()
+    .is_empty());
+
+  // the monitor picks up from the last check it recorded
+  let mut sp = con.transaction().await.unwrap();
+  assert!(hall_pass_check_service::lock_latest(&mut sp)
+    .await
+    .unwrap()
+    .is_none());
+  let check = hall_pass_check_service::add(&mut sp).await.unwrap();
+  assert_eq!(
+    hall_pass_check_service::lock_latest(&mut sp)
+      .await
+      .unwrap()
+      .map(|x| x.hall_pass_check_id),
+    Some(check.hall_pass_check_id)
+  );
+  sp.commit().await.unwrap();
+
+  db.teardown().await;
+}
@@ -0,0 +1,666 @@
+use super::{new_course, new_location, new_school, props, tick, TestDb};
+use crate::adminship_service;
+use crate::course_data_service;
+use crate::course_invitation_service;
+use crate::course_key_data_service;
+use crate::course_key_service;
+use crate::course_membership_service;
+use crate::course_membership_service::CourseCapability;
+use crate::course_service;
+use crate::encounter_service;
+use crate::guardianship_service;
+use crate::guardianship_summary_run_service;
+use crate::homeroom_service;
+use crate::location_data_service;
+use crate::location_service;
+use crate::term_service;
+use crate::utils::current_time_millis;
+use innexgo_hours_api::request;
+use serde_json::json;
+
+#[tokio::test]
+async fn location() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+
+  assert!(location_service::get_by_location_id(con, location_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    location_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let location_data = location_data_service::get_by_location_id(con, location_id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert!(
+    location_data_service::get_by_location_data_id(con, &location_data.location_data_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(
+    location_data_service::is_active_by_location_id(con, location_id)
+      .await
+      .unwrap()
+  );
+  assert_eq!(
+    location_data_service::get_history_by_location_id(con, location_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for only_recent in [true, false] {
+    let found = location_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn course() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+
+  assert!(course_service::get_by_course_id(con, course_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    course_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  // courses in a term
+  let term = term_service::add(con, 1, school_id).await.unwrap();
+  let termed = course_service::add(con, 1, school_id).await.unwrap();
+  course_data_service::add(
+    con,
+    1,
+    termed.course_id,
+    location_id,
+    Some(term.term_id),
+    "Termed".to_owned(),
+    "".to_owned(),
+    false,
+    true,
+  )
+  .await
+  .unwrap();
+
+  let in_term = course_data_service::get_active_by_term_id(con, term.term_id)
+    .await
+    .unwrap();
+  assert_eq!(in_term.len(), 1);
+  assert_eq!(in_term[0].course_id, termed.course_id);
+  assert_eq!(
+    course_service::query(con, props(json!({ "termId": [term.term_id] })))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  // taking it out of the term is just another version
+  course_data_service::add(
+    con,
+    1,
+    termed.course_id,
+    location_id,
+    None,
+    "Termed".to_owned(),
+    "".to_owned(),
+    false,
+    true,
+  )
+  .await
+  .unwrap();
+  assert!(
+    course_data_service::get_active_by_term_id(con, term.term_id)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+  assert!(
+    course_service::query(con, props(json!({ "termId": [term.term_id] })))
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  let course_data = course_data_service::get_by_course_id(con, course_id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert!(
+    course_data_service::get_by_course_data_id(con, course_data.course_data_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(course_data_service::is_active_by_course_id(con, course_id)
+    .await
+    .unwrap());
+  assert_eq!(
+    course_data_service::get_history_by_course_id(con, course_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for only_recent in [true, false] {
+    let found = course_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 2);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn course_key() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  let now = current_time_millis();
+
+  let course_key = course_key_service::add(
+    con,
+    "course_key",
+    1,
+    course_id,
+    10,
+    request::CourseMembershipKind::Student,
+    now,
+    now + 60_000,
+    Some("GHIJKL".to_owned()),
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    course_key_service::get_by_course_key_key(con, &course_key.course_key_key)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(course_key_service::get_by_short_code(con, "GHIJKL")
+    .await
+    .unwrap()
+    .is_some());
+  assert!(course_key_service::get_by_key_or_short_code(con, "ghijkl")
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    course_key_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let course_key_data =
+    course_key_data_service::add(con, 1, course_key.course_key_key.clone(), true)
+      .await
+      .unwrap();
+
+  assert!(course_key_data_service::get_by_course_key_data_id(
+    con,
+    course_key_data.course_key_data_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert!(
+    course_key_data_service::get_by_course_key_key(con, &course_key.course_key_key)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(
+    course_key_data_service::is_active_by_course_key_key(con, &course_key.course_key_key)
+      .await
+      .unwrap()
+  );
+  assert_eq!(
+    course_key_data_service::get_history_by_course_key_key(con, &course_key.course_key_key)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for only_recent in [true, false] {
+    let found = course_key_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  // joining through a key counts as a use
+  course_membership_service::add(
+    con,
+    2,
+    2,
+    course_id,
+    request::CourseMembershipKind::Student,
+    Some(course_key.course_key_key.clone()),
+    None,
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    course_membership_service::get_by_course_key_key(con, &course_key.course_key_key)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert_eq!(
+    course_membership_service::count_course_key_uses(con, &course_key.course_key_key)
+      .await
+      .unwrap(),
+    1
+  );
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn course_membership() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+
+  let course_invitation = course_invitation_service::add(
+    con,
+    1,
+    course_id,
+    "instructor@example.com".to_owned(),
+    request::CourseMembershipKind::Instructor,
+    current_time_millis() + 60_000,
+  )
+  .await
+  .unwrap();
+
+  assert!(course_invitation_service::get_by_course_invitation_id(
+    con,
+    course_invitation.course_invitation_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert_eq!(
+    course_invitation_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let course_membership = course_membership_service::add(
+    con,
+    1,
+    1,
+    course_id,
+    request::CourseMembershipKind::Instructor,
+    None,
+    Some(course_invitation.course_invitation_id),
+  )
+  .await
+  .unwrap();
+
+  assert!(course_membership_service::get_by_course_membership_id(
+    con,
+    course_membership.course_membership_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert_eq!(
+    course_membership_service::get_by_course_invitation_id(
+      con,
+      course_invitation.course_invitation_id
+    )
+    .await
+    .unwrap()
+    .len(),
+    1
+  );
+  assert_eq!(
+    course_membership_service::count_course_invitation_uses(
+      con,
+      course_invitation.course_invitation_id
+    )
+    .await
+    .unwrap(),
+    1
+  );
+  assert!(
+    course_membership_service::get_by_user_id_course_id(con, 1, course_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(course_membership_service::is_instructor(con, 1, course_id)
+    .await
+    .unwrap());
+  assert_eq!(
+    course_membership_service::count_instructors(con, course_id)
+      .await
+      .unwrap(),
+    1
+  );
+  assert_eq!(
+    course_membership_service::get_by_course_id(con, course_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  assert!(
+    course_membership_service::has_capability(con, 1, course_id, CourseCapability::Manage)
+      .await
+      .unwrap()
+  );
+  assert!(course_membership_service::has_capability_at(
+    con,
+    1,
+    location_id,
+    CourseCapability::TakeAttendance
+  )
+  .await
+  .unwrap());
+  assert!(!course_membership_service::has_capability_at(
+    con,
+    2,
+    location_id,
+    CourseCapability::TakeAttendance
+  )
+  .await
+  .unwrap());
+
+  tick().await;
+  course_membership_service::add(
+    con,
+    1,
+    1,
+    course_id,
+    request::CourseMembershipKind::Cancel,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  for (only_recent, expected) in [(true, 1), (false, 2)] {
+    let found = course_membership_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn guardianship() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+
+  // user 1 runs the school, user 2 is a student in it, user 3 is their guardian
+  adminship_service::add(
+    con,
+    1,
+    1,
+    school_id,
+    request::AdminshipKind::Admin,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  course_membership_service::add(
+    con,
+    1,
+    2,
+    course_id,
+    request::CourseMembershipKind::Student,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  assert!(guardianship_service::is_admin_of_student(con, 1, 2)
+    .await
+    .unwrap());
+  assert!(!guardianship_service::is_admin_of_student(con, 3, 2)
+    .await
+    .unwrap());
+
+  guardianship_service::add(con, 3, 3, 2, request::GuardianshipKind::Requested, true)
+    .await
+    .unwrap();
+  assert!(!guardianship_service::is_guardian(con, 3, 2).await.unwrap());
+
+  tick().await;
+  guardianship_service::add(con, 2, 3, 2, request::GuardianshipKind::Active, true)
+    .await
+    .unwrap();
+  assert!(guardianship_service::is_guardian(con, 3, 2).await.unwrap());
+  assert!(
+    guardianship_service::get_by_guardian_user_id_student_user_id(con, 3, 2)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert_eq!(
+    guardianship_service::get_weekly_summary_recipients(con)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for (only_recent, expected) in [(true, 1), (false, 2)] {
+    let found = guardianship_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn guardianship_summary_run() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let mut sp = db.con.transaction().await.unwrap();
+
+  assert!(guardianship_summary_run_service::lock_latest(&mut sp)
+    .await
+    .unwrap()
+    .is_none());
+
+  guardianship_summary_run_service::add(&mut sp)
+    .await
+    .unwrap();
+  tick().await;
+  let last_run = guardianship_summary_run_service::add(&mut sp)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    guardianship_summary_run_service::lock_latest(&mut sp)
+      .await
+      .unwrap()
+      .map(|x| x.guardianship_summary_run_id),
+    Some(last_run.guardianship_summary_run_id)
+  );
+
+  sp.commit().await.unwrap();
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn homeroom() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let mut course_ids = vec![];
+  let mut location_ids = vec![];
+  for homeroom in [true, true, false] {
+    let location_id = new_location(con, 1, school_id).await;
+    let course_id = new_course(con, 1, school_id, location_id).await;
+    course_data_service::add(
+      con,
+      1,
+      course_id,
+      location_id,
+      None,
+      "Homeroom".to_owned(),
+      "".to_owned(),
+      homeroom,
+      true,
+    )
+    .await
+    .unwrap();
+    course_ids.push(course_id);
+    location_ids.push(location_id);
+  }
+
+  // student 2 is in the first homeroom and the plain course
+  for course_id in [course_ids[0], course_ids[2]] {
+    course_membership_service::add(
+      con,
+      1,
+      2,
+      course_id,
+      request::CourseMembershipKind::Student,
+      None,
+      None,
+    )
+    .await
+    .unwrap();
+  }
+
+  let homerooms = homeroom_service::get_by_student_user_id(con, 2)
+    .await
+    .unwrap();
+  assert_eq!(homerooms.len(), 1);
+  assert_eq!(homerooms[0].course_id, course_ids[0]);
+  assert_eq!(homerooms[0].location_id, location_ids[0]);
+
+  // joining the second homeroom, or making the plain course one, would be a second homeroom
+  for (course_id, student_user_id, expected) in [
+    (course_ids[1], Some(2), 1),
+    (course_ids[0], Some(2), 0),
+    (course_ids[1], None, 0),
+    (course_ids[2], None, 1),
+  ] {
+    assert_eq!(
+      homeroom_service::count_students_in_other_homerooms(con, course_id, student_user_id)
+        .await
+        .unwrap(),
+      expected
+    );
+  }
+
+  let now = current_time_millis();
+  encounter_service::add(
+    con,
+    1,
+    location_ids[0],
+    2,
+    request::EncounterKind::Manual,
+    Some(now),
+    None,
+  )
+  .await
+  .unwrap();
+
+  // only encounters in the homeroom during the day count
+  for (min_time, expected) in [(now - 1000, Some(now)), (now + 1000, None)] {
+    let attendance =
+      homeroom_service::get_attendance(con, school_id, &None, min_time, min_time + 60_000)
+        .await
+        .unwrap();
+    assert_eq!(attendance.len(), 1);
+    assert_eq!(attendance[0].homeroom.student_user_id, 2);
+    assert_eq!(attendance[0].arrival_time, expected);
+  }
+
+  // archived homerooms don't count
+  course_data_service::add(
+    con,
+    1,
+    course_ids[0],
+    location_ids[0],
+    None,
+    "Homeroom".to_owned(),
+    "".to_owned(),
+    true,
+    false,
+  )
+  .await
+  .unwrap();
+  assert!(homeroom_service::get_by_student_user_id(con, 2)
+    .await
+    .unwrap()
+    .is_empty());
+
+  db.teardown().await;
+}
@@ -0,0 +1,3030 @@
+use super::{enroll, handler_env, new_course, new_location, new_school, props, tick, TestDb};
+use crate::adminship_service;
+use crate::api;
+use crate::commitment_service;
+use crate::course_data_service;
+use crate::course_invitation_service;
+use crate::course_key_data_service;
+use crate::course_key_service;
+use crate::course_membership_service;
+use crate::encounter_service;
+use crate::event_dispatcher;
+use crate::events;
+use crate::guardianship_service;
+use crate::handlers;
+use crate::irregularity_service;
+use crate::location_checkin_service;
+use crate::occupancy_service;
+use crate::session_data_service;
+use crate::session_request_service;
+use crate::session_service;
+use crate::stay_data_service;
+use crate::stay_service;
+use crate::subscription_service;
+use crate::term_data_service;
+use crate::term_service;
+use crate::utils;
+use crate::utils::current_time_millis;
+use futures_util::StreamExt;
+use innexgo_hours_api::request;
+use innexgo_hours_api::response;
+use innexgo_hours_api::response::InnexgoHoursError;
+use serde_json::json;
+
+fn users() -> serde_json::Value {
+  json!([
+    {
+      "user": { "userId": 1, "creationTime": 0 },
+      "email": "admin@example.com",
+      "apiKeys": ["admin"]
+    },
+    {
+      "user": { "userId": 2, "creationTime": 0 },
+      "email": "student@example.com",
+      "name": "Alex Student",
+      "apiKeys": ["student"]
+    },
+    {
+      "user": { "userId": 3, "creationTime": 0 },
+      "email": "guardian@example.com",
+      "apiKeys": ["guardian"]
+    }
+  ])
+}
+
+#[tokio::test]
+async fn school_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  subscription_service::add(&mut db.con, 1, request::SubscriptionKind::Valid, 1, 0)
+    .await
+    .unwrap();
+
+  // unknown keys are turned away before anything is written
+  let result = handlers::school_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "nobody", "name": "School", "description": "", "whole": false })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  handlers::school_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "name": "School", "description": "", "whole": false })),
+  )
+  .await
+  .unwrap();
+
+  // the student has no subscription
+  let result = handlers::school_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "name": "School", "description": "", "whole": false })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::SubscriptionNonexistent)
+  ));
+
+  let school_data = handlers::school_data_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "student" })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(school_data.len(), 1);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn course_invitation_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  let invitation = json!({
+    "apiKey": "admin",
+    "courseId": course_id,
+    "email": "Student@Example.com",
+    "courseMembershipKind": "CANCEL",
+    "endTime": current_time_millis() + 60_000,
+  });
+
+  // nobody can be invited to leave
+  let result = handlers::course_invitation_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(invitation.clone()),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::CourseMembershipKindInvalid)
+  ));
+
+  // there's no mail service here, which is reported without failing the invitation
+  let mut invitation = invitation;
+  invitation["courseMembershipKind"] = json!("STUDENT");
+  let mailed = handlers::course_invitation_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(invitation),
+  )
+  .await
+  .unwrap();
+  assert!(mailed.mail_error.is_some());
+  assert_eq!(mailed.data.email, "student@example.com");
+
+  let found = course_invitation_service::query(
+    &mut db.con,
+    props(json!({ "courseId": [course_id], "email": ["student@example.com"] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(found.len(), 1);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn stay_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let stay = |api_key: &str, attendee_user_id: i64| {
+    props::<request::StayNewProps>(json!({
+      "apiKey": api_key,
+      "attendeeUserId": attendee_user_id,
+      "locationId": location_id,
+      "fstTime": now,
+      "sndTime": now + 60_000,
+    }))
+  };
+
+  handlers::stay_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    stay("admin", 2),
+  )
+  .await
+  .unwrap();
+
+  // the student can't take attendance
+  let result = handlers::stay_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    stay("student", 2),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  // the auth service doesn't know about this attendee
+  let result = handlers::stay_new(config, db.db().await, auth_service, stay("admin", 3)).await;
+  assert!(result.is_err());
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn availability_booking_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  // an hour of half hour slots, starting in a minute
+  let start_time = current_time_millis() + 60_000;
+  let availability_data = handlers::availability_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "name": "Office Hours",
+      "startTime": start_time,
+      "endTime": start_time + 60 * 60_000,
+      "slotDuration": 30 * 60_000,
+    })),
+  )
+  .await
+  .unwrap();
+  let availability_id = availability_data.availability.availability_id;
+
+  // slots have to be long enough to be worth booking
+  let result = handlers::availability_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "name": "Office Hours",
+      "startTime": start_time,
+      "endTime": start_time + 60 * 60_000,
+      "slotDuration": 1,
+    })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::AvailabilitySlotDurationInvalid)
+  ));
+
+  let slots = handlers::availability_slot_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "availabilityId": [availability_id] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(slots.len(), 2);
+
+  let book = |start_time: i64| {
+    props::<request::AvailabilityBookingNewProps>(json!({
+      "apiKey": "student",
+      "availabilityId": availability_id,
+      "startTime": start_time,
+    }))
+  };
+
+  handlers::availability_booking_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    book(start_time),
+  )
+  .await
+  .unwrap();
+
+  // the slot is gone once booked
+  let result = handlers::availability_booking_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    book(start_time),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::AvailabilitySlotTaken)
+  ));
+
+  // and only slot starts can be booked
+  let result = handlers::availability_booking_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    book(start_time + 1),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::AvailabilitySlotNonexistent)
+  ));
+
+  // the room is taken by another course during the second slot
+  let con = &mut db.con;
+  let other_course_id = new_course(con, 1, school_id, location_id).await;
+  let session = session_service::add(con, 1, other_course_id).await.unwrap();
+  session_data_service::add(
+    con,
+    1,
+    session.session_id,
+    "Lab".to_owned(),
+    start_time + 30 * 60_000,
+    start_time + 60 * 60_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  let result = handlers::availability_booking_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    book(start_time + 30 * 60_000),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::LocationDoubleBooked)
+  ));
+
+  let slots = handlers::availability_slot_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "student", "availabilityId": [availability_id] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(slots.len(), 1);
+  assert_eq!(slots[0].start_time, start_time + 30 * 60_000);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn session_new_conflicts() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session = |start_time: i64, conflict_mode: &str| {
+    props::<request::SessionNewProps>(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "name": "Office Hours",
+      "startTime": start_time,
+      "endTime": start_time + 60_000,
+      "attendeeUserIds": [],
+      "conflictMode": conflict_mode,
+    }))
+  };
+
+  let first = handlers::session_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    session(now, "STRICT"),
+  )
+  .await
+  .unwrap();
+  assert!(first.conflicts.is_empty());
+
+  // same instructor, same room
+  let result = handlers::session_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    session(now + 30_000, "STRICT"),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::InstructorDoubleBooked)
+  ));
+
+  let second = handlers::session_new(
+    config,
+    db.db().await,
+    auth_service,
+    session(now + 30_000, "LENIENT"),
+  )
+  .await
+  .unwrap();
+  assert_eq!(second.conflicts.len(), 2);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn closure_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  adminship_service::add(
+    con,
+    1,
+    1,
+    school_id,
+    request::AdminshipKind::Admin,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  // one session on the snow day, with a student in it, and one the day after
+  let now = current_time_millis();
+  let day = 24 * 60 * 60 * 1000;
+  let mut session_ids = vec![];
+  for start_time in [now + 60_000, now + day + 60_000] {
+    let session = session_service::add(con, 1, course_id).await.unwrap();
+    session_data_service::add(
+      con,
+      1,
+      session.session_id,
+      "Office Hours".to_owned(),
+      start_time,
+      start_time + 60_000,
+      true,
+    )
+    .await
+    .unwrap();
+    commitment_service::add(con, 1, 2, session.session_id, true)
+      .await
+      .unwrap();
+    session_ids.push(session.session_id);
+  }
+
+  let report = handlers::closure_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "schoolId": school_id,
+      "name": "Snow Day",
+      "startTime": now,
+      "endTime": now + day,
+    })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(report.cancelled_session_data.len(), 1);
+  assert_eq!(report.cancelled_commitments.len(), 1);
+
+  let con = &mut db.con;
+  assert!(
+    !session_data_service::get_by_session_id(con, session_ids[0])
+      .await
+      .unwrap()
+      .unwrap()
+      .active
+  );
+  assert!(
+    session_data_service::get_by_session_id(con, session_ids[1])
+      .await
+      .unwrap()
+      .unwrap()
+      .active
+  );
+  assert!(
+    commitment_service::get_active_by_session_id(con, session_ids[0])
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  // and nothing new can go there
+  let result = handlers::session_new(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "name": "Office Hours",
+      "startTime": now + 120_000,
+      "endTime": now + 180_000,
+      "attendeeUserIds": [],
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::SchoolClosed)));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn session_cancel_reschedule() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+  session_data_service::add(
+    con,
+    1,
+    session.session_id,
+    "Office Hours".to_owned(),
+    now + 60_000,
+    now + 120_000,
+    true,
+  )
+  .await
+  .unwrap();
+  commitment_service::add(con, 1, 2, session.session_id, true)
+    .await
+    .unwrap();
+
+  let edit = |start_time: i64, active: bool| {
+    props::<request::SessionDataNewProps>(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "name": "Study Hall",
+      "startTime": start_time,
+      "endTime": start_time + 60_000,
+      "active": active,
+    }))
+  };
+
+  // editing the data can't quietly move or cancel the session
+  let result = handlers::session_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    edit(now + 180_000, true),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::SessionDataCannotMove)
+  ));
+
+  let result = handlers::session_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    edit(now + 60_000, false),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::SessionDataCannotCancel)
+  ));
+
+  let renamed = handlers::session_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    edit(now + 60_000, true),
+  )
+  .await
+  .unwrap();
+  assert_eq!(renamed.name, "Study Hall");
+
+  // only those who run the course's sessions may move them
+  let result = handlers::session_reschedule(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "sessionId": session.session_id,
+      "startTime": now + 180_000,
+      "endTime": now + 240_000,
+      "reason": "",
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let rescheduled = handlers::session_reschedule(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "startTime": now + 180_000,
+      "endTime": now + 240_000,
+      "reason": "running late",
+    })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(rescheduled.data.session_change.old_start_time, now + 60_000);
+  assert_eq!(rescheduled.data.session_data.start_time, now + 180_000);
+  assert_eq!(rescheduled.data.commitments.len(), 1);
+
+  // the student comes along
+  let con = &mut db.con;
+  assert_eq!(
+    commitment_service::get_active_by_session_id(con, session.session_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let cancelled = handlers::session_cancel(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "reason": "instructor is sick",
+    })),
+  )
+  .await
+  .unwrap();
+  assert!(!cancelled.session_data.active);
+  assert_eq!(cancelled.commitments.len(), 1);
+  assert_eq!(cancelled.session_change.reason, "instructor is sick");
+
+  let con = &mut db.con;
+  assert!(
+    commitment_service::get_active_by_session_id(con, session.session_id)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  // a cancelled session stays cancelled
+  let result = handlers::session_reschedule(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "startTime": now + 180_000,
+      "endTime": now + 240_000,
+      "reason": "",
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::SessionCancelled)));
+
+  // the student can see both changes
+  let changes = handlers::session_change_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "student", "sessionId": [session.session_id] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(changes.len(), 2);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn free_time_view() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let day = 24 * 60 * 60 * 1000;
+
+  // students can't go through everyone's calendars
+  let result = handlers::free_time_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "courseId": course_id,
+      "attendeeUserIds": [2],
+      "minTime": now,
+      "maxTime": now + day,
+      "slotDuration": 30 * 60 * 1000,
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  // nor can anyone ask about a whole year at once
+  let result = handlers::free_time_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "attendeeUserIds": [2],
+      "minTime": now,
+      "maxTime": now + 365 * day,
+      "slotDuration": 30 * 60 * 1000,
+    })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::FreeTimeRangeInvalid)
+  ));
+
+  // slots have to be long enough to be worth scheduling
+  let result = handlers::free_time_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "attendeeUserIds": [2],
+      "minTime": now,
+      "maxTime": now + day,
+      "slotDuration": 1,
+    })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::FreeTimeRangeInvalid)
+  ));
+
+  // and there can't be too many of them
+  let result = handlers::free_time_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "attendeeUserIds": [2],
+      "minTime": now,
+      "maxTime": now + 31 * day,
+      "slotDuration": 5 * 60 * 1000,
+    })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::FreeTimeRangeInvalid)
+  ));
+
+  // a school without any durations has no free time
+  let slots = handlers::free_time_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "attendeeUserIds": [2],
+      "minTime": now,
+      "maxTime": now + day,
+      "slotDuration": 30 * 60 * 1000,
+    })),
+  )
+  .await
+  .unwrap();
+  assert!(slots.is_empty());
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn session_note_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+
+  // students can't write notes
+  let result = handlers::session_note_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "sessionId": session.session_id,
+      "sessionNoteKind": "AGENDA",
+      "sessionNoteVisibility": "ATTENDEES",
+      "content": "",
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let agenda = handlers::session_note_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "sessionNoteKind": "AGENDA",
+      "sessionNoteVisibility": "ATTENDEES",
+      "content": "chapter 3 review",
+    })),
+  )
+  .await
+  .unwrap();
+
+  handlers::session_note_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "sessionNoteKind": "SUMMARY",
+      "sessionNoteVisibility": "INSTRUCTORS",
+      "content": "nobody had read chapter 3",
+    })),
+  )
+  .await
+  .unwrap();
+
+  // the student only sees the shared note, the instructor sees both
+  for (api_key, expected) in [("student", 1), ("admin", 2)] {
+    let found = handlers::session_note_data_view(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      props(json!({ "apiKey": api_key, "onlyRecent": true, "sessionId": [session.session_id] })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  // making the agenda private hides its history too
+  handlers::session_note_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionNoteId": agenda.session_note.session_note_id,
+      "sessionNoteKind": "AGENDA",
+      "sessionNoteVisibility": "INSTRUCTORS",
+      "content": "chapter 3 review",
+      "active": true,
+    })),
+  )
+  .await
+  .unwrap();
+
+  let result = handlers::session_note_data_history(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "sessionNoteId": agenda.session_note.session_note_id })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let history = handlers::session_note_data_history(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "sessionNoteId": agenda.session_note.session_note_id })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(history.len(), 2);
+
+  // notes can't be changed once the course is archived
+  course_data_service::add(
+    &mut db.con,
+    1,
+    course_id,
+    location_id,
+    None,
+    "Course".to_owned(),
+    "".to_owned(),
+    false,
+    false,
+  )
+  .await
+  .unwrap();
+
+  let result = handlers::session_note_data_new(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({
+      "apiKey": "admin",
+      "sessionNoteId": agenda.session_note.session_note_id,
+      "sessionNoteKind": "AGENDA",
+      "sessionNoteVisibility": "ATTENDEES",
+      "content": "chapter 3 review",
+      "active": true,
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::CourseArchived)));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn attachment_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session_request =
+    session_request_service::add(con, 2, course_id, "help".to_owned(), now, now + 60_000)
+      .await
+      .unwrap();
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+
+  let png = base64_url::encode(b"\x89PNG\r\n\x1a\nnot really an image");
+
+  // the declared type has to match the contents
+  let result = handlers::attachment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "sessionRequestId": session_request.session_request_id,
+      "name": "problem.pdf",
+      "mediaType": "application/pdf",
+      "data": png,
+    })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::AttachmentMediaTypeInvalid)
+  ));
+
+  // the student attaches the same photo twice
+  let mut attachments = vec![];
+  for _ in 0..2 {
+    let attachment = handlers::attachment_new(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      props(json!({
+        "apiKey": "student",
+        "sessionRequestId": session_request.session_request_id,
+        "name": "problem.png",
+        "mediaType": "image/png",
+        "data": png,
+      })),
+    )
+    .await
+    .unwrap();
+    attachments.push(attachment);
+  }
+  assert_eq!(attachments[0].checksum, attachments[1].checksum);
+  if let crate::blob_store::BlobStore::Memory(blobs) = &config.blob_store {
+    assert_eq!(blobs.lock().await.len(), 1);
+  }
+
+  // students don't hand out handouts
+  let result = handlers::attachment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "sessionId": session.session_id,
+      "name": "handout.txt",
+      "mediaType": "text/plain",
+      "data": base64_url::encode(b"hello"),
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  // and nothing was stored for the rejected upload
+  if let crate::blob_store::BlobStore::Memory(blobs) = &config.blob_store {
+    assert_eq!(blobs.lock().await.len(), 1);
+  }
+
+  // text has to actually be text
+  let result = handlers::attachment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "name": "handout.txt",
+      "mediaType": "text/plain",
+      "data": base64_url::encode(b"\xff\xfe"),
+    })),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::AttachmentMediaTypeInvalid)
+  ));
+
+  let handout = handlers::attachment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "name": "handout.txt",
+      "mediaType": "text/plain",
+      "data": base64_url::encode(b"hello"),
+    })),
+  )
+  .await
+  .unwrap();
+
+  // the instructor can see the student's photo
+  let download = handlers::attachment_download(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "attachmentId": attachments[0].attachment_id })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(download.data, png);
+
+  // but the student isn't in the course, so can't see the session's handout
+  let result = handlers::attachment_download(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "attachmentId": handout.attachment_id })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let visible = handlers::attachment_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "student", "courseId": [course_id] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(visible.len(), 2);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn attachment_new_body_limit() {
+  let Some(db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let filter = api::api(config, db.db().await, auth_service);
+
+  // oversized uploads are turned away before the body is read
+  let reply = warp::test::request()
+    .method("POST")
+    .path("/public/attachment/new")
+    .body(vec![b' '; handlers::ATTACHMENT_NEW_BODY_LIMIT as usize + 1])
+    .reply(&filter)
+    .await;
+  assert_eq!(reply.status(), warp::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn meeting_pass_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+  session_data_service::add(
+    con,
+    1,
+    session.session_id,
+    "Office Hours".to_owned(),
+    now,
+    now + 60_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  // only http links may be handed out
+  let result = handlers::meeting_link_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "url": "javascript:alert(1)",
+      "active": true,
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::MeetingLinkInvalid)));
+
+  handlers::meeting_link_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "url": "https://example.com/session",
+      "active": true,
+    })),
+  )
+  .await
+  .unwrap();
+
+  // the student can't see the link itself
+  let links = handlers::meeting_link_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "onlyRecent": true })),
+  )
+  .await
+  .unwrap();
+  assert!(links.is_empty());
+
+  // nor get a pass before being committed to the session
+  let result = handlers::meeting_pass_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "sessionId": session.session_id })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  commitment_service::add(&mut db.con, 1, 2, session.session_id, true)
+    .await
+    .unwrap();
+
+  let meeting_pass = handlers::meeting_pass_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "sessionId": session.session_id })),
+  )
+  .await
+  .unwrap();
+
+  let url = handlers::meeting_pass_redeem(
+    config.clone(),
+    db.db().await,
+    meeting_pass.meeting_pass_token.clone(),
+  )
+  .await
+  .unwrap();
+  assert_eq!(url, "https://example.com/session");
+
+  let result = handlers::meeting_pass_redeem(
+    config.clone(),
+    db.db().await,
+    meeting_pass.meeting_pass_token,
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::MeetingPassExpired)));
+
+  // the instructor can count who showed up
+  let joins = handlers::meeting_join_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "admin", "sessionId": [session.session_id] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(joins.len(), 1);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn feedback_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+      (3, request::CourseMembershipKind::Instructor),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let mut commitment_ids = vec![];
+  for (start_time, end_time) in [
+    (now - 7_200_000, now - 3_600_000),
+    (now + 3_600_000, now + 7_200_000),
+  ] {
+    let session = session_service::add(con, 1, course_id).await.unwrap();
+    session_data_service::add(
+      con,
+      1,
+      session.session_id,
+      "office hours".to_owned(),
+      start_time,
+      end_time,
+      true,
+    )
+    .await
+    .unwrap();
+    let commitment = commitment_service::add(con, 1, 2, session.session_id, true)
+      .await
+      .unwrap();
+    commitment_ids.push(commitment.commitment_id);
+  }
+
+  let new_feedback = json!({
+    "apiKey": "student",
+    "commitmentId": commitment_ids[0],
+    "rating": 5,
+    "comment": "very helpful",
+  });
+
+  let mut results = vec![];
+  for (api_key, commitment_id, rating) in [
+    ("student", commitment_ids[0], 6),
+    // only the attendee can rate
+    ("admin", commitment_ids[0], 5),
+    // not before the session is over
+    ("student", commitment_ids[1], 5),
+  ] {
+    let mut new_feedback = new_feedback.clone();
+    new_feedback["apiKey"] = json!(api_key);
+    new_feedback["commitmentId"] = json!(commitment_id);
+    new_feedback["rating"] = json!(rating);
+    results.push(
+      handlers::feedback_new(
+        config.clone(),
+        db.db().await,
+        auth_service.clone(),
+        props(new_feedback),
+      )
+      .await,
+    );
+  }
+  assert!(matches!(
+    results[0],
+    Err(InnexgoHoursError::FeedbackRatingInvalid)
+  ));
+  assert!(matches!(
+    results[1],
+    Err(InnexgoHoursError::ApiKeyUnauthorized)
+  ));
+  assert!(matches!(
+    results[2],
+    Err(InnexgoHoursError::FeedbackSessionNotEnded)
+  ));
+
+  let feedback_data = handlers::feedback_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(new_feedback.clone()),
+  )
+  .await
+  .unwrap();
+
+  // and only once
+  let result = handlers::feedback_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(new_feedback),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::FeedbackExists)));
+
+  // still inside the window, so it can be edited
+  handlers::feedback_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "feedbackId": feedback_data.feedback.feedback_id,
+      "rating": 3,
+      "comment": "helpful, but ran late",
+      "active": true,
+    })),
+  )
+  .await
+  .unwrap();
+
+  // the feedback itself is only for the student who wrote it
+  for (api_key, expected) in [("student", 1), ("admin", 0)] {
+    let found = handlers::feedback_data_view(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      props(json!({ "apiKey": api_key, "courseId": [course_id] })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  let history = |api_key: &str| {
+    props::<request::FeedbackDataHistoryProps>(json!({
+      "apiKey": api_key,
+      "feedbackId": feedback_data.feedback.feedback_id,
+    }))
+  };
+
+  let result = handlers::feedback_data_history(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    history("admin"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let versions = handlers::feedback_data_history(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    history("student"),
+  )
+  .await
+  .unwrap();
+  assert_eq!(versions.len(), 2);
+  assert_eq!(versions[1].data.rating, 3);
+
+  // students can't see the summaries, the instructor gets one for each instructor
+  for (api_key, expected) in [("student", 0), ("admin", 2)] {
+    let found = handlers::feedback_summary_view(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      props(json!({ "apiKey": api_key, "courseId": [course_id], "anonymize": true })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  let summaries = handlers::feedback_summary_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "courseId": [course_id], "anonymize": true })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(summaries[0].instructor_user_id, 1);
+  assert_eq!(summaries[1].instructor_user_id, 3);
+  assert_eq!(summaries[0].feedback_count, 1);
+  assert_eq!(summaries[0].rating_counts, vec![0, 0, 1, 0, 0]);
+  assert_eq!(summaries[0].comments.len(), 1);
+  assert_eq!(summaries[0].comments[0].attendee_user_id, None);
+  assert_eq!(summaries[0].comments[0].comment, "helpful, but ran late");
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn homeroom() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let mut course_ids = vec![];
+  for homeroom in [true, true, false] {
+    let course_id = new_course(con, 1, school_id, location_id).await;
+    course_data_service::add(
+      con,
+      1,
+      course_id,
+      location_id,
+      None,
+      "Homeroom".to_owned(),
+      "".to_owned(),
+      homeroom,
+      true,
+    )
+    .await
+    .unwrap();
+    enroll(
+      con,
+      course_id,
+      &[(1, request::CourseMembershipKind::Instructor)],
+    )
+    .await;
+    course_ids.push(course_id);
+  }
+  for course_id in [course_ids[0], course_ids[2]] {
+    enroll(
+      con,
+      course_id,
+      &[(2, request::CourseMembershipKind::Student)],
+    )
+    .await;
+  }
+  let now = current_time_millis();
+  let course_key = course_key_service::add(
+    con,
+    "homeroom_key",
+    1,
+    course_ids[1],
+    10,
+    request::CourseMembershipKind::Student,
+    now - 60_000,
+    now + 60_000,
+    None,
+  )
+  .await
+  .unwrap();
+  course_key_data_service::add(con, 1, course_key.course_key_key.clone(), true)
+    .await
+    .unwrap();
+
+  // the student already has a homeroom in this school
+  let result = handlers::course_membership_new_key(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "student", "courseKeyKey": course_key.course_key_key })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::HomeroomExists)));
+
+  // and the plain course can't become one either
+  let result = handlers::course_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_ids[2],
+      "locationId": location_id,
+      "name": "Homeroom",
+      "description": "",
+      "homeroom": true,
+      "active": true,
+    })),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::HomeroomExists)));
+
+  // without a location, attendance is taken in the student's homeroom
+  let encounter = handlers::encounter_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "attendeeUserId": 2 })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(encounter.location_id, location_id);
+
+  // students can't see the report, the homeroom teacher can
+  for (api_key, expected) in [("student", 0), ("admin", 1)] {
+    let found = handlers::homeroom_attendance_view(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      props(json!({ "apiKey": api_key, "schoolId": school_id, "dayStartTime": now - 60_000 })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  let found = handlers::homeroom_attendance_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "schoolId": school_id, "dayStartTime": now - 60_000 })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(found[0].course_id, course_ids[0]);
+  assert_eq!(found[0].student_user_id, 2);
+  assert_eq!(found[0].arrival_time, Some(encounter.creation_time));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn irregularity_view() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+  let commitment = commitment_service::add(con, 1, 2, session.session_id, true)
+    .await
+    .unwrap();
+  irregularity_service::add(
+    con,
+    1,
+    commitment.commitment_id,
+    request::IrregularityKind::Absent,
+    None,
+    None,
+    true,
+  )
+  .await
+  .unwrap();
+
+  // until the student approves, the guardian sees nothing
+  guardianship_service::add(con, 3, 3, 2, request::GuardianshipKind::Requested, false)
+    .await
+    .unwrap();
+
+  for (api_key, expected) in [("guardian", 0), ("student", 1), ("admin", 1)] {
+    let found = handlers::irregularity_view(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      props(json!({ "apiKey": api_key, "attendeeUserId": [2] })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  tick().await;
+  guardianship_service::add(
+    &mut db.con,
+    2,
+    3,
+    2,
+    request::GuardianshipKind::Active,
+    false,
+  )
+  .await
+  .unwrap();
+
+  let found = handlers::irregularity_view(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "guardian", "attendeeUserId": [2] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(found.len(), 1);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn term_rollover() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  adminship_service::add(
+    con,
+    1,
+    1,
+    school_id,
+    request::AdminshipKind::Admin,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::TeachingAssistant),
+      (3, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let term = term_service::add(con, 1, school_id).await.unwrap();
+  term_data_service::add(
+    con,
+    1,
+    term.term_id,
+    "Fall".to_owned(),
+    now,
+    now + 60_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  // an existing course is put into the term with a new version of its data
+  tick().await;
+  let course_data = handlers::course_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "locationId": location_id,
+      "termId": term.term_id,
+      "name": "Course",
+      "description": "",
+      "homeroom": false,
+      "active": true,
+    })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(course_data.term.map(|x| x.term_id), Some(term.term_id));
+
+  let rollover = || {
+    props::<request::TermRolloverProps>(json!({
+      "apiKey": "admin",
+      "termId": term.term_id,
+      "name": "Spring",
+      "startTime": now + 60_000,
+      "endTime": now + 120_000,
+    }))
+  };
+
+  tick().await;
+  let new_term_data = handlers::term_rollover(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    rollover(),
+  )
+  .await
+  .unwrap();
+  let new_term_id = new_term_data.term.term_id;
+
+  // rolling the same term over again would copy its courses twice
+  let result = handlers::term_rollover(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    rollover(),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::TermArchived)));
+
+  // the old term and its course are archived
+  let con = &mut db.con;
+  assert!(!term_data_service::is_active_by_term_id(con, term.term_id)
+    .await
+    .unwrap());
+  assert!(!course_data_service::is_active_by_course_id(con, course_id)
+    .await
+    .unwrap());
+
+  // the copy keeps all of its staff but none of its students
+  let new_courses = course_data_service::get_active_by_term_id(con, new_term_id)
+    .await
+    .unwrap();
+  assert_eq!(new_courses.len(), 1);
+  let mut memberships = course_membership_service::get_by_course_id(con, new_courses[0].course_id)
+    .await
+    .unwrap();
+  memberships.sort_by_key(|x| x.user_id);
+  assert_eq!(memberships.len(), 2);
+  assert!(matches!(
+    memberships[0].course_membership_kind,
+    request::CourseMembershipKind::Instructor
+  ));
+  assert!(matches!(
+    memberships[1].course_membership_kind,
+    request::CourseMembershipKind::TeachingAssistant
+  ));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn event_subscribe() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let mut commitments = vec![];
+  for _ in 0..2 {
+    let course_id = new_course(con, 1, school_id, location_id).await;
+    enroll(
+      con,
+      course_id,
+      &[(2, request::CourseMembershipKind::Student)],
+    )
+    .await;
+    let session = session_service::add(con, 1, course_id).await.unwrap();
+    let commitment = commitment_service::add(con, 1, 2, session.session_id, true)
+      .await
+      .unwrap();
+    commitments.push((course_id, commitment));
+  }
+  let (course_id, _) = commitments[0];
+
+  // filters come from the query string, since browsers can't send a body with an EventSource
+  let subscribe_props: request::EventSubscribeProps = api::sse_props(vec![
+    ("apiKey".to_owned(), "student".to_owned()),
+    ("courseId[]".to_owned(), course_id.to_string()),
+  ])
+  .unwrap();
+  assert_eq!(subscribe_props.course_id, Some(vec![course_id]));
+  assert!(subscribe_props.school_id.is_none());
+
+  // a list prop can't also be given as a plain key
+  let result: Result<request::EventSubscribeProps, _> = api::sse_props(vec![
+    ("apiKey".to_owned(), "student".to_owned()),
+    ("courseId".to_owned(), course_id.to_string()),
+  ]);
+  assert!(matches!(result, Err(InnexgoHoursError::DecodeError)));
+
+  event_dispatcher::spawn(config.clone(), db.db().await);
+  let stream = handlers::event_subscribe(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    subscribe_props,
+  )
+  .await
+  .unwrap();
+  let mut stream = Box::pin(stream);
+
+  // only the commitment in the filtered course comes through
+  for (_, commitment) in commitments.iter().rev() {
+    config
+      .events
+      .send(events::Event::Commitment(commitment.clone()))
+      .unwrap();
+  }
+  let timeout = std::time::Duration::from_millis(500);
+  assert!(tokio::time::timeout(timeout, stream.next())
+    .await
+    .unwrap()
+    .is_some());
+  assert!(tokio::time::timeout(timeout, stream.next()).await.is_err());
+
+  // subscribers that fall behind are told to resync instead of silently missing events,
+  // even though none of these events were meant for them
+  for _ in 0..2048 {
+    let _ = config
+      .dispatched_events
+      .send(std::sync::Arc::new(events::Dispatched::Visible {
+        school_id,
+        course_id: None,
+        location_id: None,
+        audience: Default::default(),
+        name: "encounter",
+        data: json!({}),
+      }));
+  }
+  assert!(tokio::time::timeout(timeout, stream.next())
+    .await
+    .unwrap()
+    .is_some());
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn session_new_events() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  event_dispatcher::spawn(config.clone(), db.db().await);
+  let stream = handlers::event_subscribe(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    api::sse_props(vec![("apiKey".to_owned(), "student".to_owned())]).unwrap(),
+  )
+  .await
+  .unwrap();
+  let mut stream = Box::pin(stream);
+
+  let now = current_time_millis();
+  handlers::session_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "courseId": course_id,
+      "name": "Office Hours",
+      "startTime": now + 60_000,
+      "endTime": now + 120_000,
+      "attendeeUserIds": [2],
+      "conflictMode": "STRICT",
+    })),
+  )
+  .await
+  .unwrap();
+
+  // the student hears about the commitment made for them along with the session
+  let timeout = std::time::Duration::from_millis(500);
+  assert!(tokio::time::timeout(timeout, stream.next())
+    .await
+    .unwrap()
+    .is_some());
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn encounter_new_batch() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(1, request::CourseMembershipKind::Instructor)],
+  )
+  .await;
+
+  // one scan from too long ago mustn't hold back the others
+  let now = current_time_millis();
+  let results = handlers::encounter_new_batch(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "admin",
+      "encounters": [
+        { "locationId": location_id, "attendeeUserId": 2, "scanTime": now, "idempotencyKey": "a" },
+        { "locationId": location_id, "attendeeUserId": 2, "scanTime": 0, "idempotencyKey": "b" },
+        { "locationId": location_id, "attendeeUserId": 2, "scanTime": now, "idempotencyKey": "a" },
+      ],
+    })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(results.len(), 3);
+  assert!(matches!(
+    results[0].encounter_new_batch_result_kind,
+    response::EncounterNewBatchResultKind::Accepted
+  ));
+  assert!(matches!(
+    results[1].encounter_new_batch_result_kind,
+    response::EncounterNewBatchResultKind::Rejected
+  ));
+  assert!(matches!(
+    results[1].error,
+    Some(InnexgoHoursError::EncounterScanTimeInvalid)
+  ));
+  assert!(results[1].encounter.is_none());
+  assert!(matches!(
+    results[2].encounter_new_batch_result_kind,
+    response::EncounterNewBatchResultKind::Duplicate
+  ));
+  assert_eq!(
+    results[0].encounter.as_ref().map(|x| x.encounter_id),
+    results[2].encounter.as_ref().map(|x| x.encounter_id)
+  );
+
+  // the accepted scan was committed, the rejected one left nothing behind
+  let con = &mut db.con;
+  assert!(
+    encounter_service::get_by_creator_user_id_idempotency_key(con, 1, "a")
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(
+    encounter_service::get_by_creator_user_id_idempotency_key(con, 1, "b")
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn encounter_new_checkin() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[(2, request::CourseMembershipKind::Student)],
+  )
+  .await;
+  location_checkin_service::add(con, 1, location_id, "secret".to_owned(), true)
+    .await
+    .unwrap();
+
+  let code = utils::gen_checkin_code("secret", current_time_millis());
+  let checkin = || {
+    props::<request::EncounterNewCheckinProps>(json!({
+      "apiKey": "student",
+      "locationId": location_id,
+      "code": code,
+    }))
+  };
+
+  handlers::encounter_new_checkin(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    checkin(),
+  )
+  .await
+  .unwrap();
+
+  // the same code can't sign the student in again while it's still on screen
+  let result = handlers::encounter_new_checkin(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    checkin(),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::CheckinCodeUsed)));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn attendance_sheet_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+  session_data_service::add(
+    con,
+    1,
+    session.session_id,
+    "Class".to_owned(),
+    now - 60_000,
+    now + 60_000,
+    true,
+  )
+  .await
+  .unwrap();
+  commitment_service::add(con, 1, 2, session.session_id, true)
+    .await
+    .unwrap();
+
+  let sheet = |attendance_kind: &str| {
+    props::<request::AttendanceSheetNewProps>(json!({
+      "apiKey": "admin",
+      "sessionId": session.session_id,
+      "entries": [{ "attendeeUserId": 2, "attendanceKind": attendance_kind, "time": null }],
+    }))
+  };
+
+  // submitting the same sheet twice records the student once
+  for _ in 0..2 {
+    handlers::attendance_sheet_new(
+      config.clone(),
+      db.db().await,
+      auth_service.clone(),
+      sheet("PRESENT"),
+    )
+    .await
+    .unwrap();
+    tick().await;
+  }
+
+  let con = &mut db.con;
+  let stays = stay_service::get_by_attendee_user_id_in_range(con, 2, now - 60_000, now + 60_000)
+    .await
+    .unwrap();
+  assert_eq!(stays.len(), 1);
+  assert_eq!(
+    stay_data_service::get_history_by_stay_id(con, stays[0].stay_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert_eq!(
+    occupancy_service::get_by_location_id(con, location_id, now)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  // marking them absent takes the stay back, without their sign in lingering as an occupant
+  let entries = handlers::attendance_sheet_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    sheet("ABSENT"),
+  )
+  .await
+  .unwrap();
+  assert!(matches!(
+    entries[0]
+      .irregularity
+      .as_ref()
+      .map(|x| &x.irregularity_kind),
+    Some(request::IrregularityKind::Absent)
+  ));
+
+  let con = &mut db.con;
+  assert!(
+    !stay_data_service::is_active_by_stay_id(con, stays[0].stay_id)
+      .await
+      .unwrap()
+  );
+  assert!(occupancy_service::get_by_location_id(con, location_id, now)
+    .await
+    .unwrap()
+    .is_empty());
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn school_admin_only() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  subscription_service::add(&mut db.con, 1, request::SubscriptionKind::Valid, 1, 0)
+    .await
+    .unwrap();
+
+  // creating the school makes the admin its first administrator
+  let school_data = handlers::school_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "name": "School", "description": "", "whole": false })),
+  )
+  .await
+  .unwrap();
+  let school_id = school_data.school.school_id;
+
+  let location = |api_key: &str| {
+    props::<request::LocationNewProps>(json!({
+      "apiKey": api_key,
+      "schoolId": school_id,
+      "name": "Room 1",
+      "address": "",
+      "phone": "",
+    }))
+  };
+
+  let result = handlers::location_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    location("student"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  handlers::location_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    location("admin"),
+  )
+  .await
+  .unwrap();
+
+  let now = current_time_millis();
+  let term = |api_key: &str, end_time: i64| {
+    props::<request::TermNewProps>(json!({
+      "apiKey": api_key,
+      "schoolId": school_id,
+      "name": "Fall",
+      "startTime": now,
+      "endTime": end_time,
+    }))
+  };
+
+  let result = handlers::term_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    term("admin", now),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::TermInvalidTimes)));
+
+  let result = handlers::term_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    term("student", now + 60_000),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let term_data = handlers::term_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    term("admin", now + 60_000),
+  )
+  .await
+  .unwrap();
+  assert_eq!(term_data.term.school.school_id, school_id);
+
+  let school_duration = handlers::school_duration_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({ "apiKey": "admin", "schoolId": school_id })),
+  )
+  .await
+  .unwrap();
+
+  let school_duration_data = |minute_start: i64, minute_end: i64| {
+    props::<request::SchoolDurationDataNewProps>(json!({
+      "apiKey": "admin",
+      "schoolDurationId": school_duration.school_duration_id,
+      "day": 1,
+      "minuteStart": minute_start,
+      "minuteEnd": minute_end,
+      "active": true,
+    }))
+  };
+
+  // durations have to fit in a day, counted from midnight utc
+  let result = handlers::school_duration_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    school_duration_data(23 * 60, 25 * 60),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::SchoolDurationInvalidTimes)
+  ));
+
+  handlers::school_duration_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    school_duration_data(13 * 60, 21 * 60),
+  )
+  .await
+  .unwrap();
+
+  let archive = |api_key: &str| {
+    props::<request::SchoolDataNewProps>(json!({
+      "apiKey": api_key,
+      "schoolId": school_id,
+      "name": "School",
+      "description": "",
+      "active": false,
+    }))
+  };
+
+  let result = handlers::school_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    archive("student"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  handlers::school_data_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    archive("admin"),
+  )
+  .await
+  .unwrap();
+
+  // nothing new goes into an archived school
+  let result = handlers::location_new(config, db.db().await, auth_service, location("admin")).await;
+  assert!(matches!(result, Err(InnexgoHoursError::SchoolArchived)));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn guardianship_new() {
+  let Some(db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let request = |api_key: &str, student_user_id: i64| {
+    props::<request::GuardianshipNewRequestProps>(json!({
+      "apiKey": api_key,
+      "studentUserId": student_user_id,
+      "weeklySummary": true,
+    }))
+  };
+
+  let result = handlers::guardianship_new_request(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    request("guardian", 3),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::GuardianshipSelf)));
+
+  let requested = handlers::guardianship_new_request(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    request("guardian", 2),
+  )
+  .await
+  .unwrap();
+  assert!(matches!(
+    requested.guardianship_kind,
+    request::GuardianshipKind::Requested
+  ));
+
+  // a pending request can't be made twice
+  let result = handlers::guardianship_new_request(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    request("guardian", 2),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::GuardianshipExistent)
+  ));
+
+  // only the student the request was made of can approve it
+  let approve = |api_key: &str| {
+    props::<request::GuardianshipNewApproveProps>(json!({
+      "apiKey": api_key,
+      "guardianUserId": 3,
+    }))
+  };
+
+  let result = handlers::guardianship_new_approve(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    approve("admin"),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::GuardianshipNonexistent)
+  ));
+
+  let approved = handlers::guardianship_new_approve(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    approve("student"),
+  )
+  .await
+  .unwrap();
+  assert!(matches!(
+    approved.guardianship_kind,
+    request::GuardianshipKind::Active
+  ));
+  assert!(approved.weekly_summary);
+
+  let cancel = |api_key: &str| {
+    props::<request::GuardianshipNewCancelProps>(json!({
+      "apiKey": api_key,
+      "guardianUserId": 3,
+      "studentUserId": 2,
+    }))
+  };
+
+  // the admin isn't an admin of any school the student attends
+  let result = handlers::guardianship_new_cancel(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    cancel("admin"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let cancelled = handlers::guardianship_new_cancel(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    cancel("guardian"),
+  )
+  .await
+  .unwrap();
+  assert!(matches!(
+    cancelled.guardianship_kind,
+    request::GuardianshipKind::Cancel
+  ));
+
+  // once cancelled there is nothing left to cancel
+  let result =
+    handlers::guardianship_new_cancel(config, db.db().await, auth_service, cancel("student")).await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::GuardianshipNonexistent)
+  ));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn course_membership_new_cancel() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let cancel = |api_key: &str, user_id: i64| {
+    props::<request::CourseMembershipNewCancelProps>(json!({
+      "apiKey": api_key,
+      "courseId": course_id,
+      "userId": user_id,
+    }))
+  };
+
+  // outsiders can't remove anyone
+  let result = handlers::course_membership_new_cancel(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    cancel("guardian", 2),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  // and the last instructor can't leave
+  let result = handlers::course_membership_new_cancel(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    cancel("admin", 1),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::CourseMembershipCannotLeaveEmpty)
+  ));
+
+  // students may leave on their own
+  let membership = handlers::course_membership_new_cancel(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    cancel("student", 2),
+  )
+  .await
+  .unwrap();
+  assert!(matches!(
+    membership.course_membership_kind,
+    request::CourseMembershipKind::Cancel
+  ));
+
+  let members = handlers::course_membership_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "admin", "courseId": [course_id] })),
+  )
+  .await
+  .unwrap();
+  assert!(members.iter().all(|x| x.user_id != 2
+    || matches!(
+      x.course_membership_kind,
+      request::CourseMembershipKind::Cancel
+    )));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn session_request_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+  session_data_service::add(
+    con,
+    1,
+    session.session_id,
+    "Office Hours".to_owned(),
+    now + 60_000,
+    now + 120_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  let ask = |api_key: &str| {
+    props::<request::SessionRequestNewProps>(json!({
+      "apiKey": api_key,
+      "courseId": course_id,
+      "startTime": now + 60_000,
+      "endTime": now + 120_000,
+      "message": "help with homework",
+    }))
+  };
+
+  // only students of the course can ask
+  let result = handlers::session_request_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    ask("guardian"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let session_request = handlers::session_request_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    ask("student"),
+  )
+  .await
+  .unwrap();
+
+  let respond = |api_key: &str| {
+    props::<request::SessionRequestResponseNewProps>(json!({
+      "apiKey": api_key,
+      "sessionRequestId": session_request.session_request_id,
+      "sessionId": session.session_id,
+      "message": "see you there",
+    }))
+  };
+
+  // the student can withdraw the request but not accept it
+  let result = handlers::session_request_response_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    respond("student"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let response = handlers::session_request_response_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    respond("admin"),
+  )
+  .await
+  .unwrap();
+  let commitment = response.commitment.unwrap();
+  assert_eq!(commitment.attendee_user_id, 2);
+  assert_eq!(commitment.session.session_id, session.session_id);
+
+  // a request is only answered once
+  let result = handlers::session_request_response_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    respond("admin"),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::SessionRequestResponseExistent)
+  ));
+
+  let commitments = handlers::commitment_view(
+    config,
+    db.db().await,
+    auth_service,
+    props(json!({ "apiKey": "student", "sessionId": [session.session_id] })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(commitments.len(), 1);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn commitment_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let session = session_service::add(con, 1, course_id).await.unwrap();
+  session_data_service::add(
+    con,
+    1,
+    session.session_id,
+    "Office Hours".to_owned(),
+    now + 60_000,
+    now + 120_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  let commit = |api_key: &str, attendee_user_id: i64| {
+    props::<request::CommitmentNewProps>(json!({
+      "apiKey": api_key,
+      "sessionId": session.session_id,
+      "attendeeUserIds": [attendee_user_id],
+      "conflictMode": "STRICT",
+      "active": true,
+    }))
+  };
+
+  // students can't sign each other up
+  let result = handlers::commitment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    commit("student", 2),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  // and only students of the course can be signed up
+  let result = handlers::commitment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    commit("admin", 3),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::UserNonexistent)));
+
+  let committed = handlers::commitment_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    commit("admin", 2),
+  )
+  .await
+  .unwrap();
+  assert_eq!(committed.data.len(), 1);
+  assert!(committed.data[0].active);
+
+  let con = &mut db.con;
+  assert_eq!(
+    commitment_service::get_active_by_session_id(con, session.session_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn hall_pass_new() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let origin_location_id = new_location(con, 1, school_id).await;
+  let destination_location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, origin_location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let now = current_time_millis();
+  let pass = |api_key: &str, destination_location_id: i64| {
+    props::<request::HallPassNewProps>(json!({
+      "apiKey": api_key,
+      "attendeeUserId": 2,
+      "originLocationId": origin_location_id,
+      "destinationLocationId": destination_location_id,
+      "expectedReturnTime": now + 300_000,
+      "hallPassReasonKind": "RESTROOM",
+    }))
+  };
+
+  let result = handlers::hall_pass_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    pass("admin", origin_location_id),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::HallPassSameLocation)
+  ));
+
+  // students can't write their own passes
+  let result = handlers::hall_pass_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    pass("student", destination_location_id),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let hall_pass = handlers::hall_pass_new(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    pass("admin", destination_location_id),
+  )
+  .await
+  .unwrap();
+  assert_eq!(hall_pass.return_time, None);
+
+  let con = &mut db.con;
+  let occupants =
+    occupancy_service::get_by_location_id(con, destination_location_id, current_time_millis())
+      .await
+      .unwrap();
+  assert_eq!(occupants.len(), 1);
+
+  let return_props = || {
+    props::<request::HallPassNewReturnProps>(json!({
+      "apiKey": "admin",
+      "hallPassId": hall_pass.hall_pass_id,
+    }))
+  };
+
+  tick().await;
+
+  let returned = handlers::hall_pass_new_return(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    return_props(),
+  )
+  .await
+  .unwrap();
+  assert!(returned.return_time.is_some());
+
+  let result =
+    handlers::hall_pass_new_return(config, db.db().await, auth_service, return_props()).await;
+  assert!(matches!(result, Err(InnexgoHoursError::HallPassReturned)));
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn location_checkin_code() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let (config, auth_service) = handler_env(users()).await;
+
+  let con = &mut db.con;
+  let school_id = new_school(con, 1).await;
+  let location_id = new_location(con, 1, school_id).await;
+  let course_id = new_course(con, 1, school_id, location_id).await;
+  enroll(
+    con,
+    course_id,
+    &[
+      (1, request::CourseMembershipKind::Instructor),
+      (2, request::CourseMembershipKind::Student),
+    ],
+  )
+  .await;
+
+  let code = |api_key: &str| {
+    props::<request::LocationCheckinCodeProps>(json!({
+      "apiKey": api_key,
+      "locationId": location_id,
+    }))
+  };
+
+  // students would just sign themselves in
+  let result = handlers::location_checkin_code(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    code("student"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let result = handlers::location_checkin_code(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    code("admin"),
+  )
+  .await;
+  assert!(matches!(
+    result,
+    Err(InnexgoHoursError::LocationCheckinNonexistent)
+  ));
+
+  let con = &mut db.con;
+  location_checkin_service::add(con, 1, location_id, "secret".to_owned(), true)
+    .await
+    .unwrap();
+
+  let checkin_code = handlers::location_checkin_code(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    code("admin"),
+  )
+  .await
+  .unwrap();
+  assert_eq!(checkin_code.expiry_time % utils::CHECKIN_CODE_PERIOD, 0);
+  assert!(
+    utils::verify_checkin_code("secret", &checkin_code.code, current_time_millis()).is_some()
+  );
+
+  // the code works for the student, and shows them in the room
+  handlers::encounter_new_checkin(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    props(json!({
+      "apiKey": "student",
+      "locationId": location_id,
+      "code": checkin_code.code,
+    })),
+  )
+  .await
+  .unwrap();
+
+  let view = |api_key: &str| {
+    props::<request::OccupancyViewLocationProps>(json!({
+      "apiKey": api_key,
+      "locationId": location_id,
+    }))
+  };
+
+  let result = handlers::occupancy_view_location(
+    config.clone(),
+    db.db().await,
+    auth_service.clone(),
+    view("student"),
+  )
+  .await;
+  assert!(matches!(result, Err(InnexgoHoursError::ApiKeyUnauthorized)));
+
+  let occupants =
+    handlers::occupancy_view_location(config, db.db().await, auth_service, view("admin"))
+      .await
+      .unwrap();
+  assert_eq!(occupants.len(), 1);
+
+  db.teardown().await;
+}
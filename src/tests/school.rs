@@ -0,0 +1,565 @@
+use super::{new_school, props, tick, TestDb};
+use crate::adminship_service;
+use crate::closure_data_service;
+use crate::closure_service;
+use crate::school_data_service;
+use crate::school_duration_data_service;
+use crate::school_duration_service;
+use crate::school_invitation_service;
+use crate::school_key_data_service;
+use crate::school_key_service;
+use crate::school_service;
+use crate::subscription_service;
+use crate::term_data_service;
+use crate::term_service;
+use crate::utils::current_time_millis;
+use innexgo_hours_api::request;
+use serde_json::json;
+
+#[tokio::test]
+async fn subscription() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let subscription = subscription_service::add(con, 1, request::SubscriptionKind::Valid, 1, 0)
+    .await
+    .unwrap();
+
+  let by_id = subscription_service::get_by_subscription_id(con, subscription.subscription_id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(by_id.subscription_id, subscription.subscription_id);
+  assert_eq!(by_id.creation_time, subscription.creation_time);
+  assert_eq!(by_id.creator_user_id, 1);
+
+  assert!(subscription_service::get_by_user_id(con, 1)
+    .await
+    .unwrap()
+    .is_some());
+
+  for only_recent in [true, false] {
+    let found = subscription_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn school() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+
+  assert!(school_service::get_by_school_id(con, school_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    school_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  // make a second version so history and as_of have something to tell apart
+  let first = school_data_service::get_by_school_id(con, school_id)
+    .await
+    .unwrap()
+    .unwrap();
+  tick().await;
+  let second =
+    school_data_service::add(con, 1, school_id, "Renamed".to_owned(), "".to_owned(), true)
+      .await
+      .unwrap();
+
+  assert!(
+    school_data_service::get_by_school_data_id(con, &first.school_data_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert_eq!(
+    school_data_service::get_by_school_id(con, school_id)
+      .await
+      .unwrap()
+      .unwrap()
+      .school_data_id,
+    second.school_data_id
+  );
+  assert!(school_data_service::is_active_by_school_id(con, school_id)
+    .await
+    .unwrap());
+
+  let history = school_data_service::get_history_by_school_id(con, school_id)
+    .await
+    .unwrap();
+  assert_eq!(
+    history.iter().map(|x| x.school_data_id).collect::<Vec<_>>(),
+    vec![first.school_data_id, second.school_data_id]
+  );
+
+  let recent = school_data_service::query(con, props(json!({ "onlyRecent": true })))
+    .await
+    .unwrap();
+  assert_eq!(recent.len(), 1);
+
+  let all = school_data_service::query(con, props(json!({ "onlyRecent": false })))
+    .await
+    .unwrap();
+  assert_eq!(all.len(), 2);
+
+  let as_of = school_data_service::query(
+    con,
+    props(json!({ "onlyRecent": true, "asOf": first.creation_time })),
+  )
+  .await
+  .unwrap();
+  assert_eq!(as_of.len(), 1);
+  assert_eq!(as_of[0].school_data_id, first.school_data_id);
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn school_duration() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+
+  let school_duration = school_duration_service::add(con, 1, school_id)
+    .await
+    .unwrap();
+  assert!(school_duration_service::get_by_school_duration_id(
+    con,
+    school_duration.school_duration_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert_eq!(
+    school_duration_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let school_duration_data = school_duration_data_service::add(
+    con,
+    1,
+    school_duration.school_duration_id,
+    1,
+    8 * 60,
+    9 * 60,
+    true,
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    school_duration_data_service::get_by_school_duration_data_id(
+      con,
+      &school_duration_data.school_duration_data_id
+    )
+    .await
+    .unwrap()
+    .is_some()
+  );
+  assert!(school_duration_data_service::get_by_school_duration_id(
+    con,
+    school_duration.school_duration_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert_eq!(
+    school_duration_data_service::get_history_by_school_duration_id(
+      con,
+      school_duration.school_duration_id
+    )
+    .await
+    .unwrap()
+    .len(),
+    1
+  );
+
+  for only_recent in [true, false] {
+    let found =
+      school_duration_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn school_key() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+  let now = current_time_millis();
+
+  let school_key = school_key_service::add(
+    con,
+    "school_key",
+    1,
+    school_id,
+    now,
+    now + 60_000,
+    Some("ABCDEF".to_owned()),
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    school_key_service::get_by_school_key_key(con, &school_key.school_key_key)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(school_key_service::get_by_short_code(con, "ABCDEF")
+    .await
+    .unwrap()
+    .is_some());
+  assert!(school_key_service::get_by_key_or_short_code(con, "abcdef")
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    school_key_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let school_key_data =
+    school_key_data_service::add(con, 1, school_key.school_key_key.clone(), true)
+      .await
+      .unwrap();
+
+  assert!(school_key_data_service::get_by_school_key_data_id(
+    con,
+    school_key_data.school_key_data_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert!(
+    school_key_data_service::get_by_school_key_key(con, &school_key.school_key_key)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(
+    school_key_data_service::is_active_by_school_key_key(con, &school_key.school_key_key)
+      .await
+      .unwrap()
+  );
+  assert_eq!(
+    school_key_data_service::get_history_by_school_key_key(con, &school_key.school_key_key)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for only_recent in [true, false] {
+    let found = school_key_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  // joining through a key counts as a use
+  adminship_service::add(
+    con,
+    2,
+    2,
+    school_id,
+    request::AdminshipKind::Admin,
+    Some(school_key.school_key_key.clone()),
+    None,
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    adminship_service::count_school_key_uses(con, &school_key.school_key_key)
+      .await
+      .unwrap(),
+    1
+  );
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn adminship() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+
+  let school_invitation = school_invitation_service::add(
+    con,
+    1,
+    school_id,
+    "admin@example.com".to_owned(),
+    current_time_millis() + 60_000,
+  )
+  .await
+  .unwrap();
+
+  assert!(school_invitation_service::get_by_school_invitation_id(
+    con,
+    school_invitation.school_invitation_id
+  )
+  .await
+  .unwrap()
+  .is_some());
+  assert_eq!(
+    school_invitation_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let adminship = adminship_service::add(
+    con,
+    1,
+    1,
+    school_id,
+    request::AdminshipKind::Admin,
+    None,
+    Some(school_invitation.school_invitation_id),
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    adminship_service::get_by_adminship_id(con, adminship.adminship_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(
+    adminship_service::get_by_user_id_school_id(con, 1, school_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert!(adminship_service::is_admin(con, 1, school_id)
+    .await
+    .unwrap());
+  assert_eq!(
+    adminship_service::get_by_user_id(con, 1)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert_eq!(
+    adminship_service::count_valid_adminships_by_user_id(con, 1)
+      .await
+      .unwrap(),
+    1
+  );
+  assert_eq!(
+    adminship_service::count_school_invitation_uses(con, school_invitation.school_invitation_id)
+      .await
+      .unwrap(),
+    1
+  );
+  assert_eq!(
+    adminship_service::get_by_school_id(con, school_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert_eq!(
+    adminship_service::count_admins(con, school_id)
+      .await
+      .unwrap(),
+    1
+  );
+
+  tick().await;
+  adminship_service::add(
+    con,
+    1,
+    1,
+    school_id,
+    request::AdminshipKind::Cancel,
+    None,
+    None,
+  )
+  .await
+  .unwrap();
+
+  assert!(!adminship_service::is_admin(con, 1, school_id)
+    .await
+    .unwrap());
+
+  for (only_recent, expected) in [(true, 1), (false, 2)] {
+    let found = adminship_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn term() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+
+  let term = term_service::add(con, 1, school_id).await.unwrap();
+  assert!(term_service::get_by_term_id(con, term.term_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    term_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let now = current_time_millis();
+  term_data_service::add(
+    con,
+    1,
+    term.term_id,
+    "Fall".to_owned(),
+    now,
+    now + 60_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  assert!(term_data_service::get_by_term_id(con, term.term_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert!(term_data_service::is_active_by_term_id(con, term.term_id)
+    .await
+    .unwrap());
+  assert_eq!(
+    term_data_service::get_history_by_term_id(con, term.term_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for only_recent in [true, false] {
+    let found = term_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  db.teardown().await;
+}
+
+#[tokio::test]
+async fn closure() {
+  let Some(mut db) = TestDb::new().await else {
+    return;
+  };
+  let con = &mut db.con;
+
+  let school_id = new_school(con, 1).await;
+
+  let closure = closure_service::add(con, 1, school_id).await.unwrap();
+  assert!(closure_service::get_by_closure_id(con, closure.closure_id)
+    .await
+    .unwrap()
+    .is_some());
+  assert_eq!(
+    closure_service::query(con, props(json!({})))
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let now = current_time_millis();
+  closure_data_service::add(
+    con,
+    1,
+    closure.closure_id,
+    "Snow Day".to_owned(),
+    now,
+    now + 60_000,
+    true,
+  )
+  .await
+  .unwrap();
+
+  assert!(
+    closure_data_service::get_by_closure_id(con, closure.closure_id)
+      .await
+      .unwrap()
+      .is_some()
+  );
+  assert_eq!(
+    closure_data_service::get_history_by_closure_id(con, closure.closure_id)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  for (start_time, expected) in [(now - 60_000, 1), (now + 30_000, 1), (now + 60_000, 0)] {
+    let found = closure_data_service::get_active_by_school_id_in_range(
+      con,
+      school_id,
+      start_time,
+      start_time + 60_000,
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), expected);
+  }
+
+  for only_recent in [true, false] {
+    let found = closure_data_service::query(con, props(json!({ "onlyRecent": only_recent })))
+      .await
+      .unwrap();
+    assert_eq!(found.len(), 1);
+  }
+
+  db.teardown().await;
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use hmac::{Hmac, Mac};
 use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
 use std::convert::TryFrom;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -16,6 +18,112 @@ pub fn gen_random_string() -> String {
   base64_url::encode(&thread_rng().gen::<[u8; 32]>())
 }
 
+// letters and digits that can't be mistaken for each other when read off a projector
+static SHORT_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+pub fn gen_short_code() -> String {
+  let mut rng = thread_rng();
+  (0..8)
+    .map(|_| SHORT_CODE_ALPHABET[rng.gen_range(0..SHORT_CODE_ALPHABET.len())] as char)
+    .collect()
+}
+
+// short codes are typed by hand, so be lenient about case and stray whitespace
+pub fn normalize_short_code(code: &str) -> String {
+  code.trim().to_uppercase()
+}
+
+// for putting user supplied text into html, such as the body of an email
+pub fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+// names a blob by its contents, url safe so it can double as a file name
+pub fn checksum(data: &[u8]) -> String {
+  base64_url::encode(&Sha256::digest(data))
+}
+
+pub fn render_qr_svg(data: &str) -> Result<String, qrcode::types::QrError> {
+  let code = qrcode::QrCode::new(data.as_bytes())?;
+  Ok(
+    code
+      .render::<qrcode::render::svg::Color>()
+      .min_dimensions(256, 256)
+      .build(),
+  )
+}
+
+// selects every version in a versioned table, or with only_recent the newest version of each group
+// the query parameter numbered as_of_param may hold a time, and then versions made after it are ignored,
+// so only_recent gives what the recent_*_v view would have shown back then
+pub fn select_versions(
+  table: &str,
+  alias: &str,
+  id: &str,
+  group_by: &str,
+  only_recent: bool,
+  as_of_param: usize,
+) -> String {
+  if only_recent {
+    format!(
+      "SELECT {alias}.* FROM {table} {alias}
+       INNER JOIN (
+         SELECT max({id}) id
+         FROM {table}
+         WHERE (${as_of_param}::bigint IS NULL OR creation_time <= ${as_of_param})
+         GROUP BY {group_by}
+       ) maxids
+       ON maxids.id = {alias}.{id}"
+    )
+  } else {
+    format!("SELECT {alias}.* FROM {table} {alias}")
+  }
+}
+
+// how long a self check in code stays on screen before rotating
+pub static CHECKIN_CODE_PERIOD: i64 = 30 * 1000;
+
+// derives the 6 digit code for the period containing time, in the style of TOTP (RFC 6238)
+pub fn gen_checkin_code(secret: &str, time: i64) -> String {
+  let counter = time.div_euclid(CHECKIN_CODE_PERIOD);
+
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes any key size");
+  mac.update(&counter.to_be_bytes());
+  let digest = mac.finalize().into_bytes();
+
+  // dynamic truncation
+  let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+  let bin = u32::from_be_bytes([
+    digest[offset] & 0x7f,
+    digest[offset + 1],
+    digest[offset + 2],
+    digest[offset + 3],
+  ]);
+
+  format!("{:06}", bin % 1_000_000)
+}
+
+// accepts the current code and the one just before it, since students need a moment to type it in
+// returns the period of the code that matched, if one did
+pub fn verify_checkin_code(secret: &str, code: &str, time: i64) -> Option<i64> {
+  let code = code.trim();
+  [time, time - CHECKIN_CODE_PERIOD]
+    .into_iter()
+    .find(|&t| gen_checkin_code(secret, t) == code)
+    .map(|t| t.div_euclid(CHECKIN_CODE_PERIOD))
+}
+
 // fun error handling stuff
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]